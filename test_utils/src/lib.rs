@@ -5,4 +5,4 @@ mod jvm;
 mod platform;
 mod runtime;
 
-pub use self::{jvm::test_jvm, platform::TestPlatform};
+pub use self::{jvm::test_jvm, platform::test_platform};
@@ -1,3 +1,7 @@
+use crate::mixer::Mixer;
+
+// Platform audio output. The sink pulls mixed samples from the mixer at its own pace, and has to keep doing so while it's alive.
+// A sink that can't output anything has to call `Mixer::discard` instead, so queued samples don't pile up.
 pub trait AudioSink {
-    fn play_wave(&self, channel: u8, sampling_rate: u32, wave_data: &[i16]);
+    fn attach(&self, mixer: Mixer);
 }
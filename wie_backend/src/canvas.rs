@@ -1,13 +1,21 @@
+mod decode;
+mod encode;
+mod font;
+mod shape;
+mod transform;
+
+use alloc::vec::Vec;
 use core::mem::size_of;
 
-use ab_glyph::{Font, FontRef, ScaleFont};
-use bytemuck::{cast_slice, pod_collect_to_vec, Pod};
-use image::io::Reader as ImageReader;
+use bytemuck::{cast_slice, Pod};
 use num_traits::{Num, Zero};
 
-lazy_static::lazy_static! {
-    static ref FONT: FontRef<'static> = FontRef::try_from_slice(include_bytes!("../../fonts/neodgm.ttf")).unwrap();
-}
+pub use self::{
+    decode::{decode_image, decode_image_frames, ImageFrame, ImageFrameCache},
+    encode::{encode_image, ImageFormat},
+    font::{Font, FontFace, FontSize, FontStyle},
+    transform::{Anchor, Transform},
+};
 
 pub enum TextAlignment {
     Left,
@@ -28,6 +36,8 @@ pub trait Image {
     fn height(&self) -> u32;
     fn bytes_per_pixel(&self) -> u32;
     fn get_pixel(&self, x: u32, y: u32) -> Color;
+    // every pixel has full alpha, so it can be copied without blending
+    fn is_opaque(&self) -> bool;
     fn raw(&self) -> &[u8];
     fn colors(&self) -> Vec<Color>;
 }
@@ -37,19 +47,102 @@ pub trait ImageBuffer {
     fn put_pixels(&mut self, x: u32, y: u32, width: u32, colors: &[Color]);
 }
 
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x.saturating_add(self.width)
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y.saturating_add(self.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        Rect::new(x, y, right.saturating_sub(x).max(0), bottom.saturating_sub(y).max(0))
+    }
+
+    // smallest rect containing both, empty rects are ignored
+    pub fn union(&self, other: &Rect) -> Rect {
+        if other.is_empty() {
+            return *self;
+        }
+        if self.is_empty() {
+            return *other;
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+
+        Rect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+
+    pub fn offset(&self, dx: i32, dy: i32) -> Rect {
+        Rect::new(self.x.saturating_add(dx), self.y.saturating_add(dy), self.width, self.height)
+    }
+}
+
+// Coordinates are relative to the current translation, and nothing is drawn outside of the clip.
 pub trait Canvas {
     fn image(&self) -> &dyn Image;
+    fn translate(&mut self, x: i32, y: i32);
+    fn translation(&self) -> (i32, i32);
+    fn set_clip(&mut self, x: i32, y: i32, w: i32, h: i32);
+    fn clip_rect(&mut self, x: i32, y: i32, w: i32, h: i32);
+    fn clip(&self) -> Rect;
     #[allow(clippy::too_many_arguments)]
-    fn draw(&mut self, dx: u32, dy: u32, w: u32, h: u32, src: &dyn Image, sx: u32, sy: u32);
-    fn draw_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Color);
-    fn draw_text(&mut self, string: &str, x: u32, y: u32, text_alignment: TextAlignment);
-    fn draw_rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: Color);
-    fn fill_rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: Color);
-    fn put_pixel(&mut self, x: u32, y: u32, color: Color);
+    fn draw(&mut self, dx: i32, dy: i32, w: i32, h: i32, src: &dyn Image, sx: i32, sy: i32);
+    // draws `w` x `h` region of `src` at (sx, sy) transformed, placed at (dx, dy) by `anchor`
+    #[allow(clippy::too_many_arguments)]
+    fn draw_region(&mut self, src: &dyn Image, sx: i32, sy: i32, w: i32, h: i32, transform: Transform, dx: i32, dy: i32, anchor: Anchor);
+    fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Color);
+    // `y` is the top of the text
+    fn draw_text(&mut self, string: &str, x: i32, y: i32, font: &Font, color: Color, text_alignment: TextAlignment);
+    fn draw_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color);
+    fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color);
+    #[allow(clippy::too_many_arguments)]
+    fn draw_arc(&mut self, x: i32, y: i32, w: i32, h: i32, start_angle: i32, arc_angle: i32, color: Color);
+    #[allow(clippy::too_many_arguments)]
+    fn fill_arc(&mut self, x: i32, y: i32, w: i32, h: i32, start_angle: i32, arc_angle: i32, color: Color);
+    #[allow(clippy::too_many_arguments)]
+    fn draw_round_rect(&mut self, x: i32, y: i32, w: i32, h: i32, arc_width: i32, arc_height: i32, color: Color);
+    #[allow(clippy::too_many_arguments)]
+    fn fill_round_rect(&mut self, x: i32, y: i32, w: i32, h: i32, arc_width: i32, arc_height: i32, color: Color);
+    #[allow(clippy::too_many_arguments)]
+    fn fill_triangle(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32, color: Color);
+    fn draw_polygon(&mut self, points: &[(i32, i32)], color: Color);
+    fn fill_polygon(&mut self, points: &[(i32, i32)], color: Color);
+    fn put_pixel(&mut self, x: i32, y: i32, color: Color);
 }
 
 pub trait PixelType {
     type DataType: Copy + Pod + Num;
+    const OPAQUE: bool;
     fn from_color(color: Color) -> Self::DataType;
     fn to_color(raw: Self::DataType) -> Color;
 }
@@ -58,6 +151,7 @@ pub struct Rgb565Pixel {}
 
 impl PixelType for Rgb565Pixel {
     type DataType = u16;
+    const OPAQUE: bool = true;
 
     fn from_color(color: Color) -> Self::DataType {
         let r = (color.r as u16) >> 3;
@@ -84,6 +178,7 @@ pub struct Rgb8Pixel {}
 
 impl PixelType for Rgb8Pixel {
     type DataType = u32;
+    const OPAQUE: bool = true;
 
     fn from_color(color: Color) -> Self::DataType {
         (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32
@@ -102,6 +197,7 @@ pub struct ArgbPixel {}
 
 impl PixelType for ArgbPixel {
     type DataType = u32;
+    const OPAQUE: bool = false;
 
     fn from_color(color: Color) -> Self::DataType {
         (color.a as u32) << 24 | (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32
@@ -121,6 +217,7 @@ pub struct AbgrPixel {}
 
 impl PixelType for AbgrPixel {
     type DataType = u32;
+    const OPAQUE: bool = false;
 
     fn from_color(color: Color) -> Self::DataType {
         (color.a as u32) << 24 | (color.b as u32) << 16 | (color.g as u32) << 8 | color.r as u32
@@ -184,6 +281,10 @@ where
         T::to_color(raw)
     }
 
+    fn is_opaque(&self) -> bool {
+        T::OPAQUE
+    }
+
     fn raw(&self) -> &[u8] {
         cast_slice(&self.data)
     }
@@ -228,6 +329,8 @@ where
     T: ImageBuffer + Image,
 {
     image_buffer: T,
+    translation: (i32, i32),
+    clip: Rect, // in image coordinates, may extend past the image
 }
 
 impl<T> ImageBufferCanvas<T>
@@ -235,15 +338,38 @@ where
     T: ImageBuffer + Image,
 {
     pub fn new(image_buffer: T) -> Self {
-        Self { image_buffer }
+        let clip = Rect::new(0, 0, image_buffer.width() as _, image_buffer.height() as _);
+
+        Self {
+            image_buffer,
+            translation: (0, 0),
+            clip,
+        }
     }
 
     pub fn into_inner(self) -> T {
         self.image_buffer
     }
 
-    fn blend_pixel(&mut self, x: u32, y: u32, color: Color) {
-        let bg = self.image_buffer.get_pixel(x, y);
+    // part of the image we may draw on, in image coordinates
+    fn drawable_area(&self) -> Rect {
+        let bounds = Rect::new(0, 0, self.image_buffer.width() as _, self.image_buffer.height() as _);
+
+        self.clip.intersect(&bounds)
+    }
+
+    fn put_pixel_in(&mut self, area: &Rect, x: i32, y: i32, color: Color) {
+        if area.contains(x, y) {
+            self.image_buffer.put_pixel(x as _, y as _, color);
+        }
+    }
+
+    fn blend_pixel_in(&mut self, area: &Rect, x: i32, y: i32, color: Color) {
+        if !area.contains(x, y) {
+            return;
+        }
+
+        let bg = self.image_buffer.get_pixel(x as _, y as _);
         let factor = color.a as f32 / 255.0;
 
         let computed_color = Color {
@@ -253,7 +379,26 @@ where
             b: (color.b as f32 * factor + bg.b as f32 * (1.0 - factor)) as u8,
         };
 
-        self.put_pixel(x, y, computed_color);
+        self.image_buffer.put_pixel(x as _, y as _, computed_color);
+    }
+
+    // shape bounds in image coordinates
+    fn translated_rect(&self, x: i32, y: i32, w: i32, h: i32) -> Rect {
+        Rect::new(x, y, w, h).offset(self.translation.0, self.translation.1)
+    }
+
+    fn fill_spans(&mut self, spans: &[shape::Span], color: Color) {
+        for span in spans {
+            for x in span.x..span.x_end {
+                self.image_buffer.put_pixel(x as _, span.y as _, color);
+            }
+        }
+    }
+
+    fn put_pixels(&mut self, area: &Rect, pixels: &[(i32, i32)], color: Color) {
+        for &(x, y) in pixels {
+            self.put_pixel_in(area, x, y, color);
+        }
     }
 }
 
@@ -265,35 +410,94 @@ where
         &self.image_buffer
     }
 
+    fn translate(&mut self, x: i32, y: i32) {
+        self.translation = (self.translation.0.saturating_add(x), self.translation.1.saturating_add(y));
+    }
+
+    fn translation(&self) -> (i32, i32) {
+        self.translation
+    }
+
+    fn set_clip(&mut self, x: i32, y: i32, w: i32, h: i32) {
+        self.clip = Rect::new(x, y, w.max(0), h.max(0)).offset(self.translation.0, self.translation.1);
+    }
+
+    fn clip_rect(&mut self, x: i32, y: i32, w: i32, h: i32) {
+        let rect = Rect::new(x, y, w.max(0), h.max(0)).offset(self.translation.0, self.translation.1);
+
+        self.clip = self.clip.intersect(&rect);
+    }
+
+    fn clip(&self) -> Rect {
+        self.clip.offset(self.translation.0.saturating_neg(), self.translation.1.saturating_neg())
+    }
+
     #[allow(clippy::too_many_arguments)]
-    fn draw(&mut self, dx: u32, dy: u32, w: u32, h: u32, src: &dyn Image, sx: u32, sy: u32) {
-        for y in 0..h {
-            for x in 0..w {
-                if sx + x >= src.width() || sy + y >= src.height() {
-                    continue;
-                }
-                if dx + x >= self.image_buffer.width() || dy + y >= self.image_buffer.height() {
+    fn draw(&mut self, dx: i32, dy: i32, w: i32, h: i32, src: &dyn Image, sx: i32, sy: i32) {
+        self.draw_region(src, sx, sy, w, h, Transform::None, dx, dy, Anchor::TOP_LEFT);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_region(&mut self, src: &dyn Image, sx: i32, sy: i32, w: i32, h: i32, transform: Transform, dx: i32, dy: i32, anchor: Anchor) {
+        if w <= 0 || h <= 0 {
+            return;
+        }
+
+        let (target_width, target_height) = transform.transformed_size(w, h);
+        let (x, y) = anchor.top_left(dx, dy, target_width, target_height);
+
+        let area = self.drawable_area();
+        let target = self.translated_rect(x, y, target_width, target_height);
+        let visible = target.intersect(&area);
+        let source_bounds = Rect::new(0, 0, src.width() as _, src.height() as _);
+        let opaque = src.is_opaque();
+
+        for y in visible.y..visible.bottom() {
+            for x in visible.x..visible.right() {
+                let (offset_x, offset_y) = transform.source_offset(x - target.x, y - target.y, w, h);
+                let (source_x, source_y) = (sx.saturating_add(offset_x), sy.saturating_add(offset_y));
+                if !source_bounds.contains(source_x, source_y) {
                     continue;
                 }
 
-                self.blend_pixel(dx + x, dy + y, src.get_pixel(sx + x, sy + y));
+                let color = src.get_pixel(source_x as _, source_y as _);
+                if opaque {
+                    self.image_buffer.put_pixel(x as _, y as _, color);
+                } else {
+                    self.blend_pixel_in(&area, x, y, color);
+                }
             }
         }
     }
 
-    fn draw_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Color) {
+    fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Color) {
+        let area = self.drawable_area();
+        let (x1, y1) = (x1.saturating_add(self.translation.0), y1.saturating_add(self.translation.1));
+        let (x2, y2) = (x2.saturating_add(self.translation.0), y2.saturating_add(self.translation.1));
+
+        // clip to one pixel around the area first, so iteration is bounded and the excluded end point stays outside
+        let bounds = Rect::new(
+            area.x.saturating_sub(1),
+            area.y.saturating_sub(1),
+            area.width.saturating_add(2),
+            area.height.saturating_add(2),
+        );
+        let Some((x1, y1, x2, y2)) = shape::clip_line(&bounds, x1, y1, x2, y2) else {
+            return;
+        };
+
         // bresenham's line drawing
-        let dx = (x2 as i32 - x1 as i32).abs();
-        let dy = (y2 as i32 - y1 as i32).abs();
-        let sx = if x1 < x2 { 1i32 } else { -1 };
-        let sy = if y1 < y2 { 1i32 } else { -1 };
+        let dx = (x2 as i64 - x1 as i64).abs();
+        let dy = (y2 as i64 - y1 as i64).abs();
+        let sx = if x1 < x2 { 1 } else { -1 };
+        let sy = if y1 < y2 { 1 } else { -1 };
         let mut err = dx - dy;
 
-        let mut x = x1 as i32;
-        let mut y = y1 as i32;
+        let mut x = x1;
+        let mut y = y1;
 
-        while x != x2 as i32 || y != y2 as i32 {
-            self.blend_pixel(x as _, y as _, color);
+        while x != x2 || y != y2 {
+            self.blend_pixel_in(&area, x, y, color);
 
             let e2 = 2 * err;
             if e2 > -dy {
@@ -307,104 +511,182 @@ where
         }
     }
 
-    fn draw_text(&mut self, string: &str, x: u32, y: u32, text_alignment: TextAlignment) {
-        let font = FONT.as_scaled(FONT.pt_to_px_scale(10.0).unwrap());
-
-        let total_width = string.chars().map(|c| font.h_advance(font.scaled_glyph(c).id)).sum::<f32>();
+    fn draw_text(&mut self, string: &str, x: i32, y: i32, font: &Font, color: Color, text_alignment: TextAlignment) {
         let x = match text_alignment {
             TextAlignment::Left => x,
-            TextAlignment::Center => x - (total_width / 2.0) as u32,
-            TextAlignment::Right => x - total_width as u32,
+            TextAlignment::Center => x - font.string_width(string) / 2,
+            TextAlignment::Right => x - font.string_width(string),
         };
+        let (x, y) = (x.saturating_add(self.translation.0), y.saturating_add(self.translation.1));
 
-        let mut position = 0.0;
-        for c in string.chars() {
-            if c.is_control() {
-                continue;
-            }
+        let area = self.drawable_area();
+        font.render(string, |glyph_x, glyph_y, coverage| {
+            let color = Color {
+                a: (coverage.clamp(0.0, 1.0) * color.a as f32) as u8,
+                ..color
+            };
 
-            let glyph = font.scaled_glyph(c);
-            let h_advance = font.h_advance(glyph.id);
-
-            if let Some(outlined_glyph) = font.outline_glyph(glyph) {
-                outlined_glyph.draw(|glyph_x: u32, glyph_y, c| {
-                    self.blend_pixel(
-                        x + (glyph_x as f32 + position) as u32,
-                        y + glyph_y,
-                        Color {
-                            a: (c * 255.0) as u8,
-                            r: 0,
-                            g: 0,
-                            b: 0,
-                        },
-                    )
-                });
-            }
+            self.blend_pixel_in(&area, x + glyph_x, y + glyph_y, color)
+        });
+    }
 
-            position += h_advance;
+    fn draw_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        if w < 0 || h < 0 {
+            return;
+        }
+
+        let rect = self.translated_rect(x, y, w.saturating_add(1), h.saturating_add(1));
+
+        let area = self.drawable_area();
+        let visible = rect.intersect(&area);
+
+        for x in visible.x..visible.right() {
+            self.put_pixel_in(&area, x, rect.y, color);
+            self.put_pixel_in(&area, x, rect.bottom() - 1, color);
+        }
+        for y in visible.y..visible.bottom() {
+            self.put_pixel_in(&area, rect.x, y, color);
+            self.put_pixel_in(&area, rect.right() - 1, y, color);
         }
     }
 
-    fn draw_rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: Color) {
-        for x in x..x + w {
-            if x >= self.image_buffer.width() {
-                continue;
+    fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        let area = self.drawable_area();
+        let target = Rect::new(x, y, w, h).offset(self.translation.0, self.translation.1).intersect(&area);
+
+        for y in target.y..target.bottom() {
+            for x in target.x..target.right() {
+                self.image_buffer.put_pixel(x as _, y as _, color);
             }
-            self.put_pixel(x, y, color);
-            self.put_pixel(x, y + h - 1, color);
         }
-        for y in y..y + h {
-            if y >= self.image_buffer.height() {
-                continue;
-            }
-            self.put_pixel(x, y, color);
-            self.put_pixel(x + w - 1, y, color);
+    }
+
+    fn draw_arc(&mut self, x: i32, y: i32, w: i32, h: i32, start_angle: i32, arc_angle: i32, color: Color) {
+        if w < 0 || h < 0 {
+            return;
         }
+
+        let bounds = self.translated_rect(x, y, w.saturating_add(1), h.saturating_add(1));
+        let ellipse = shape::Ellipse::new(&bounds);
+        let area = self.drawable_area();
+
+        let pixels = shape::outline_region(&bounds, &area, |x, y| ellipse.contains(x, y))
+            .into_iter()
+            .filter(|&(x, y)| shape::in_arc(ellipse.angle(x as f32 + 0.5, y as f32 + 0.5), start_angle, arc_angle))
+            .collect::<Vec<_>>();
+
+        self.put_pixels(&area, &pixels, color);
     }
 
-    fn fill_rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: Color) {
-        for y in y..y + h {
-            for x in x..x + w {
-                if x >= self.image_buffer.width() || y >= self.image_buffer.height() {
-                    continue;
-                }
-                self.put_pixel(x, y, color);
-            }
+    fn fill_arc(&mut self, x: i32, y: i32, w: i32, h: i32, start_angle: i32, arc_angle: i32, color: Color) {
+        let bounds = self.translated_rect(x, y, w, h);
+        if bounds.is_empty() {
+            return;
         }
+
+        let ellipse = shape::Ellipse::new(&bounds);
+        let spans = shape::fill_region(&bounds, &self.drawable_area(), |x, y| {
+            ellipse.contains(x, y) && shape::in_arc(ellipse.angle(x, y), start_angle, arc_angle)
+        });
+
+        self.fill_spans(&spans, color);
     }
 
-    fn put_pixel(&mut self, x: u32, y: u32, color: Color) {
-        self.image_buffer.put_pixel(x, y, color)
+    fn draw_round_rect(&mut self, x: i32, y: i32, w: i32, h: i32, arc_width: i32, arc_height: i32, color: Color) {
+        if w < 0 || h < 0 {
+            return;
+        }
+
+        let bounds = self.translated_rect(x, y, w.saturating_add(1), h.saturating_add(1));
+        let area = self.drawable_area();
+
+        let pixels = shape::outline_region(&bounds, &area, |x, y| shape::round_rect_contains(&bounds, arc_width, arc_height, x, y));
+
+        self.put_pixels(&area, &pixels, color);
+    }
+
+    fn fill_round_rect(&mut self, x: i32, y: i32, w: i32, h: i32, arc_width: i32, arc_height: i32, color: Color) {
+        let bounds = self.translated_rect(x, y, w, h);
+        if bounds.is_empty() {
+            return;
+        }
+
+        let spans = shape::fill_region(&bounds, &self.drawable_area(), |x, y| {
+            shape::round_rect_contains(&bounds, arc_width, arc_height, x, y)
+        });
+
+        self.fill_spans(&spans, color);
     }
-}
 
-pub fn decode_image(data: &[u8]) -> anyhow::Result<Box<dyn Image>> {
-    use std::io::Cursor;
+    fn fill_triangle(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32, color: Color) {
+        self.fill_polygon(&[(x1, y1), (x2, y2), (x3, y3)], color);
+    }
+
+    fn draw_polygon(&mut self, points: &[(i32, i32)], color: Color) {
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+
+            self.draw_line(x1, y1, x2, y2, color);
+        }
+    }
 
-    let image = ImageReader::new(Cursor::new(&data)).with_guessed_format()?.decode()?;
-    let rgba = image.into_rgba8();
+    fn fill_polygon(&mut self, points: &[(i32, i32)], color: Color) {
+        let points = points
+            .iter()
+            .map(|&(x, y)| (x.saturating_add(self.translation.0), y.saturating_add(self.translation.1)))
+            .collect::<Vec<_>>();
 
-    let data = rgba.pixels().flat_map(|x| [x.0[2], x.0[1], x.0[0], x.0[3]]).collect::<Vec<_>>();
+        let spans = shape::fill_polygon(&points, &self.drawable_area());
 
-    Ok(Box::new(VecImageBuffer::<ArgbPixel>::from_raw(
-        rgba.width(),
-        rgba.height(),
-        pod_collect_to_vec(&data),
-    )) as Box<_>)
+        self.fill_spans(&spans, color);
+    }
+
+    fn put_pixel(&mut self, x: i32, y: i32, color: Color) {
+        let area = self.drawable_area();
+
+        self.put_pixel_in(&area, x.saturating_add(self.translation.0), y.saturating_add(self.translation.1), color)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::canvas::{Image, ImageBufferCanvas};
 
-    use super::{ArgbPixel, Canvas, Color, VecImageBuffer};
+    use alloc::{string::String, vec::Vec};
+
+    use super::{Anchor, ArgbPixel, Canvas, Color, Font, Rect, Rgb8Pixel, TextAlignment, Transform, VecImageBuffer};
+
+    const BLACK: Color = Color { r: 0, g: 0, b: 0, a: 255 };
+    const WHITE: Color = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+        a: 255,
+    };
+
+    fn is_white(image: &dyn Image, x: u32, y: u32) -> bool {
+        image.get_pixel(x, y).r == 255
+    }
+
+    // draws on a white canvas and returns the black pixels as `#`
+    fn render(width: u32, height: u32, draw: impl FnOnce(&mut dyn Canvas)) -> Vec<String> {
+        let mut canvas = ImageBufferCanvas::new(VecImageBuffer::<ArgbPixel>::new(width, height));
+        canvas.fill_rect(0, 0, width as _, height as _, WHITE);
+
+        draw(&mut canvas);
+
+        let image = canvas.image();
+        (0..height)
+            .map(|y| (0..width).map(|x| if is_white(image, x, y) { '.' } else { '#' }).collect())
+            .collect()
+    }
 
     #[test]
     fn test_canvas() -> anyhow::Result<()> {
         let image_buffer = VecImageBuffer::<ArgbPixel>::new(10, 10);
         let mut canvas = ImageBufferCanvas::new(image_buffer);
 
-        canvas.fill_rect(0, 0, 10, 10, Color { r: 0, g: 0, b: 0, a: 255 });
+        canvas.fill_rect(0, 0, 10, 10, BLACK);
 
         let image_buffer = canvas.into_inner();
         let raw = image_buffer.raw();
@@ -419,4 +701,356 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_clip_translate() {
+        let mut canvas = ImageBufferCanvas::new(VecImageBuffer::<ArgbPixel>::new(10, 10));
+
+        canvas.translate(2, 3);
+        canvas.set_clip(-1, -1, 4, 4);
+        assert_eq!(canvas.clip(), Rect::new(-1, -1, 4, 4));
+        canvas.clip_rect(0, 0, 10, 10);
+        assert_eq!(canvas.clip(), Rect::new(0, 0, 3, 3));
+
+        canvas.fill_rect(-100, -100, 200, 200, WHITE);
+
+        let image = canvas.image();
+        let white = (0..10)
+            .flat_map(|y| (0..10).map(move |x| (x, y)))
+            .filter(|&(x, y)| is_white(image, x, y))
+            .count();
+        assert_eq!(white, 9);
+        assert!(is_white(image, 2, 3) && is_white(image, 4, 5) && !is_white(image, 5, 5));
+    }
+
+    #[test]
+    fn test_rect_union() {
+        let a = Rect::new(0, 0, 2, 2);
+        let b = Rect::new(5, -1, 1, 1);
+
+        assert_eq!(a.union(&b), Rect::new(0, -1, 6, 3));
+        assert_eq!(a.union(&Rect::default()), a);
+        assert_eq!(Rect::new(3, 3, 0, 5).union(&b), b);
+    }
+
+    #[test]
+    fn test_extreme_coordinates() {
+        let a = Rect::new(i32::MIN, i32::MIN, i32::MAX, i32::MAX);
+        let b = Rect::new(0, 0, i32::MAX, i32::MAX);
+        assert_eq!(a.intersect(&b), Rect::new(0, 0, 0, 0));
+        assert_eq!(a.union(&b), Rect::new(i32::MIN, i32::MIN, i32::MAX, i32::MAX));
+
+        let mut canvas = ImageBufferCanvas::new(VecImageBuffer::<ArgbPixel>::new(10, 10));
+        canvas.fill_rect(0, 0, 10, 10, BLACK);
+
+        // outlines cover one more pixel, which must not overflow
+        canvas.draw_arc(0, 0, i32::MAX, i32::MAX, 0, 360, BLACK);
+        canvas.draw_round_rect(0, 0, i32::MAX, i32::MAX, 4, 4, BLACK);
+
+        // only the visible part of the segment is walked
+        canvas.draw_line(i32::MIN, 4, i32::MAX, 4, WHITE);
+        canvas.translate(i32::MIN, i32::MIN);
+        canvas.set_clip(0, 0, i32::MAX, i32::MAX);
+        canvas.clip_rect(i32::MAX, i32::MAX, 10, 10);
+
+        let image = canvas.image();
+        assert!((0..10).all(|x| is_white(image, x, 4)));
+        assert!(!is_white(image, 0, 3) && !is_white(image, 0, 5));
+    }
+
+    #[test]
+    fn test_negative_coordinates() {
+        let mut source = ImageBufferCanvas::new(VecImageBuffer::<ArgbPixel>::new(4, 4));
+        source.fill_rect(0, 0, 4, 4, WHITE);
+        let source = source.into_inner();
+
+        let mut canvas = ImageBufferCanvas::new(VecImageBuffer::<ArgbPixel>::new(10, 10));
+        canvas.fill_rect(0, 0, 10, 10, BLACK);
+
+        // only the bottom right 2x2 of the source is visible
+        canvas.draw(-2, -2, 4, 4, &source, 0, 0);
+        canvas.draw_line(-5, 9, 3, 9, WHITE);
+        canvas.put_pixel(-1, 0, WHITE);
+        canvas.fill_rect(5, 5, -3, 2, WHITE);
+
+        let image = canvas.image();
+        assert!(is_white(image, 0, 0) && is_white(image, 1, 1) && !is_white(image, 2, 2));
+        assert!(is_white(image, 0, 9) && is_white(image, 2, 9) && !is_white(image, 3, 9));
+        assert!(!is_white(image, 9, 0) && !is_white(image, 4, 5));
+    }
+
+    #[test]
+    fn test_arc() {
+        // outline covers w + 1 x h + 1 pixels
+        assert_eq!(
+            render(12, 12, |c| c.draw_arc(1, 1, 9, 9, 0, 360, BLACK)),
+            [
+                "............",
+                "....####....",
+                "..##....##..",
+                "..#......#..",
+                ".#........#.",
+                ".#........#.",
+                ".#........#.",
+                ".#........#.",
+                "..#......#..",
+                "..##....##..",
+                "....####....",
+                "............",
+            ]
+        );
+
+        // negative arc angle goes clockwise
+        assert_eq!(
+            render(12, 12, |c| c.draw_arc(1, 1, 10, 10, 90, -180, BLACK)),
+            [
+                "............",
+                "......###...",
+                ".........#..",
+                "..........#.",
+                "...........#",
+                "...........#",
+                "...........#",
+                "...........#",
+                "...........#",
+                "..........#.",
+                ".........#..",
+                "......###...",
+            ]
+        );
+
+        // fill covers w x h pixels
+        assert_eq!(
+            render(12, 8, |c| c.fill_arc(1, 1, 10, 6, 0, 90, BLACK)),
+            [
+                "............",
+                "......###...",
+                "......####..",
+                "......#####.",
+                "............",
+                "............",
+                "............",
+                "............",
+            ]
+        );
+
+        // angles are relative to the bounding box, 45 degrees points to the top-right corner
+        assert_eq!(
+            render(12, 8, |c| c.fill_arc(0, 0, 12, 8, 45, 90, BLACK)),
+            [
+                "...######...",
+                "..########..",
+                "....####....",
+                ".....##.....",
+                "............",
+                "............",
+                "............",
+                "............",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rect() {
+        // outline covers w + 1 x h + 1 pixels, fill covers w x h
+        assert_eq!(
+            render(8, 6, |c| c.draw_rect(1, 1, 5, 3, BLACK)),
+            ["........", ".######.", ".#....#.", ".#....#.", ".######.", "........"]
+        );
+        assert_eq!(
+            render(8, 6, |c| c.fill_rect(1, 1, 5, 3, BLACK)),
+            ["........", ".#####..", ".#####..", ".#####..", "........", "........"]
+        );
+        assert_eq!(render(4, 3, |c| c.draw_rect(1, 1, 0, 0, BLACK)), ["....", ".#..", "...."]);
+    }
+
+    #[test]
+    fn test_round_rect() {
+        assert_eq!(
+            render(12, 10, |c| c.draw_round_rect(1, 1, 9, 7, 4, 4, BLACK)),
+            [
+                "............",
+                "..########..",
+                ".#........#.",
+                ".#........#.",
+                ".#........#.",
+                ".#........#.",
+                ".#........#.",
+                ".#........#.",
+                "..########..",
+                "............",
+            ]
+        );
+
+        assert_eq!(
+            render(12, 10, |c| c.fill_round_rect(1, 1, 10, 8, 4, 4, BLACK)),
+            [
+                "............",
+                "..########..",
+                ".##########.",
+                ".##########.",
+                ".##########.",
+                ".##########.",
+                ".##########.",
+                ".##########.",
+                "..########..",
+                "............",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_polygon() {
+        assert_eq!(
+            render(10, 10, |c| c.fill_triangle(1, 1, 9, 1, 1, 9, BLACK)),
+            [
+                "..........",
+                ".#######..",
+                ".######...",
+                ".#####....",
+                ".####.....",
+                ".###......",
+                ".##.......",
+                ".#........",
+                "..........",
+                "..........",
+            ]
+        );
+
+        assert_eq!(
+            render(10, 10, |c| c.fill_polygon(&[(1, 1), (9, 1), (9, 9), (5, 4), (1, 9)], BLACK)),
+            [
+                "..........",
+                ".########.",
+                ".########.",
+                ".########.",
+                ".########.",
+                ".###..###.",
+                ".##....##.",
+                ".#......#.",
+                "..........",
+                "..........",
+            ]
+        );
+
+        assert_eq!(
+            render(10, 10, |c| c.draw_polygon(&[(1, 1), (8, 1), (8, 8), (1, 8)], BLACK)),
+            [
+                "..........",
+                ".########.",
+                ".#......#.",
+                ".#......#.",
+                ".#......#.",
+                ".#......#.",
+                ".#......#.",
+                ".#......#.",
+                ".########.",
+                "..........",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shape_clip_translate() {
+        assert_eq!(
+            render(10, 10, |c| {
+                c.translate(-3, -3);
+                c.clip_rect(3, 3, 6, 10);
+                c.fill_arc(0, 0, 10, 10, 0, 360, BLACK)
+            }),
+            [
+                "######....",
+                "######....",
+                "######....",
+                "######....",
+                "######....",
+                "######....",
+                "####......",
+                "..........",
+                "..........",
+                "..........",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        let font = Font::default();
+        let red = Color { r: 255, g: 0, b: 0, a: 255 };
+
+        let mut canvas = ImageBufferCanvas::new(VecImageBuffer::<ArgbPixel>::new(40, 20));
+        canvas.fill_rect(0, 0, 40, 20, WHITE);
+        canvas.draw_text("AB", 40, 0, &font, red, TextAlignment::Right);
+
+        // right aligned text ends at x, and is drawn in the requested color
+        let image = canvas.image();
+        let drawn = (0..20)
+            .flat_map(|y| (0..40).map(move |x| (x, y)))
+            .filter(|&(x, y)| image.get_pixel(x, y).g != 255)
+            .collect::<Vec<_>>();
+
+        assert!(!drawn.is_empty());
+        assert!(drawn
+            .iter()
+            .all(|&(x, y)| x >= 40 - font.string_width("AB") as u32 && y < font.height() as u32));
+        assert!(drawn.iter().all(|&(x, y)| {
+            let color = image.get_pixel(x, y);
+            color.r > color.g && color.g == color.b
+        }));
+    }
+
+    #[test]
+    fn test_draw_region() {
+        let mut source = ImageBufferCanvas::new(VecImageBuffer::<ArgbPixel>::new(3, 2));
+        source.fill_rect(0, 0, 3, 2, WHITE);
+        source.put_pixel(0, 0, BLACK);
+        source.put_pixel(1, 0, BLACK);
+        let source = source.into_inner();
+
+        let draw = |transform, anchor| render(5, 5, |c| c.draw_region(&source, 0, 0, 3, 2, transform, 1, 1, anchor));
+
+        assert_eq!(draw(Transform::None, Anchor::TOP_LEFT), [".....", ".##..", ".....", ".....", "....."]);
+        assert_eq!(draw(Transform::Mirror, Anchor::TOP_LEFT), [".....", "..##.", ".....", ".....", "....."]);
+        assert_eq!(draw(Transform::Rot90, Anchor::TOP_LEFT), [".....", "..#..", "..#..", ".....", "....."]);
+        assert_eq!(draw(Transform::Rot180, Anchor::TOP_LEFT), [".....", ".....", "..##.", ".....", "....."]);
+        assert_eq!(draw(Transform::Rot270, Anchor::TOP_LEFT), [".....", ".....", ".#...", ".#...", "....."]);
+        assert_eq!(
+            draw(Transform::MirrorRot90, Anchor::TOP_LEFT),
+            [".....", ".....", "..#..", "..#..", "....."]
+        );
+        assert_eq!(
+            draw(Transform::MirrorRot180, Anchor::TOP_LEFT),
+            [".....", ".....", ".##..", ".....", "....."]
+        );
+        assert_eq!(
+            draw(Transform::MirrorRot270, Anchor::TOP_LEFT),
+            [".....", ".#...", ".#...", ".....", "....."]
+        );
+
+        // anchor applies to the transformed size
+        let bottom_right = Anchor::from_bits(Anchor::BOTTOM.bits() | Anchor::RIGHT.bits());
+        assert_eq!(
+            render(5, 5, |c| c.draw_region(&source, 0, 0, 3, 2, Transform::Rot90, 2, 3, bottom_right)),
+            [".#...", ".#...", ".....", ".....", "....."]
+        );
+    }
+
+    #[test]
+    fn test_draw_region_alpha() {
+        let mut source = ImageBufferCanvas::new(VecImageBuffer::<ArgbPixel>::new(2, 1));
+        source.put_pixel(0, 0, BLACK);
+        let source = source.into_inner();
+
+        // transparent pixels of non-opaque images are blended, opaque ones are copied
+        assert_eq!(
+            render(3, 1, |c| c.draw_region(&source, 0, 0, 2, 1, Transform::None, 0, 0, Anchor::TOP_LEFT)),
+            ["#.."]
+        );
+
+        let opaque = VecImageBuffer::<Rgb8Pixel>::new(2, 1);
+        assert_eq!(
+            render(3, 1, |c| c.draw_region(&opaque, 0, 0, 2, 1, Transform::None, 0, 0, Anchor::TOP_LEFT)),
+            ["##."]
+        );
+    }
 }
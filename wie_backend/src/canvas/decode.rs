@@ -0,0 +1,417 @@
+use alloc::{boxed::Box, collections::BTreeMap, rc::Rc, vec, vec::Vec};
+use std::io::Cursor;
+
+use bytemuck::pod_collect_to_vec;
+use image::{codecs::gif::GifDecoder, io::Reader as ImageReader, AnimationDecoder, ImageFormat, RgbaImage};
+
+use super::{ArgbPixel, Image, VecImageBuffer};
+
+// larger sizes are more likely to be some other data than a real wbmp
+const WBMP_MAX_SIZE: u32 = 4096;
+const LBM_MAX_SIZE: u32 = 4096;
+// apps usually animate only a handful of images at once
+const FRAME_CACHE_SIZE: usize = 16;
+
+pub struct ImageFrame {
+    pub image: Box<dyn Image>,
+    pub delay: u32, // in milliseconds
+}
+
+pub fn decode_image(data: &[u8]) -> anyhow::Result<Box<dyn Image>> {
+    let frame = decode_image_frames(data)?.swap_remove(0);
+
+    Ok(frame.image)
+}
+
+// animated images are returned as fully composed frames, still images as a single frame. never empty on success
+pub fn decode_image_frames(data: &[u8]) -> anyhow::Result<Vec<ImageFrame>> {
+    let reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+
+    match reader.format() {
+        Some(ImageFormat::Gif) => {
+            let frames = GifDecoder::new(Cursor::new(data))?.into_frames().collect_frames()?;
+            if frames.is_empty() {
+                anyhow::bail!("Image has no frames");
+            }
+
+            Ok(frames
+                .into_iter()
+                .map(|x| {
+                    let (numerator, denominator) = x.delay().numer_denom_ms();
+
+                    ImageFrame {
+                        delay: numerator / denominator.max(1),
+                        image: from_rgba(x.into_buffer()),
+                    }
+                })
+                .collect())
+        }
+        Some(_) => Ok(vec![ImageFrame {
+            image: from_rgba(reader.decode()?.into_rgba8()),
+            delay: 0,
+        }]),
+        None => Ok(vec![ImageFrame {
+            image: decode_wbmp(data)
+                .or_else(|| decode_lbm(data))
+                .ok_or_else(|| anyhow::anyhow!("Unsupported image format"))?,
+            delay: 0,
+        }]),
+    }
+}
+
+// Decoded frames of animated images keyed by an id of their source, so stepping through frames doesn't decode them again
+pub struct ImageFrameCache {
+    entries: BTreeMap<u64, Rc<Vec<ImageFrame>>>,
+}
+
+impl ImageFrameCache {
+    pub fn new() -> Self {
+        Self { entries: BTreeMap::new() }
+    }
+
+    pub fn get(&self, key: u64) -> Option<Rc<Vec<ImageFrame>>> {
+        self.entries.get(&key).cloned()
+    }
+
+    // evicts an arbitrary entry when full, it is decoded again on next use
+    pub fn insert(&mut self, key: u64, frames: Vec<ImageFrame>) -> Rc<Vec<ImageFrame>> {
+        if !self.entries.contains_key(&key) && self.entries.len() >= FRAME_CACHE_SIZE {
+            self.entries.pop_first();
+        }
+
+        let frames = Rc::new(frames);
+        self.entries.insert(key, frames.clone());
+
+        frames
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Default for ImageFrameCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn from_rgba(rgba: RgbaImage) -> Box<dyn Image> {
+    let data = rgba.pixels().flat_map(|x| [x.0[2], x.0[1], x.0[0], x.0[3]]).collect::<Vec<_>>();
+
+    Box::new(VecImageBuffer::<ArgbPixel>::from_raw(
+        rgba.width(),
+        rgba.height(),
+        pod_collect_to_vec(&data),
+    ))
+}
+
+// type 0 wbmp: type and fixed header, width and height as multi-byte integers, then rows of 1bpp pixels where 1 is white
+fn decode_wbmp(data: &[u8]) -> Option<Box<dyn Image>> {
+    let mut cursor = data.iter();
+
+    let mut read_multibyte = || {
+        let mut result = 0u32;
+        for _ in 0..4 {
+            let byte = *cursor.next()?;
+            result = (result << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+        None
+    };
+
+    let image_type = read_multibyte()?;
+    let fixed_header = read_multibyte()?;
+    let width = read_multibyte()?;
+    let height = read_multibyte()?;
+    if image_type != 0 || fixed_header != 0 || !(1..=WBMP_MAX_SIZE).contains(&width) || !(1..=WBMP_MAX_SIZE).contains(&height) {
+        return None;
+    }
+
+    let pixels = cursor.as_slice();
+    let stride = width.div_ceil(8) as usize;
+    if pixels.len() < stride * height as usize {
+        return None;
+    }
+
+    let data = (0..height as usize)
+        .flat_map(|y| (0..width as usize).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            if pixels[y * stride + x / 8] & (0x80 >> (x % 8)) != 0 {
+                0xffffffffu32
+            } else {
+                0xff000000
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Some(Box::new(VecImageBuffer::<ArgbPixel>::from_raw(width, height, data)))
+}
+
+struct LbmHeader {
+    width: u32,
+    height: u32,
+    planes: u8,
+    masking: u8,
+    compression: u8,
+    transparent: u32,
+}
+
+// IFF ILBM or PBM: BMHD header, optional CMAP palette, then BODY rows of bitplanes (ILBM) or palette indices (PBM),
+// uncompressed or ByteRun1 packed. 24 planes are direct rgb, masking 1 has a mask plane after the color planes
+fn decode_lbm(data: &[u8]) -> Option<Box<dyn Image>> {
+    let read_u32 = |offset: usize| Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().unwrap()));
+
+    if data.get(..4)? != b"FORM" {
+        return None;
+    }
+    let planar = match data.get(8..12)? {
+        b"ILBM" => true,
+        b"PBM " => false,
+        _ => return None,
+    };
+    let form_end = (read_u32(4)? as usize).saturating_add(8).min(data.len());
+
+    let (mut header, mut palette, mut body) = (None, Vec::new(), None);
+    let mut offset = 12;
+    while offset + 8 <= form_end {
+        let size = read_u32(offset + 4)? as usize;
+        let chunk = data.get(offset + 8..(offset + 8).checked_add(size)?)?;
+
+        match &data[offset..offset + 4] {
+            b"BMHD" if chunk.len() >= 20 => {
+                header = Some(LbmHeader {
+                    width: u16::from_be_bytes([chunk[0], chunk[1]]) as _,
+                    height: u16::from_be_bytes([chunk[2], chunk[3]]) as _,
+                    planes: chunk[8],
+                    masking: chunk[9],
+                    compression: chunk[10],
+                    transparent: u16::from_be_bytes([chunk[12], chunk[13]]) as _,
+                })
+            }
+            b"CMAP" => palette = chunk.chunks_exact(3).map(|x| [x[0], x[1], x[2]]).collect(),
+            b"BODY" => body = Some(chunk),
+            _ => {}
+        }
+
+        offset += 8 + size + size % 2;
+    }
+
+    let (header, body) = (header?, body?);
+    if !(1..=LBM_MAX_SIZE).contains(&header.width) || !(1..=LBM_MAX_SIZE).contains(&header.height) {
+        return None;
+    }
+    let supported_planes = if planar {
+        matches!(header.planes, 1..=8 | 24)
+    } else {
+        header.planes == 8
+    };
+    if !supported_planes {
+        return None;
+    }
+
+    let (width, height) = (header.width as usize, header.height as usize);
+    let plane_stride = width.div_ceil(16) * 2;
+    let stride = if planar {
+        plane_stride * (header.planes as usize + (header.masking == 1) as usize)
+    } else {
+        width + width % 2
+    };
+
+    let pixels = match header.compression {
+        0 => body.get(..stride * height)?.to_vec(),
+        1 => unpack_byte_run(body, stride * height)?,
+        _ => return None,
+    };
+
+    let data = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let row = &pixels[y * stride..(y + 1) * stride];
+            let value = if planar {
+                (0..header.planes as usize).fold(0u32, |acc, plane| {
+                    let bit = (row[plane * plane_stride + x / 8] >> (7 - x % 8)) & 1;
+
+                    acc | ((bit as u32) << plane)
+                })
+            } else {
+                row[x] as u32
+            };
+
+            if header.planes == 24 {
+                return 0xff000000 | ((value & 0xff) << 16) | (value & 0xff00) | (value >> 16);
+            }
+
+            let [r, g, b] = palette.get(value as usize).copied().unwrap_or_else(|| {
+                let gray = (value * 255 / ((1u32 << header.planes) - 1)) as u8;
+
+                [gray, gray, gray]
+            });
+            let alpha = if header.masking == 2 && value == header.transparent { 0 } else { 0xff };
+
+            (alpha << 24) | ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+        })
+        .collect::<Vec<_>>();
+
+    Some(Box::new(VecImageBuffer::<ArgbPixel>::from_raw(header.width, header.height, data)))
+}
+
+// packbits: n in 0..=127 copies next n + 1 bytes, -127..=-1 repeats next byte -n + 1 times, -128 is no-op
+fn unpack_byte_run(data: &[u8], size: usize) -> Option<Vec<u8>> {
+    let mut data = data.iter();
+    let mut result = Vec::with_capacity(size);
+
+    while result.len() < size {
+        let n = *data.next()? as i8;
+        match n {
+            0..=127 => {
+                for _ in 0..=n {
+                    result.push(*data.next()?);
+                }
+            }
+            -127..=-1 => {
+                let value = *data.next()?;
+                result.resize(result.len() + (1 - n as i32) as usize, value);
+            }
+            -128 => {}
+        }
+    }
+    result.truncate(size);
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{
+        codecs::{
+            gif::{GifEncoder, Repeat},
+            jpeg::JpegEncoder,
+        },
+        Delay, Frame, Rgb, RgbImage, Rgba, RgbaImage,
+    };
+
+    use super::{decode_image, decode_image_frames};
+
+    #[test]
+    fn test_wbmp() {
+        // 10x2, first row alternating from white, second row black
+        let data = [0, 0, 10, 2, 0b1010_1010, 0b1000_0000, 0, 0];
+
+        let image = decode_image(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (10, 2));
+        assert_eq!(image.get_pixel(0, 0).r, 255);
+        assert_eq!(image.get_pixel(1, 0).r, 0);
+        assert_eq!(image.get_pixel(8, 0).r, 255);
+        assert_eq!(image.get_pixel(9, 0).r, 0);
+        assert_eq!(image.get_pixel(0, 1).r, 0);
+
+        assert!(decode_image(&data[..6]).is_err());
+        assert!(decode_image(&[1, 2, 3]).is_err());
+    }
+
+    fn iff(form_type: &[u8], chunks: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+        let mut body = form_type.to_vec();
+        for (id, data) in chunks {
+            body.extend_from_slice(id);
+            body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            body.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+
+        let mut result = b"FORM".to_vec();
+        result.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        result.extend(body);
+
+        result
+    }
+
+    fn bmhd(width: u16, height: u16, planes: u8, masking: u8, compression: u8, transparent: u16) -> Vec<u8> {
+        let mut result = Vec::new();
+        result.extend_from_slice(&width.to_be_bytes());
+        result.extend_from_slice(&height.to_be_bytes());
+        result.extend_from_slice(&[0, 0, 0, 0, planes, masking, compression, 0]);
+        result.extend_from_slice(&transparent.to_be_bytes());
+        result.extend_from_slice(&[1, 1, 0, 0, 0, 0]);
+
+        result
+    }
+
+    #[test]
+    fn test_lbm() {
+        // 3x1 ilbm of 2 planes, pixels are 1, 2, 3 in red, green, blue
+        let data = iff(
+            b"ILBM",
+            &[
+                (b"BMHD", bmhd(3, 1, 2, 0, 0, 0)),
+                (b"CMAP", vec![0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255]),
+                (b"BODY", vec![0b1010_0000, 0, 0b0110_0000, 0]),
+            ],
+        );
+
+        let image = decode_image(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 1));
+        assert_eq!(image.get_pixel(0, 0).r, 255);
+        assert_eq!(image.get_pixel(1, 0).g, 255);
+        assert_eq!(image.get_pixel(2, 0).b, 255);
+
+        // 3x2 packed pbm, index 0 is transparent
+        let data = iff(
+            b"PBM ",
+            &[
+                (b"BMHD", bmhd(3, 2, 8, 2, 1, 0)),
+                (b"CMAP", vec![0, 0, 0, 255, 255, 255]),
+                (b"BODY", vec![3, 1, 0, 1, 0, 0xfd, 1]),
+            ],
+        );
+
+        let image = decode_image(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert_eq!((image.get_pixel(0, 0).r, image.get_pixel(0, 0).a), (255, 255));
+        assert_eq!(image.get_pixel(1, 0).a, 0);
+        assert_eq!(image.get_pixel(2, 1).r, 255);
+
+        // truncated body
+        assert!(decode_image(&data[..data.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn test_animated_gif() {
+        let mut data = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(Cursor::new(&mut data));
+            encoder.set_repeat(Repeat::Infinite).unwrap();
+
+            for (color, delay) in [([255, 0, 0, 255], 100), ([0, 0, 255, 255], 250)] {
+                let frame = Frame::from_parts(RgbaImage::from_pixel(2, 2, Rgba(color)), 0, 0, Delay::from_numer_denom_ms(delay, 1));
+                encoder.encode_frame(frame).unwrap();
+            }
+        }
+
+        let frames = decode_image_frames(&data).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames.iter().map(|x| x.delay).collect::<Vec<_>>(), [100, 250]);
+        assert_eq!(frames[0].image.get_pixel(1, 1).r, 255);
+        assert_eq!(frames[1].image.get_pixel(1, 1).b, 255);
+
+        assert_eq!(decode_image(&data).unwrap().get_pixel(0, 0).r, 255);
+    }
+
+    #[test]
+    fn test_jpeg() {
+        let mut data = Vec::new();
+        JpegEncoder::new(&mut data)
+            .encode_image(&RgbImage::from_pixel(8, 8, Rgb([0, 0, 255])))
+            .unwrap();
+
+        let image = decode_image(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (8, 8));
+        assert!(image.get_pixel(4, 4).b > 200 && image.get_pixel(4, 4).r < 50);
+    }
+}
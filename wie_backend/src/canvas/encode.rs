@@ -0,0 +1,95 @@
+use alloc::vec::Vec;
+
+use image::{
+    codecs::{bmp::BmpEncoder, png::PngEncoder},
+    ExtendedColorType, ImageEncoder,
+};
+
+use super::Image;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageFormat {
+    Bmp,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "bmp" => Some(Self::Bmp),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Bmp => "bmp",
+            Self::Png => "png",
+        }
+    }
+}
+
+// opaque images are written without an alpha channel, so 16-bit framebuffers become 24-bit files
+pub fn encode_image(image: &dyn Image, format: ImageFormat) -> anyhow::Result<Vec<u8>> {
+    let colors = image.colors();
+    let (data, color_type) = if image.is_opaque() {
+        (colors.iter().flat_map(|x| [x.r, x.g, x.b]).collect::<Vec<_>>(), ExtendedColorType::Rgb8)
+    } else {
+        (
+            colors.iter().flat_map(|x| [x.r, x.g, x.b, x.a]).collect::<Vec<_>>(),
+            ExtendedColorType::Rgba8,
+        )
+    };
+
+    let mut result = Vec::new();
+    match format {
+        ImageFormat::Bmp => BmpEncoder::new(&mut result).write_image(&data, image.width(), image.height(), color_type)?,
+        ImageFormat::Png => PngEncoder::new(&mut result).write_image(&data, image.width(), image.height(), color_type)?,
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::{decode_image, ArgbPixel, Image, Rgb565Pixel, VecImageBuffer};
+
+    use super::{encode_image, ImageFormat};
+
+    #[test]
+    fn test_encode_rgb565() {
+        // red, green, blue, white
+        let image = VecImageBuffer::<Rgb565Pixel>::from_raw(2, 2, vec![0xf800, 0x07e0, 0x001f, 0xffff]);
+
+        for format in [ImageFormat::Bmp, ImageFormat::Png] {
+            let decoded = decode_image(&encode_image(&image, format).unwrap()).unwrap();
+
+            assert_eq!((decoded.width(), decoded.height()), (2, 2));
+            for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let (expected, actual) = (image.get_pixel(x, y), decoded.get_pixel(x, y));
+                assert_eq!((actual.a, actual.r, actual.g, actual.b), (255, expected.r, expected.g, expected.b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_argb() {
+        let image = VecImageBuffer::<ArgbPixel>::from_raw(2, 1, vec![0xff102030, 0x80405060]);
+
+        for format in [ImageFormat::Bmp, ImageFormat::Png] {
+            let decoded = decode_image(&encode_image(&image, format).unwrap()).unwrap();
+
+            let colors = decoded.colors();
+            assert_eq!((colors[0].a, colors[0].r, colors[0].g, colors[0].b), (0xff, 0x10, 0x20, 0x30));
+            assert_eq!((colors[1].a, colors[1].r, colors[1].g, colors[1].b), (0x80, 0x40, 0x50, 0x60));
+        }
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert_eq!(ImageFormat::from_extension("PNG"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_extension("bmp"), Some(ImageFormat::Bmp));
+        assert_eq!(ImageFormat::from_extension("gif"), None);
+    }
+}
@@ -0,0 +1,224 @@
+use ab_glyph::{point, Font as _, FontRef, PxScale, ScaleFont};
+
+lazy_static::lazy_static! {
+    static ref NEODGM: FontRef<'static> = FontRef::try_from_slice(include_bytes!("../../../fonts/neodgm.ttf")).unwrap();
+}
+
+const ITALIC_SHEAR: f32 = 0.2;
+
+// values follow the J2ME font constants, which WIPI uses too
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FontFace {
+    #[default]
+    System = 0,
+    Monospace = 32,
+    Proportional = 64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FontSize {
+    #[default]
+    Medium = 0,
+    Small = 8,
+    Large = 16,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FontStyle(u32);
+
+impl FontStyle {
+    pub const PLAIN: Self = Self(0);
+    pub const BOLD: Self = Self(1);
+    pub const ITALIC: Self = Self(2);
+    pub const UNDERLINED: Self = Self(4);
+
+    pub fn from_bits_truncate(bits: u32) -> Self {
+        Self(bits & 7)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl FontFace {
+    pub fn from_bits(bits: u32) -> Self {
+        match bits {
+            32 => Self::Monospace,
+            64 => Self::Proportional,
+            _ => Self::System,
+        }
+    }
+}
+
+impl FontSize {
+    pub fn from_bits(bits: u32) -> Self {
+        match bits {
+            8 => Self::Small,
+            16 => Self::Large,
+            _ => Self::Medium,
+        }
+    }
+
+    fn pixel_height(&self) -> f32 {
+        match self {
+            Self::Small => 12.0,
+            Self::Medium => 14.0,
+            Self::Large => 16.0,
+        }
+    }
+}
+
+// Face, style and size of a font. All metrics are in pixels, and text boxes start at the top of the ascent.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Font {
+    pub face: FontFace,
+    pub style: FontStyle,
+    pub size: FontSize,
+}
+
+impl Font {
+    pub fn new(face: FontFace, style: FontStyle, size: FontSize) -> Self {
+        Self { face, style, size }
+    }
+
+    // face, style and size or-ed together as in J2ME
+    pub fn from_bits(face: u32, style: u32, size: u32) -> Self {
+        Self::new(FontFace::from_bits(face), FontStyle::from_bits_truncate(style), FontSize::from_bits(size))
+    }
+
+    pub fn ascent(&self) -> i32 {
+        self.scaled().ascent().ceil() as i32
+    }
+
+    pub fn descent(&self) -> i32 {
+        (-self.scaled().descent()).ceil() as i32
+    }
+
+    pub fn height(&self) -> i32 {
+        self.ascent() + self.descent()
+    }
+
+    // offset of the baseline from the top of the text
+    pub fn baseline(&self) -> i32 {
+        self.ascent()
+    }
+
+    pub fn char_width(&self, c: char) -> i32 {
+        if c.is_control() {
+            return 0;
+        }
+
+        let scaled = self.scaled();
+        let advance = scaled.h_advance(scaled.glyph_id(c)).round() as i32;
+
+        // bold glyphs are drawn twice, one pixel apart
+        if self.style.contains(FontStyle::BOLD) {
+            advance + 1
+        } else {
+            advance
+        }
+    }
+
+    pub fn string_width(&self, string: &str) -> i32 {
+        string.chars().map(|c| self.char_width(c)).sum()
+    }
+
+    // calls `plot` with the coverage of each pixel of `string` drawn with its top-left at the origin
+    pub fn render(&self, string: &str, mut plot: impl FnMut(i32, i32, f32)) {
+        let scaled = self.scaled();
+        let baseline = self.baseline();
+        let bold = self.style.contains(FontStyle::BOLD);
+        let italic = self.style.contains(FontStyle::ITALIC);
+
+        let mut pen = 0;
+        for c in string.chars() {
+            if c.is_control() {
+                continue;
+            }
+
+            let mut glyph = scaled.scaled_glyph(c);
+            glyph.position = point(pen as f32, baseline as f32);
+
+            if let Some(outlined_glyph) = scaled.outline_glyph(glyph) {
+                let bounds = outlined_glyph.px_bounds();
+
+                outlined_glyph.draw(|glyph_x, glyph_y, coverage| {
+                    let y = bounds.min.y as i32 + glyph_y as i32;
+                    let mut x = bounds.min.x as i32 + glyph_x as i32;
+                    if italic {
+                        x += ((baseline - y) as f32 * ITALIC_SHEAR).round() as i32;
+                    }
+
+                    plot(x, y, coverage);
+                    if bold {
+                        plot(x + 1, y, coverage);
+                    }
+                });
+            }
+
+            pen += self.char_width(c);
+        }
+
+        if self.style.contains(FontStyle::UNDERLINED) {
+            for x in 0..pen {
+                plot(x, baseline + 1, 1.0);
+            }
+        }
+    }
+
+    // only one typeface is bundled, so every face maps to it
+    fn scaled(&self) -> impl ScaleFont<&'static FontRef<'static>> {
+        let typeface: &'static FontRef<'static> = &NEODGM;
+
+        typeface.into_scaled(PxScale::from(self.size.pixel_height()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Font, FontFace, FontSize, FontStyle};
+
+    #[test]
+    fn test_metrics() {
+        let small = Font::new(FontFace::System, FontStyle::PLAIN, FontSize::Small);
+        let large = Font::new(FontFace::System, FontStyle::PLAIN, FontSize::Large);
+
+        assert!(small.height() < large.height());
+        assert_eq!(small.height(), small.ascent() + small.descent());
+        assert_eq!(small.string_width("ab"), small.char_width('a') + small.char_width('b'));
+        assert_eq!(small.string_width("\n"), 0);
+
+        let bold = Font::new(FontFace::System, FontStyle::BOLD, FontSize::Small);
+        assert_eq!(bold.string_width("ab"), small.string_width("ab") + 2);
+
+        assert_eq!(Font::from_bits(32, 5, 16), Font::new(FontFace::Monospace, FontStyle(5), FontSize::Large));
+    }
+
+    #[test]
+    fn test_render() {
+        let font = Font::default();
+        let underlined = Font::new(FontFace::System, FontStyle::UNDERLINED, FontSize::Medium);
+
+        let mut pixels = Vec::new();
+        font.render("A", |x, y, coverage| pixels.push((x, y, coverage)));
+
+        // glyph sits on the baseline within its advance
+        assert!(!pixels.is_empty());
+        assert!(pixels
+            .iter()
+            .all(|&(x, y, _)| x >= 0 && x < font.char_width('A') && y >= 0 && y < font.baseline()));
+
+        let mut underline = 0;
+        underlined.render("A", |_, y, _| {
+            if y == underlined.baseline() + 1 {
+                underline += 1
+            }
+        });
+        assert_eq!(underline, underlined.char_width('A'));
+    }
+}
@@ -0,0 +1,221 @@
+// Shape rasterization following J2ME conventions: coordinates lie on the grid lines between pixels.
+// Fills cover pixels whose center is inside the shape, outlines are drawn with a 1-pixel pen
+// hanging down and right from the path, so an outline of w x h covers w + 1 x h + 1 pixels.
+
+use alloc::vec::Vec;
+
+use super::Rect;
+
+// horizontal run of pixels from `x` until before `x_end`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) struct Span {
+    pub y: i32,
+    pub x: i32,
+    pub x_end: i32,
+}
+
+pub(super) struct Ellipse {
+    center_x: f32,
+    center_y: f32,
+    radius_x: f32,
+    radius_y: f32,
+}
+
+impl Ellipse {
+    pub fn new(bounds: &Rect) -> Self {
+        Self {
+            center_x: bounds.x as f32 + bounds.width as f32 / 2.0,
+            center_y: bounds.y as f32 + bounds.height as f32 / 2.0,
+            radius_x: bounds.width as f32 / 2.0,
+            radius_y: bounds.height as f32 / 2.0,
+        }
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let dx = (x - self.center_x) / self.radius_x;
+        let dy = (y - self.center_y) / self.radius_y;
+
+        dx * dx + dy * dy <= 1.0
+    }
+
+    // angles are counter-clockwise from 3 o'clock and relative to the bounding box,
+    // so 45 degrees always points to the top-right corner
+    pub fn angle(&self, x: f32, y: f32) -> Option<f32> {
+        let dx = (x - self.center_x) / self.radius_x;
+        let dy = (self.center_y - y) / self.radius_y;
+        if dx == 0.0 && dy == 0.0 {
+            return None;
+        }
+
+        Some(dy.atan2(dx).to_degrees().rem_euclid(360.0))
+    }
+}
+
+pub(super) fn in_arc(angle: Option<f32>, start_angle: i32, arc_angle: i32) -> bool {
+    if arc_angle == 0 {
+        return false;
+    }
+    if arc_angle.abs() >= 360 {
+        return true;
+    }
+    let Some(angle) = angle else {
+        return true;
+    };
+
+    let (start, length) = if arc_angle < 0 {
+        (start_angle + arc_angle, -arc_angle)
+    } else {
+        (start_angle, arc_angle)
+    };
+
+    (angle - start as f32).rem_euclid(360.0) <= length as f32
+}
+
+// rectangle of `bounds` with each corner replaced by a quarter of an `arc_width` x `arc_height` ellipse
+pub(super) fn round_rect_contains(bounds: &Rect, arc_width: i32, arc_height: i32, x: f32, y: f32) -> bool {
+    let (left, top) = (bounds.x as f32, bounds.y as f32);
+    let (right, bottom) = (bounds.right() as f32, bounds.bottom() as f32);
+    if x < left || x > right || y < top || y > bottom {
+        return false;
+    }
+
+    let radius_x = arc_width.clamp(0, bounds.width) as f32 / 2.0;
+    let radius_y = arc_height.clamp(0, bounds.height) as f32 / 2.0;
+    if radius_x == 0.0 || radius_y == 0.0 {
+        return true;
+    }
+
+    let corner_x = if x < left + radius_x {
+        left + radius_x
+    } else if x > right - radius_x {
+        right - radius_x
+    } else {
+        return true;
+    };
+    let corner_y = if y < top + radius_y {
+        top + radius_y
+    } else if y > bottom - radius_y {
+        bottom - radius_y
+    } else {
+        return true;
+    };
+
+    let dx = (x - corner_x) / radius_x;
+    let dy = (y - corner_y) / radius_y;
+
+    dx * dx + dy * dy <= 1.0
+}
+
+// liang-barsky, part of the segment within `bounds` with endpoints rounded to pixels on or inside its edges
+pub(super) fn clip_line(bounds: &Rect, x1: i32, y1: i32, x2: i32, y2: i32) -> Option<(i32, i32, i32, i32)> {
+    if bounds.is_empty() {
+        return None;
+    }
+
+    let (x1, y1) = (x1 as f64, y1 as f64);
+    let (dx, dy) = (x2 as f64 - x1, y2 as f64 - y1);
+    let (left, top) = (bounds.x as f64, bounds.y as f64);
+    let (right, bottom) = (bounds.right() as f64 - 1.0, bounds.bottom() as f64 - 1.0);
+
+    let mut t0 = 0.0f64;
+    let mut t1 = 1.0f64;
+    for (p, q) in [(-dx, x1 - left), (dx, right - x1), (-dy, y1 - top), (dy, bottom - y1)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+
+    let point = |t: f64| ((x1 + t * dx).round() as i32, (y1 + t * dy).round() as i32);
+    let ((x1, y1), (x2, y2)) = (point(t0), point(t1));
+
+    Some((x1, y1, x2, y2))
+}
+
+// pixels of `bounds` within `area` whose center satisfies `contains`
+pub(super) fn fill_region(bounds: &Rect, area: &Rect, contains: impl Fn(f32, f32) -> bool) -> Vec<Span> {
+    let visible = bounds.intersect(area);
+    let mut result = Vec::new();
+
+    for y in visible.y..visible.bottom() {
+        let mut start = None;
+        for x in visible.x..=visible.right() {
+            let inside = x < visible.right() && contains(x as f32 + 0.5, y as f32 + 0.5);
+
+            match (inside, start) {
+                (true, None) => start = Some(x),
+                (false, Some(x_start)) => {
+                    result.push(Span { y, x: x_start, x_end: x });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    result
+}
+
+// pixels of a filled region within `area` which have a 4-neighbour outside of the region
+pub(super) fn outline_region(bounds: &Rect, area: &Rect, contains: impl Fn(f32, f32) -> bool) -> Vec<(i32, i32)> {
+    let visible = bounds.intersect(area);
+    let inside = |x: i32, y: i32| bounds.contains(x, y) && contains(x as f32 + 0.5, y as f32 + 0.5);
+
+    let mut result = Vec::new();
+    for y in visible.y..visible.bottom() {
+        for x in visible.x..visible.right() {
+            if inside(x, y) && !(inside(x - 1, y) && inside(x + 1, y) && inside(x, y - 1) && inside(x, y + 1)) {
+                result.push((x, y));
+            }
+        }
+    }
+
+    result
+}
+
+// even-odd scanline fill, sampled at pixel centers
+pub(super) fn fill_polygon(points: &[(i32, i32)], area: &Rect) -> Vec<Span> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+
+    let min_y = points.iter().map(|x| x.1).min().unwrap().max(area.y);
+    let max_y = points.iter().map(|x| x.1).max().unwrap().min(area.bottom());
+
+    let mut result = Vec::new();
+    let mut crossings = Vec::new();
+    for y in min_y..max_y {
+        let sample_y = y as f64 + 0.5;
+
+        crossings.clear();
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            let (x1, y1, x2, y2) = (x1 as f64, y1 as f64, x2 as f64, y2 as f64);
+
+            if (y1 <= sample_y && sample_y < y2) || (y2 <= sample_y && sample_y < y1) {
+                crossings.push(x1 + (sample_y - y1) * (x2 - x1) / (y2 - y1));
+            }
+        }
+        crossings.sort_by(|a, b| a.total_cmp(b));
+
+        for pair in crossings.chunks_exact(2) {
+            // first and last pixel whose center is within the crossings
+            let x = ((pair[0] - 0.5).ceil() as i32).max(area.x);
+            let x_end = ((pair[1] - 0.5).ceil() as i32).min(area.right());
+
+            if x < x_end {
+                result.push(Span { y, x, x_end });
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,100 @@
+// values follow the J2ME Sprite transform constants
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Transform {
+    #[default]
+    None = 0,
+    MirrorRot180 = 1,
+    Mirror = 2,
+    Rot180 = 3,
+    MirrorRot270 = 4,
+    Rot90 = 5,
+    Rot270 = 6,
+    MirrorRot90 = 7,
+}
+
+impl Transform {
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        Some(match raw {
+            0 => Self::None,
+            1 => Self::MirrorRot180,
+            2 => Self::Mirror,
+            3 => Self::Rot180,
+            4 => Self::MirrorRot270,
+            5 => Self::Rot90,
+            6 => Self::Rot270,
+            7 => Self::MirrorRot90,
+            _ => return None,
+        })
+    }
+
+    pub fn transformed_size(&self, width: i32, height: i32) -> (i32, i32) {
+        match self {
+            Self::None | Self::Mirror | Self::Rot180 | Self::MirrorRot180 => (width, height),
+            Self::Rot90 | Self::Rot270 | Self::MirrorRot90 | Self::MirrorRot270 => (height, width),
+        }
+    }
+
+    // position in the `width` x `height` source region drawn at (x, y) of the transformed region
+    // rotations are clockwise, and mirrored transforms mirror horizontally before rotating
+    pub fn source_offset(&self, x: i32, y: i32, width: i32, height: i32) -> (i32, i32) {
+        match self {
+            Self::None => (x, y),
+            Self::Mirror => (width - 1 - x, y),
+            Self::Rot90 => (y, height - 1 - x),
+            Self::Rot180 => (width - 1 - x, height - 1 - y),
+            Self::Rot270 => (width - 1 - y, x),
+            Self::MirrorRot90 => (width - 1 - y, height - 1 - x),
+            Self::MirrorRot180 => (x, height - 1 - y),
+            Self::MirrorRot270 => (y, x),
+        }
+    }
+}
+
+// values follow the J2ME Graphics anchor constants
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Anchor(u32);
+
+impl Anchor {
+    pub const HCENTER: Self = Self(1);
+    pub const VCENTER: Self = Self(2);
+    pub const LEFT: Self = Self(4);
+    pub const RIGHT: Self = Self(8);
+    pub const TOP: Self = Self(16);
+    pub const BOTTOM: Self = Self(32);
+    pub const BASELINE: Self = Self(64);
+
+    pub const TOP_LEFT: Self = Self(16 | 4);
+
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    // top-left corner of a `width` x `height` box anchored at (x, y), missing directions default to top and left
+    pub fn top_left(&self, x: i32, y: i32, width: i32, height: i32) -> (i32, i32) {
+        let x = if self.contains(Self::HCENTER) {
+            x - width / 2
+        } else if self.contains(Self::RIGHT) {
+            x - width
+        } else {
+            x
+        };
+
+        let y = if self.contains(Self::VCENTER) {
+            y - height / 2
+        } else if self.contains(Self::BOTTOM) {
+            y - height
+        } else {
+            y
+        };
+
+        (x, y)
+    }
+}
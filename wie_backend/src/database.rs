@@ -1,14 +1,214 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt;
+
 pub type RecordId = u32;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DatabaseError {
+    NotFound,
+    InvalidRecordId,
+    InvalidName,
+    Corrupted,
+    Io,
+}
+
+pub type DatabaseResult<T> = Result<T, DatabaseError>;
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            DatabaseError::NotFound => "Database not found",
+            DatabaseError::InvalidRecordId => "Invalid record id",
+            DatabaseError::InvalidName => "Invalid database name",
+            DatabaseError::Corrupted => "Database is corrupted",
+            DatabaseError::Io => "Database I/O error",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+impl From<DatabaseError> for anyhow::Error {
+    fn from(e: DatabaseError) -> Self {
+        anyhow::anyhow!("{}", e)
+    }
+}
+
 pub trait Database {
-    fn add(&mut self, data: &[u8]) -> RecordId;
-    fn get(&self, id: RecordId) -> Option<Vec<u8>>;
-    fn set(&mut self, id: RecordId, data: &[u8]) -> bool;
-    fn delete(&mut self, id: RecordId) -> bool;
+    fn add(&mut self, data: &[u8]) -> DatabaseResult<RecordId>;
+    fn get(&self, id: RecordId) -> DatabaseResult<Vec<u8>>;
+    fn set(&mut self, id: RecordId, data: &[u8]) -> DatabaseResult<()>;
+    fn delete(&mut self, id: RecordId) -> DatabaseResult<()>;
 
-    fn get_record_ids(&self) -> Vec<RecordId>;
+    fn get_record_ids(&self) -> DatabaseResult<Vec<RecordId>>;
+    fn get_record_size(&self, id: RecordId) -> DatabaseResult<usize>;
 }
 
 pub trait DatabaseRepository {
-    fn open(&self, name: &str) -> Box<dyn Database>;
+    fn open(&self, name: &str, create: bool) -> DatabaseResult<Box<dyn Database>>;
+    fn list(&self) -> DatabaseResult<Vec<String>>;
+    fn delete(&self, name: &str) -> DatabaseResult<()>;
+}
+
+const DATABASE_FILE_MAGIC: &[u8; 4] = b"WIDB";
+const DATABASE_FILE_VERSION: u16 = 1;
+const DATABASE_FILE_HEADER_SIZE: usize = 16;
+const DATABASE_FILE_INDEX_ENTRY_SIZE: usize = 8;
+
+// Records of one database, serialized as a single file:
+//   header: magic, version u16, reserved u16, next record id u32, record count u32
+//   index: record id u32 and record size u32 per record
+//   record data in index order, then FNV-1a checksum u32 of everything before it
+// All integers are little endian. Ids are never reused, so a deleted record can't be mistaken for a new one.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DatabaseFile {
+    records: BTreeMap<RecordId, Vec<u8>>,
+    next_id: RecordId,
+}
+
+impl DatabaseFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(data: &[u8]) -> DatabaseResult<Self> {
+        if data.len() < DATABASE_FILE_HEADER_SIZE + 4 || &data[0..4] != DATABASE_FILE_MAGIC {
+            return Err(DatabaseError::Corrupted);
+        }
+
+        let (body, checksum) = data.split_at(data.len() - 4);
+        if Self::checksum(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(DatabaseError::Corrupted);
+        }
+
+        let read_u32 = |offset: usize| -> DatabaseResult<u32> {
+            let bytes = body.get(offset..offset + 4).ok_or(DatabaseError::Corrupted)?;
+
+            Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+        };
+
+        let version = u16::from_le_bytes([body[4], body[5]]);
+        if version != DATABASE_FILE_VERSION {
+            return Err(DatabaseError::Corrupted);
+        }
+
+        let next_id = read_u32(8)?;
+        let record_count = read_u32(12)? as usize;
+
+        let mut records = BTreeMap::new();
+        let mut data_offset = DATABASE_FILE_HEADER_SIZE + record_count.checked_mul(DATABASE_FILE_INDEX_ENTRY_SIZE).ok_or(DatabaseError::Corrupted)?;
+        for i in 0..record_count {
+            let entry_offset = DATABASE_FILE_HEADER_SIZE + i * DATABASE_FILE_INDEX_ENTRY_SIZE;
+            let id = read_u32(entry_offset)?;
+            let size = read_u32(entry_offset + 4)? as usize;
+
+            let record = body.get(data_offset..data_offset + size).ok_or(DatabaseError::Corrupted)?;
+            if id >= next_id || records.insert(id, record.to_vec()).is_some() {
+                return Err(DatabaseError::Corrupted);
+            }
+
+            data_offset += size;
+        }
+
+        if data_offset != body.len() {
+            return Err(DatabaseError::Corrupted);
+        }
+
+        Ok(Self { records, next_id })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        result.extend_from_slice(DATABASE_FILE_MAGIC);
+        result.extend_from_slice(&DATABASE_FILE_VERSION.to_le_bytes());
+        result.extend_from_slice(&0u16.to_le_bytes());
+        result.extend_from_slice(&self.next_id.to_le_bytes());
+        result.extend_from_slice(&(self.records.len() as u32).to_le_bytes());
+
+        for (id, record) in &self.records {
+            result.extend_from_slice(&id.to_le_bytes());
+            result.extend_from_slice(&(record.len() as u32).to_le_bytes());
+        }
+        for record in self.records.values() {
+            result.extend_from_slice(record);
+        }
+
+        let checksum = Self::checksum(&result);
+        result.extend_from_slice(&checksum.to_le_bytes());
+
+        result
+    }
+
+    pub fn add(&mut self, data: &[u8]) -> DatabaseResult<RecordId> {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).ok_or(DatabaseError::InvalidRecordId)?;
+        self.records.insert(id, data.to_vec());
+
+        Ok(id)
+    }
+
+    pub fn get(&self, id: RecordId) -> DatabaseResult<&[u8]> {
+        self.records.get(&id).map(|x| x.as_slice()).ok_or(DatabaseError::InvalidRecordId)
+    }
+
+    // writing to an id not allocated yet creates the record, later additions get ids after it
+    pub fn set(&mut self, id: RecordId, data: &[u8]) -> DatabaseResult<()> {
+        if id >= self.next_id {
+            self.next_id = id.checked_add(1).ok_or(DatabaseError::InvalidRecordId)?;
+        }
+        self.records.insert(id, data.to_vec());
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, id: RecordId) -> DatabaseResult<()> {
+        self.records.remove(&id).map(|_| ()).ok_or(DatabaseError::InvalidRecordId)
+    }
+
+    pub fn record_ids(&self) -> Vec<RecordId> {
+        self.records.keys().cloned().collect()
+    }
+
+    fn checksum(data: &[u8]) -> u32 {
+        data.iter().fold(0x811c9dc5, |hash, &x| (hash ^ x as u32).wrapping_mul(0x01000193))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DatabaseError, DatabaseFile};
+
+    #[test]
+    fn test_database_file() {
+        let mut file = DatabaseFile::new();
+        assert_eq!(file.add(b"first"), Ok(0));
+        assert_eq!(file.add(b"second"), Ok(1));
+        file.set(5, b"").unwrap();
+        file.delete(1).unwrap();
+
+        let serialized = file.serialize();
+        let parsed = DatabaseFile::parse(&serialized).unwrap();
+        assert_eq!(parsed, file);
+        assert_eq!(parsed.record_ids(), [0, 5]);
+        assert_eq!(parsed.get(0), Ok(b"first".as_slice()));
+        assert_eq!(parsed.get(1), Err(DatabaseError::InvalidRecordId));
+
+        // ids of deleted records are not handed out again
+        let mut parsed = parsed;
+        assert_eq!(parsed.add(b"third"), Ok(6));
+    }
+
+    #[test]
+    fn test_database_file_corrupted() {
+        let mut file = DatabaseFile::new();
+        file.add(b"record").unwrap();
+        let serialized = file.serialize();
+
+        let mut flipped = serialized.clone();
+        flipped[20] ^= 1;
+        assert_eq!(DatabaseFile::parse(&flipped), Err(DatabaseError::Corrupted));
+        assert_eq!(DatabaseFile::parse(&serialized[..serialized.len() - 1]), Err(DatabaseError::Corrupted));
+        assert_eq!(DatabaseFile::parse(b""), Err(DatabaseError::Corrupted));
+    }
 }
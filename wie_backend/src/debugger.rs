@@ -0,0 +1,9 @@
+// Transport independent debugger frontend, e.g. gdb remote protocol
+pub trait Debugger {
+    // handles bytes received from client, returns bytes to send back
+    fn handle_input(&mut self, data: &[u8]) -> Vec<u8>;
+    // returns notifications for the client, like stop events
+    fn poll(&mut self) -> Vec<u8>;
+    // client went away, removes breakpoints and resumes execution
+    fn detach(&mut self);
+}
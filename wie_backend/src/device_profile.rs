@@ -0,0 +1,151 @@
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Carrier {
+    Ktf,
+    Skt,
+    Lgt,
+    Generic,
+}
+
+impl Carrier {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ktf" | "kt" => Some(Self::Ktf),
+            "skt" | "sk" => Some(Self::Skt),
+            "lgt" | "lg" => Some(Self::Lgt),
+            "generic" => Some(Self::Generic),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ktf => "ktf",
+            Self::Skt => "skt",
+            Self::Lgt => "lgt",
+            Self::Generic => "generic",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyLayout {
+    // separate directional keys with an ok key in the middle
+    DirectionalPad,
+    // no directional keys, games move with 2, 4, 6, 8 and select with 5
+    NumberPad,
+}
+
+impl KeyLayout {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "dpad" | "directional" => Some(Self::DirectionalPad),
+            "numpad" | "number" => Some(Self::NumberPad),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DirectionalPad => "dpad",
+            Self::NumberPad => "numpad",
+        }
+    }
+}
+
+// Handset the app runs on. Apps size themselves from the screen and may branch on the carrier or handset properties.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeviceProfile {
+    pub carrier: Carrier,
+    pub screen_width: u32,
+    pub screen_height: u32,
+    pub color_depth: u32,
+    pub key_layout: KeyLayout,
+    pub properties: BTreeMap<String, String>,
+}
+
+impl DeviceProfile {
+    pub fn new(carrier: Carrier) -> Self {
+        Self {
+            carrier,
+            screen_width: 240,
+            screen_height: 320,
+            color_depth: 16,
+            key_layout: KeyLayout::DirectionalPad,
+            properties: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_screen_size(mut self, width: u32, height: u32) -> Self {
+        self.screen_width = width;
+        self.screen_height = height;
+
+        self
+    }
+
+    pub fn with_key_layout(mut self, key_layout: KeyLayout) -> Self {
+        self.key_layout = key_layout;
+
+        self
+    }
+
+    pub fn with_property(mut self, name: &str, value: &str) -> Self {
+        self.properties.insert(name.to_string(), value.to_string());
+
+        self
+    }
+
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(|x| x.as_str())
+    }
+
+    // accepts `240x320`, `240*320` and `240,320`
+    pub fn parse_screen_size(value: &str) -> Option<(u32, u32)> {
+        let (width, height) = value.trim().split_once(['x', 'X', '*', ','])?;
+        let (width, height) = (width.trim().parse().ok()?, height.trim().parse().ok()?);
+
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        Some((width, height))
+    }
+}
+
+impl Default for DeviceProfile {
+    fn default() -> Self {
+        Self::new(Carrier::Generic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Carrier, DeviceProfile, KeyLayout};
+
+    #[test]
+    fn test_parse_screen_size() {
+        assert_eq!(DeviceProfile::parse_screen_size("176x220"), Some((176, 220)));
+        assert_eq!(DeviceProfile::parse_screen_size(" 240, 400 "), Some((240, 400)));
+        assert_eq!(DeviceProfile::parse_screen_size("128*160"), Some((128, 160)));
+        assert_eq!(DeviceProfile::parse_screen_size("240"), None);
+        assert_eq!(DeviceProfile::parse_screen_size("0x320"), None);
+    }
+
+    #[test]
+    fn test_profile() {
+        let profile = DeviceProfile::new(Carrier::from_name("SKT").unwrap())
+            .with_screen_size(176, 220)
+            .with_key_layout(KeyLayout::from_name("numpad").unwrap())
+            .with_property("m.MIN", "01012345678");
+
+        assert_eq!(profile.carrier.name(), "skt");
+        assert_eq!((profile.screen_width, profile.screen_height), (176, 220));
+        assert_eq!(profile.key_layout, KeyLayout::NumberPad);
+        assert_eq!(profile.property("m.MIN"), Some("01012345678"));
+        assert_eq!(profile.property("m.CARRIER"), None);
+    }
+}
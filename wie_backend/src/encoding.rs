@@ -0,0 +1,119 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str;
+
+use encoding_rs::{EUC_KR, SHIFT_JIS};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TextEncoding {
+    EucKr,
+    Utf8,
+    Latin1,
+    ShiftJis,
+}
+
+impl TextEncoding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "euc-kr" | "euckr" | "cp949" | "ks-c-5601-1987" => Some(Self::EucKr),
+            "utf-8" | "utf8" => Some(Self::Utf8),
+            "iso-8859-1" | "latin1" | "latin-1" => Some(Self::Latin1),
+            "shift-jis" | "shiftjis" | "sjis" | "cp932" => Some(Self::ShiftJis),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::EucKr => "EUC-KR",
+            Self::Utf8 => "UTF-8",
+            Self::Latin1 => "ISO-8859-1",
+            Self::ShiftJis => "Shift_JIS",
+        }
+    }
+
+    // Descriptors rarely declare their charset, so we guess from the bytes.
+    // Legacy multibyte encodings accept much of each other's byte sequences, so the one expected on the platform is tried first.
+    pub fn detect(data: &[u8], default: Self) -> Self {
+        if data.is_ascii() {
+            return default;
+        }
+
+        if str::from_utf8(data).is_ok() {
+            return Self::Utf8;
+        }
+
+        let candidates = match default {
+            Self::ShiftJis => [Self::ShiftJis, Self::EucKr],
+            _ => [Self::EucKr, Self::ShiftJis],
+        };
+
+        candidates.into_iter().find(|x| x.decode_strict(data).is_some()).unwrap_or(Self::Latin1)
+    }
+
+    pub fn encode(&self, string: &str) -> Vec<u8> {
+        match self {
+            Self::EucKr => EUC_KR.encode(string).0.to_vec(),
+            Self::Utf8 => string.as_bytes().to_vec(),
+            Self::Latin1 => string.chars().map(|x| if (x as u32) < 0x100 { x as u8 } else { b'?' }).collect(),
+            Self::ShiftJis => SHIFT_JIS.encode(string).0.to_vec(),
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Self::EucKr => EUC_KR.decode(bytes).0.to_string(),
+            Self::Utf8 => String::from_utf8_lossy(bytes).to_string(),
+            Self::Latin1 => bytes.iter().map(|&x| x as char).collect(),
+            Self::ShiftJis => SHIFT_JIS.decode(bytes).0.to_string(),
+        }
+    }
+
+    fn decode_strict(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Self::EucKr => EUC_KR.decode_without_bom_handling_and_without_replacement(bytes).map(|x| x.to_string()),
+            Self::Utf8 => str::from_utf8(bytes).ok().map(|x| x.to_string()),
+            Self::Latin1 => Some(self.decode(bytes)),
+            Self::ShiftJis => SHIFT_JIS
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .map(|x| x.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextEncoding;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(TextEncoding::detect(b"MIDlet-Name: Test", TextEncoding::EucKr), TextEncoding::EucKr);
+        assert_eq!(TextEncoding::detect("Name:테스트".as_bytes(), TextEncoding::EucKr), TextEncoding::Utf8);
+        assert_eq!(
+            TextEncoding::detect(&TextEncoding::EucKr.encode("Name:테스트"), TextEncoding::Utf8),
+            TextEncoding::EucKr
+        );
+        assert_eq!(
+            TextEncoding::detect(&TextEncoding::ShiftJis.encode("Name:テスト"), TextEncoding::ShiftJis),
+            TextEncoding::ShiftJis
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (encoding, text) in [
+            (TextEncoding::EucKr, "한글"),
+            (TextEncoding::Utf8, "한글"),
+            (TextEncoding::Latin1, "café"),
+            (TextEncoding::ShiftJis, "日本語"),
+        ] {
+            assert_eq!(encoding.decode(&encoding.encode(text)), text);
+        }
+
+        assert_eq!(TextEncoding::from_name("Shift_JIS"), Some(TextEncoding::ShiftJis));
+        assert_eq!(TextEncoding::from_name("euc-kr"), Some(TextEncoding::EucKr));
+        assert_eq!(TextEncoding::from_name("unknown"), None);
+    }
+}
@@ -1,24 +1,48 @@
-use alloc::rc::Rc;
+use alloc::{collections::BTreeSet, rc::Rc, string::ToString, sync::Arc};
 use core::{
     cell::RefCell,
     fmt::Debug,
     future::Future,
     pin::Pin,
-    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    task::{Context, Poll, Waker},
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Mutex, task::Wake};
 
-use crate::time::Instant;
+use wie_util::{SnapshotReader, SnapshotResult, SnapshotWriter};
+
+use crate::{
+    task::{JoinHandle, JoinState, TaskInfo, TaskOptions, TaskState},
+    time::Instant,
+};
+
+const MAX_STEPS_WITHOUT_TIME_ADVANCE: usize = 1024;
 
 type Task = Pin<Box<dyn Future<Output = anyhow::Result<()>>>>;
 
 pub struct ExecutorInner {
     current_task_id: Option<usize>,
     tasks: HashMap<usize, Task>,
+    task_options: HashMap<usize, TaskOptions>,
     sleeping_tasks: HashMap<usize, Instant>,
+    ready_tasks: Arc<Mutex<BTreeSet<usize>>>,
     last_task_id: usize,
 }
 
+struct TaskWaker {
+    task_id: usize,
+    ready_tasks: Arc<Mutex<BTreeSet<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready_tasks.lock().unwrap().insert(self.task_id);
+    }
+}
+
 #[async_trait::async_trait(?Send)]
 pub trait AsyncCallable<R, E> {
     async fn call(self) -> Result<R, E>;
@@ -36,7 +60,8 @@ where
     }
 }
 
-// Executor polling every future until it is ready to implement generator using async ecosystem
+// Executor driving every task to completion to implement generator using async ecosystem.
+// Tasks are only polled when they are woken, either by a waker, by an expired sleep or by spawning.
 #[derive(Clone)]
 pub struct Executor {
     inner: Rc<RefCell<ExecutorInner>>,
@@ -48,41 +73,135 @@ impl Executor {
         let inner = Rc::new(RefCell::new(ExecutorInner {
             current_task_id: None,
             tasks: HashMap::new(),
+            task_options: HashMap::new(),
             sleeping_tasks: HashMap::new(),
+            ready_tasks: Arc::new(Mutex::new(BTreeSet::new())),
             last_task_id: 0,
         }));
 
         Self { inner }
     }
 
-    pub fn spawn<C, R, E>(&mut self, callable: C) -> usize
+    pub fn spawn<C, R, E>(&mut self, callable: C) -> JoinHandle<R>
     where
         C: AsyncCallable<R, E> + 'static,
+        R: 'static,
         E: Debug,
     {
-        let fut = async move {
-            callable.call().await.map_err(|x| anyhow::anyhow!("{:?}", x))?;
-
-            anyhow::Ok(())
-        };
+        self.spawn_with(TaskOptions::new("task"), callable)
+    }
 
+    // failure of a critical task is returned from tick, others only fail their JoinHandle
+    pub fn spawn_with<C, R, E>(&mut self, options: TaskOptions, callable: C) -> JoinHandle<R>
+    where
+        C: AsyncCallable<R, E> + 'static,
+        R: 'static,
+        E: Debug,
+    {
         let task_id = {
             let mut inner = self.inner.borrow_mut();
             inner.last_task_id += 1;
             inner.last_task_id
         };
 
-        self.inner.borrow_mut().tasks.insert(task_id, Box::pin(fut));
+        let state = Rc::new(RefCell::new(JoinState::new()));
+
+        let state_clone = state.clone();
+        let name = options.name().to_string();
+        let critical = options.is_critical();
+        let fut = async move {
+            let result = callable.call().await.map_err(|x| anyhow::anyhow!("{:?}", x));
+
+            match result {
+                Ok(x) => {
+                    state_clone.borrow_mut().complete(Ok(x));
+
+                    anyhow::Ok(())
+                }
+                Err(err) if critical => {
+                    state_clone.borrow_mut().complete(Err(anyhow::anyhow!("{}", err)));
+
+                    Err(err)
+                }
+                Err(err) => {
+                    tracing::error!("Task {} ({}) failed: {}", task_id, name, err);
+                    state_clone.borrow_mut().complete(Err(err));
+
+                    anyhow::Ok(())
+                }
+            }
+        };
+
+        let mut inner = self.inner.borrow_mut();
+        inner.tasks.insert(task_id, Box::pin(fut));
+        inner.task_options.insert(task_id, options);
+        inner.ready_tasks.lock().unwrap().insert(task_id);
 
-        task_id
+        JoinHandle::new(task_id, state, self.clone())
     }
 
-    // TODO we need to remove error handling from here. we need to JoinHandle like on spawn..
-    pub fn tick<T>(&mut self, now: T) -> anyhow::Result<()>
+    // returns false if the task has already finished
+    pub(crate) fn cancel(&mut self, task_id: usize) -> bool {
+        let task = {
+            let mut inner = self.inner.borrow_mut();
+            if inner.task_options.remove(&task_id).is_none() {
+                return false;
+            }
+
+            inner.sleeping_tasks.remove(&task_id);
+            inner.ready_tasks.lock().unwrap().remove(&task_id);
+
+            // if the task cancels itself, it's not in the map while being polled and will be dropped by step
+            inner.tasks.remove(&task_id)
+        };
+
+        // dropping a future may touch the executor, so we drop it outside of the borrow
+        drop(task);
+
+        true
+    }
+
+    pub(crate) fn is_alive(&self, task_id: usize) -> bool {
+        self.inner.borrow().task_options.contains_key(&task_id)
+    }
+
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let inner = self.inner.borrow();
+        let ready_tasks = inner.ready_tasks.lock().unwrap();
+
+        let mut result = inner
+            .task_options
+            .iter()
+            .map(|(&id, options)| {
+                let state = if let Some(&until) = inner.sleeping_tasks.get(&id) {
+                    TaskState::Sleeping(until)
+                } else if ready_tasks.contains(&id) || inner.current_task_id == Some(id) {
+                    TaskState::Ready
+                } else {
+                    TaskState::Waiting
+                };
+
+                TaskInfo {
+                    id,
+                    name: options.name().to_string(),
+                    critical: options.is_critical(),
+                    state,
+                }
+            })
+            .collect::<Vec<_>>();
+        result.sort_by_key(|x| x.id);
+
+        result
+    }
+
+    // `virtual_clock` is set if time doesn't move unless the host advances it
+    pub fn tick<T>(&mut self, now: T, virtual_clock: bool) -> anyhow::Result<()>
     where
         T: Fn() -> Instant,
     {
-        let end = now() + 8; // TODO hardcoded
+        let start = now();
+        let end = start + 8; // TODO hardcoded
+        let mut steps_without_time_advance = 0;
         loop {
             let now = now();
 
@@ -90,56 +209,134 @@ impl Executor {
                 break;
             }
 
-            let running_task_count = self.inner.borrow().tasks.len() - self.inner.borrow().sleeping_tasks.len();
-            if running_task_count == 0 && !self.inner.borrow().sleeping_tasks.is_empty() {
-                let next_wakeup = *self.inner.borrow().sleeping_tasks.values().min().unwrap();
-                if now < next_wakeup {
+            // virtual clocks don't advance during tick, so we have to stop somewhere
+            if virtual_clock && now == start {
+                steps_without_time_advance += 1;
+                if steps_without_time_advance > MAX_STEPS_WITHOUT_TIME_ADVANCE {
                     break;
                 }
             }
 
+            if !self.has_ready_tasks(now) {
+                break;
+            }
+
             self.step(now)?;
         }
 
         Ok(())
     }
 
+    fn has_ready_tasks(&self, now: Instant) -> bool {
+        let inner = self.inner.borrow();
+
+        !inner.ready_tasks.lock().unwrap().is_empty() || inner.sleeping_tasks.values().any(|&x| x <= now)
+    }
+
     fn step(&mut self, now: Instant) -> anyhow::Result<()> {
-        let mut next_tasks = HashMap::new();
-        let tasks = self.inner.borrow_mut().tasks.drain().collect::<HashMap<_, _>>();
-        let mut sleeping_tasks = self.inner.borrow_mut().sleeping_tasks.drain().collect::<HashMap<_, _>>();
-
-        for (task_id, mut task) in tasks.into_iter() {
-            let item = sleeping_tasks.get(&task_id);
-            if let Some(item) = item {
-                if *item <= now {
-                    sleeping_tasks.remove(&task_id);
+        let ready_tasks = {
+            let mut inner = self.inner.borrow_mut();
+            let inner = &mut *inner;
+
+            let mut ready_tasks = core::mem::take(&mut *inner.ready_tasks.lock().unwrap());
+
+            inner.sleeping_tasks.retain(|&task_id, &mut until| {
+                if until <= now {
+                    ready_tasks.insert(task_id);
+                    false
                 } else {
-                    next_tasks.insert(task_id, task);
-                    continue;
+                    true
                 }
-            }
+            });
 
-            let waker = self.create_waker();
+            ready_tasks
+        };
+
+        let mut ready_tasks = ready_tasks.into_iter();
+        while let Some(task_id) = ready_tasks.next() {
+            // wakers may outlive their task
+            let Some(mut task) = self.inner.borrow_mut().tasks.remove(&task_id) else {
+                continue;
+            };
+
+            let waker = self.create_waker(task_id);
             let mut context = Context::from_waker(&waker);
             self.inner.borrow_mut().current_task_id = Some(task_id);
 
-            match task.as_mut().poll(&mut context) {
+            let result = task.as_mut().poll(&mut context);
+
+            self.inner.borrow_mut().current_task_id = None;
+
+            match result {
                 Poll::Ready(x) => {
-                    x?;
+                    let mut inner = self.inner.borrow_mut();
+                    inner.sleeping_tasks.remove(&task_id);
+                    inner.task_options.remove(&task_id);
+
+                    if let Err(x) = x {
+                        // the rest are already taken from the queue, keep them for the next step
+                        inner.ready_tasks.lock().unwrap().extend(ready_tasks);
+
+                        return Err(x);
+                    }
                 }
                 Poll::Pending => {
-                    next_tasks.insert(task_id, task);
+                    if self.is_alive(task_id) {
+                        self.inner.borrow_mut().tasks.insert(task_id, task);
+                    }
                 }
             }
+        }
 
-            self.inner.borrow_mut().current_task_id = None;
+        Ok(())
+    }
+
+    // Futures can't be serialized, so we only save which tasks were alive and how long they had to sleep
+    pub(crate) fn save_state(&self, writer: &mut SnapshotWriter, now: Instant) {
+        let inner = self.inner.borrow();
+
+        let mut task_ids = inner.tasks.keys().cloned().collect::<Vec<_>>();
+        task_ids.sort();
+
+        writer.write_u32(task_ids.len() as _);
+        for task_id in task_ids {
+            let remaining = inner.sleeping_tasks.get(&task_id).map(|&x| if x > now { x - now } else { 0 });
+
+            writer.write_u32(task_id as _);
+            writer.write_u8(remaining.is_some() as _);
+            writer.write_u64(remaining.unwrap_or(0));
         }
+    }
 
-        self.inner.borrow_mut().sleeping_tasks.extend(sleeping_tasks);
-        self.inner.borrow_mut().tasks.extend(next_tasks);
+    pub(crate) fn read_state(reader: &mut SnapshotReader, now: Instant) -> SnapshotResult<HashMap<usize, Instant>> {
+        let mut sleeping_tasks = HashMap::new();
 
-        Ok(())
+        let task_count = reader.read_u32()?;
+        for _ in 0..task_count {
+            let task_id = reader.read_u32()? as usize;
+            let sleeping = reader.read_u8()? != 0;
+            let remaining = reader.read_u64()?;
+
+            if sleeping {
+                sleeping_tasks.insert(task_id, now + remaining);
+            }
+        }
+
+        Ok(sleeping_tasks)
+    }
+
+    pub(crate) fn restore_state(&mut self, sleeping_tasks: HashMap<usize, Instant>) {
+        let mut inner = self.inner.borrow_mut();
+        let inner = &mut *inner;
+        for task_id in inner.tasks.keys() {
+            if let Some(x) = sleeping_tasks.get(task_id) {
+                inner.sleeping_tasks.insert(*task_id, *x);
+            } else {
+                // we don't know what the others were waiting for, so let them check again
+                inner.sleeping_tasks.remove(task_id);
+                inner.ready_tasks.lock().unwrap().insert(*task_id);
+            }
+        }
     }
 
     pub(crate) fn sleep(&mut self, until: Instant) {
@@ -148,19 +345,52 @@ impl Executor {
         self.inner.borrow_mut().sleeping_tasks.insert(task_id, until);
     }
 
-    fn create_waker(&self) -> Waker {
-        unsafe fn noop_clone(_data: *const ()) -> RawWaker {
-            noop_raw_waker()
-        }
+    pub(crate) fn is_sleeping(&self) -> bool {
+        let inner = self.inner.borrow();
 
-        unsafe fn noop(_data: *const ()) {}
+        inner.current_task_id.is_some_and(|x| inner.sleeping_tasks.contains_key(&x))
+    }
 
-        const NOOP_WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(noop_clone, noop, noop, noop);
+    fn create_waker(&self, task_id: usize) -> Waker {
+        let ready_tasks = self.inner.borrow().ready_tasks.clone();
 
-        const fn noop_raw_waker() -> RawWaker {
-            RawWaker::new(core::ptr::null(), &NOOP_WAKER_VTABLE)
-        }
+        Waker::from(Arc::new(TaskWaker { task_id, ready_tasks }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    use crate::{task::TaskOptions, time::Instant};
+
+    use super::Executor;
+
+    #[test]
+    fn test_critical_failure() -> anyhow::Result<()> {
+        let mut executor = Executor::new();
+        let now = || Instant::from_epoch_millis(0);
+
+        let finished = Rc::new(Cell::new(false));
+        let finished_clone = finished.clone();
 
-        unsafe { Waker::from_raw(noop_raw_waker()) }
+        executor.spawn_with(TaskOptions::new("failing").critical(), || async {
+            Err::<(), _>(anyhow::anyhow!("failed"))
+        });
+        executor.spawn(move || async move {
+            finished_clone.set(true);
+
+            anyhow::Ok(())
+        });
+
+        assert!(executor.tick(now, true).is_err());
+        assert!(!finished.get());
+
+        // the other task is still ready after the failure
+        executor.tick(now, true)?;
+        assert!(finished.get());
+
+        Ok(())
     }
 }
@@ -0,0 +1,7 @@
+// Writable files of the app, kept by the platform across restarts. Paths are normalized by the caller.
+pub trait FileStorage {
+    fn read(&self, path: &str) -> Option<Vec<u8>>;
+    fn write(&self, path: &str, data: &[u8]) -> bool;
+    fn remove(&self, path: &str) -> bool;
+    fn list(&self) -> Vec<String>;
+}
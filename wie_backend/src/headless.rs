@@ -0,0 +1,259 @@
+mod audio_sink;
+mod clock;
+mod database;
+mod file_storage;
+mod screen;
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use crate::{
+    audio_sink::AudioSink, database::DatabaseRepository, device_profile::DeviceProfile, file_storage::FileStorage, platform::Platform,
+    screen::Screen, time::Instant,
+};
+
+pub use self::{
+    audio_sink::{AudioCapture, HeadlessAudioSink},
+    clock::VirtualClock,
+    database::{InMemoryDatabase, InMemoryDatabaseRepository},
+    file_storage::InMemoryFileStorage,
+    screen::{CapturedFrame, HeadlessScreen},
+};
+
+// Platform without any host dependency. Time only moves when the host advances `VirtualClock`,
+// so every tick and sleep is reproducible.
+pub struct HeadlessPlatform {
+    clock: VirtualClock,
+    screen: HeadlessScreen,
+    database_repository: InMemoryDatabaseRepository,
+    file_storage: InMemoryFileStorage,
+    audio_capture: Option<AudioCapture>,
+    device_profile: DeviceProfile,
+}
+
+impl HeadlessPlatform {
+    pub fn new(width: u32, height: u32, clock: VirtualClock) -> Self {
+        Self {
+            clock,
+            screen: HeadlessScreen::new(width, height),
+            database_repository: InMemoryDatabaseRepository::new(),
+            file_storage: InMemoryFileStorage::new(),
+            audio_capture: None,
+            device_profile: DeviceProfile::default().with_screen_size(width, height),
+        }
+    }
+
+    // screen is resized to the profile
+    pub fn with_device_profile(mut self, device_profile: DeviceProfile) -> Self {
+        self.screen = HeadlessScreen::new(device_profile.screen_width, device_profile.screen_height);
+        self.device_profile = device_profile;
+
+        self
+    }
+
+    pub fn with_audio_capture(mut self, audio_capture: AudioCapture) -> Self {
+        self.audio_capture = Some(audio_capture);
+
+        self
+    }
+
+    pub fn with_database_repository(mut self, database_repository: InMemoryDatabaseRepository) -> Self {
+        self.database_repository = database_repository;
+
+        self
+    }
+
+    pub fn with_file_storage(mut self, file_storage: InMemoryFileStorage) -> Self {
+        self.file_storage = file_storage;
+
+        self
+    }
+
+    pub fn clock(&self) -> VirtualClock {
+        self.clock.clone()
+    }
+
+    pub fn frames(&self) -> Rc<RefCell<Vec<CapturedFrame>>> {
+        self.screen.frames()
+    }
+
+    pub fn database_repository_handle(&self) -> InMemoryDatabaseRepository {
+        self.database_repository.clone()
+    }
+}
+
+impl Platform for HeadlessPlatform {
+    fn screen(&mut self) -> &mut dyn Screen {
+        &mut self.screen
+    }
+
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    fn is_clock_virtual(&self) -> bool {
+        true
+    }
+
+    fn database_repository(&self) -> &dyn DatabaseRepository {
+        &self.database_repository
+    }
+
+    fn file_storage(&self) -> &dyn FileStorage {
+        &self.file_storage
+    }
+
+    fn audio_sink(&self) -> Box<dyn AudioSink> {
+        Box::new(HeadlessAudioSink::new(self.audio_capture.clone()))
+    }
+
+    fn device_profile(&self) -> &DeviceProfile {
+        &self.device_profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc};
+    use core::cell::Cell;
+
+    use crate::{Event, HeadlessPlatform, KeyCode, System, TaskOptions, TaskState, VirtualClock};
+
+    #[test]
+    fn test_virtual_clock_sleep() -> anyhow::Result<()> {
+        let clock = VirtualClock::new(0);
+        let mut system = System::new(Box::new(HeadlessPlatform::new(240, 320, clock.clone())), Box::new(()));
+
+        let woken = Rc::new(Cell::new(false));
+
+        let woken_clone = woken.clone();
+        let mut system_clone = system.clone();
+        system.spawn(move || async move {
+            let until = system_clone.platform().now() + 100;
+            system_clone.sleep(until).await;
+
+            woken_clone.set(true);
+
+            anyhow::Ok(())
+        });
+
+        system.tick()?;
+        assert!(!woken.get());
+
+        clock.advance(99);
+        system.tick()?;
+        assert!(!woken.get());
+
+        clock.advance(1);
+        system.tick()?;
+        assert!(woken.get());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_event() -> anyhow::Result<()> {
+        let clock = VirtualClock::new(0);
+        let mut system = System::new(Box::new(HeadlessPlatform::new(240, 320, clock.clone())), Box::new(()));
+
+        let polls = Rc::new(Cell::new(0));
+        let received = Rc::new(Cell::new(None));
+
+        let polls_clone = polls.clone();
+        let received_clone = received.clone();
+        let system_clone = system.clone();
+        system.spawn(move || async move {
+            polls_clone.set(polls_clone.get() + 1);
+            let event = system_clone.next_event().await;
+            polls_clone.set(polls_clone.get() + 1);
+
+            if let Event::Keydown(x) = event {
+                received_clone.set(Some(x));
+            }
+
+            anyhow::Ok(())
+        });
+
+        // parked task must stay parked while time passes without events
+        system.tick()?;
+        clock.advance(100);
+        system.tick()?;
+        assert_eq!(polls.get(), 1);
+        assert_eq!(received.get(), None);
+
+        system.event_queue().push(Event::Keydown(KeyCode::OK));
+        system.tick()?;
+        assert_eq!(polls.get(), 2);
+        assert_eq!(received.get(), Some(KeyCode::OK));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_task_failure() -> anyhow::Result<()> {
+        let clock = VirtualClock::new(0);
+        let mut system = System::new(Box::new(HeadlessPlatform::new(240, 320, clock.clone())), Box::new(()));
+
+        let failing = system.spawn_with(TaskOptions::new("failing"), || async { Err::<(), _>(anyhow::anyhow!("boom")) });
+
+        let joined = Rc::new(Cell::new(false));
+
+        let joined_clone = joined.clone();
+        system.spawn(move || async move {
+            let result = failing.await;
+            joined_clone.set(result.is_err());
+
+            anyhow::Ok(())
+        });
+
+        // failure of a regular task doesn't stop the others
+        system.tick()?;
+        assert!(joined.get());
+        assert!(system.tasks().is_empty());
+
+        system.spawn_with(TaskOptions::new("main").critical(), || async { Err::<(), _>(anyhow::anyhow!("boom")) });
+        assert!(system.tick().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_task_cancel() -> anyhow::Result<()> {
+        let clock = VirtualClock::new(0);
+        let mut system = System::new(Box::new(HeadlessPlatform::new(240, 320, clock.clone())), Box::new(()));
+
+        let mut system_clone = system.clone();
+        let mut handle = system.spawn_with(TaskOptions::new("sleeper"), move || async move {
+            let until = system_clone.platform().now() + 100;
+            system_clone.sleep(until).await;
+
+            anyhow::Ok(1)
+        });
+
+        system.tick()?;
+
+        let tasks = system.tasks();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].name, "sleeper");
+        assert_eq!(tasks[0].state, TaskState::Sleeping(clock.now() + 100));
+
+        handle.cancel();
+        assert!(handle.is_finished());
+        assert!(system.tasks().is_empty());
+
+        let result = Rc::new(Cell::new(None));
+
+        let result_clone = result.clone();
+        system.spawn(move || async move {
+            result_clone.set(Some(handle.await.is_err()));
+
+            anyhow::Ok(())
+        });
+
+        clock.advance(100);
+        system.tick()?;
+        assert_eq!(result.get(), Some(true));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use alloc::{rc::Rc, vec, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{audio_sink::AudioSink, mixer::Mixer};
+
+// Handle to pull mixed audio in step with the virtual clock
+#[derive(Clone, Default)]
+pub struct AudioCapture {
+    mixer: Rc<RefCell<Option<Mixer>>>,
+}
+
+impl AudioCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sampling_rate(&self) -> u32 {
+        self.mixer.borrow().as_ref().map(|x| x.output_rate()).unwrap_or(0)
+    }
+
+    // interleaved stereo samples for the given duration, silence if nothing is playing
+    pub fn render(&self, millis: u64) -> Vec<i16> {
+        let mixer = self.mixer.borrow();
+        let Some(mixer) = mixer.as_ref() else {
+            return Vec::new();
+        };
+
+        let frames = (mixer.output_rate() as u64 * millis / 1000) as usize;
+        let mut result = vec![0; frames * 2];
+        mixer.mix(&mut result);
+
+        result
+    }
+}
+
+// Lets the host pull audio if a capture is given, discards it otherwise
+pub struct HeadlessAudioSink {
+    capture: Option<AudioCapture>,
+}
+
+impl HeadlessAudioSink {
+    pub fn new(capture: Option<AudioCapture>) -> Self {
+        Self { capture }
+    }
+}
+
+impl AudioSink for HeadlessAudioSink {
+    fn attach(&self, mixer: Mixer) {
+        if let Some(capture) = &self.capture {
+            *capture.mixer.borrow_mut() = Some(mixer);
+        } else {
+            mixer.discard();
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use alloc::rc::Rc;
+use core::cell::Cell;
+
+use crate::time::Instant;
+
+#[derive(Clone, Default)]
+pub struct VirtualClock {
+    now: Rc<Cell<u64>>,
+}
+
+impl VirtualClock {
+    pub fn new(epoch_millis: u64) -> Self {
+        Self {
+            now: Rc::new(Cell::new(epoch_millis)),
+        }
+    }
+
+    pub fn now(&self) -> Instant {
+        Instant::from_epoch_millis(self.now.get())
+    }
+
+    pub fn advance(&self, millis: u64) {
+        self.now.set(self.now.get() + millis);
+    }
+
+    pub fn set(&self, now: Instant) {
+        self.now.set(now.raw());
+    }
+}
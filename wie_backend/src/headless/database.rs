@@ -0,0 +1,67 @@
+use alloc::{collections::BTreeMap, rc::Rc, string::String};
+use core::cell::RefCell;
+
+use crate::database::{Database, DatabaseError, DatabaseFile, DatabaseRepository, DatabaseResult, RecordId};
+
+#[derive(Clone, Default)]
+pub struct InMemoryDatabaseRepository {
+    databases: Rc<RefCell<BTreeMap<String, Rc<RefCell<DatabaseFile>>>>>,
+}
+
+impl InMemoryDatabaseRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DatabaseRepository for InMemoryDatabaseRepository {
+    fn open(&self, name: &str, create: bool) -> DatabaseResult<Box<dyn Database>> {
+        let mut databases = self.databases.borrow_mut();
+
+        let file = match databases.get(name) {
+            Some(x) => x.clone(),
+            None if create => databases.entry(name.into()).or_default().clone(),
+            None => return Err(DatabaseError::NotFound),
+        };
+
+        Ok(Box::new(InMemoryDatabase { file }))
+    }
+
+    fn list(&self) -> DatabaseResult<Vec<String>> {
+        Ok(self.databases.borrow().keys().cloned().collect())
+    }
+
+    fn delete(&self, name: &str) -> DatabaseResult<()> {
+        self.databases.borrow_mut().remove(name).map(|_| ()).ok_or(DatabaseError::NotFound)
+    }
+}
+
+pub struct InMemoryDatabase {
+    file: Rc<RefCell<DatabaseFile>>,
+}
+
+impl Database for InMemoryDatabase {
+    fn add(&mut self, data: &[u8]) -> DatabaseResult<RecordId> {
+        self.file.borrow_mut().add(data)
+    }
+
+    fn get(&self, id: RecordId) -> DatabaseResult<Vec<u8>> {
+        self.file.borrow().get(id).map(|x| x.to_vec())
+    }
+
+    fn set(&mut self, id: RecordId, data: &[u8]) -> DatabaseResult<()> {
+        self.file.borrow_mut().set(id, data)
+    }
+
+    fn delete(&mut self, id: RecordId) -> DatabaseResult<()> {
+        self.file.borrow_mut().delete(id)
+    }
+
+    fn get_record_ids(&self) -> DatabaseResult<Vec<RecordId>> {
+        Ok(self.file.borrow().record_ids())
+    }
+
+    fn get_record_size(&self, id: RecordId) -> DatabaseResult<usize> {
+        self.file.borrow().get(id).map(|x| x.len())
+    }
+}
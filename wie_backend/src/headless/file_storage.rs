@@ -0,0 +1,35 @@
+use alloc::{collections::BTreeMap, rc::Rc, string::String};
+use core::cell::RefCell;
+
+use crate::file_storage::FileStorage;
+
+#[derive(Clone, Default)]
+pub struct InMemoryFileStorage {
+    files: Rc<RefCell<BTreeMap<String, Vec<u8>>>>,
+}
+
+impl InMemoryFileStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl FileStorage for InMemoryFileStorage {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        self.files.borrow().get(path).cloned()
+    }
+
+    fn write(&self, path: &str, data: &[u8]) -> bool {
+        self.files.borrow_mut().insert(path.into(), data.to_vec());
+
+        true
+    }
+
+    fn remove(&self, path: &str) -> bool {
+        self.files.borrow_mut().remove(path).is_some()
+    }
+
+    fn list(&self) -> Vec<String> {
+        self.files.borrow().keys().cloned().collect()
+    }
+}
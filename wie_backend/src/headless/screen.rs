@@ -0,0 +1,130 @@
+use alloc::rc::Rc;
+use core::cell::{Cell, RefCell};
+
+use crate::{
+    canvas::{ArgbPixel, Image, PixelType, Rect, VecImageBuffer},
+    screen::Screen,
+};
+
+#[derive(Clone)]
+pub struct CapturedFrame {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u32>, // argb
+}
+
+impl CapturedFrame {
+    pub fn empty() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            data: Vec::new(),
+        }
+    }
+
+    pub fn into_image(self) -> VecImageBuffer<ArgbPixel> {
+        VecImageBuffer::from_raw(self.width, self.height, self.data)
+    }
+
+    // copies `area` of `image` over the frame and returns the area updated,
+    // which is the whole image if its size differs from the frame
+    pub fn update(&mut self, image: &dyn Image, area: &Rect) -> Rect {
+        let (width, height) = (image.width(), image.height());
+        let bounds = Rect::new(0, 0, width as _, height as _);
+
+        let area = if self.width == width && self.height == height {
+            area.intersect(&bounds)
+        } else {
+            *self = Self {
+                width,
+                height,
+                data: vec![0; (width * height) as usize],
+            };
+
+            bounds
+        };
+
+        for y in area.y..area.bottom() {
+            for x in area.x..area.right() {
+                self.data[(y as u32 * width + x as u32) as usize] = ArgbPixel::from_color(image.get_pixel(x as _, y as _));
+            }
+        }
+
+        area
+    }
+}
+
+// every captured frame is the whole screen, with the painted area composed over the previous frame
+pub struct HeadlessScreen {
+    width: u32,
+    height: u32,
+    current: CapturedFrame,
+    frames: Rc<RefCell<Vec<CapturedFrame>>>,
+    redraw_requests: Cell<u32>,
+}
+
+impl HeadlessScreen {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            current: CapturedFrame::empty(),
+            frames: Rc::new(RefCell::new(Vec::new())),
+            redraw_requests: Cell::new(0),
+        }
+    }
+
+    pub fn frames(&self) -> Rc<RefCell<Vec<CapturedFrame>>> {
+        self.frames.clone()
+    }
+
+    pub fn redraw_requests(&self) -> u32 {
+        self.redraw_requests.get()
+    }
+}
+
+impl Screen for HeadlessScreen {
+    fn request_redraw(&self) -> anyhow::Result<()> {
+        self.redraw_requests.set(self.redraw_requests.get() + 1);
+
+        Ok(())
+    }
+
+    fn paint(&mut self, image: &dyn Image, area: &Rect) {
+        self.current.update(image, area);
+
+        self.frames.borrow_mut().push(self.current.clone());
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        canvas::{ArgbPixel, Rect, VecImageBuffer},
+        screen::Screen,
+    };
+
+    use super::HeadlessScreen;
+
+    #[test]
+    fn test_paint_area() {
+        let mut screen = HeadlessScreen::new(2, 2);
+
+        screen.paint(&VecImageBuffer::<ArgbPixel>::from_raw(2, 2, vec![1, 2, 3, 4]), &Rect::new(1, 1, 1, 1));
+        screen.paint(&VecImageBuffer::<ArgbPixel>::from_raw(2, 2, vec![5, 6, 7, 8]), &Rect::new(1, 0, 1, 1));
+
+        // first paint always covers the whole screen
+        let frames = screen.frames();
+        let frames = frames.borrow();
+        assert_eq!(frames[0].data, [1, 2, 3, 4]);
+        assert_eq!(frames[1].data, [1, 6, 3, 4]);
+    }
+}
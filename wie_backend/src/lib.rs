@@ -3,8 +3,15 @@ extern crate alloc;
 mod audio_sink;
 pub mod canvas;
 mod database;
+mod debugger;
+mod device_profile;
+mod encoding;
 mod executor;
+mod file_storage;
+mod headless;
+mod mixer;
 mod platform;
+mod save_data;
 mod screen;
 mod system;
 mod task;
@@ -12,11 +19,25 @@ mod time;
 
 pub use self::{
     audio_sink::AudioSink,
-    database::{Database, DatabaseRepository, RecordId},
+    database::{Database, DatabaseError, DatabaseFile, DatabaseRepository, DatabaseResult, RecordId},
+    debugger::Debugger,
+    device_profile::{Carrier, DeviceProfile, KeyLayout},
+    encoding::TextEncoding,
     executor::AsyncCallable,
+    file_storage::FileStorage,
+    headless::{
+        AudioCapture, CapturedFrame, HeadlessAudioSink, HeadlessPlatform, HeadlessScreen, InMemoryDatabase, InMemoryDatabaseRepository,
+        InMemoryFileStorage, VirtualClock,
+    },
+    mixer::{Mixer, StreamId},
     platform::Platform,
+    save_data::SaveData,
     screen::Screen,
-    system::{Event, KeyCode, System},
+    system::{
+        normalize_path, AudioError, AudioHandle, Event, KeyCode, NextEventFuture, PcmFormat, PlaybackCompletion, PlaybackHandle, PlaybackStatus,
+        SynthChip, System, SystemState, Vfs,
+    },
+    task::{JoinHandle, TaskInfo, TaskOptions, TaskState},
     time::Instant,
 };
 
@@ -26,14 +47,46 @@ pub trait App {
     fn start(&mut self) -> anyhow::Result<()>;
     fn on_event(&mut self, event: Event);
     fn tick(&mut self) -> anyhow::Result<()>;
+
+    fn save_state(&mut self) -> anyhow::Result<Vec<u8>> {
+        anyhow::bail!("Save state is not supported on this platform")
+    }
+
+    fn load_state(&mut self, _data: &[u8]) -> anyhow::Result<()> {
+        anyhow::bail!("Save state is not supported on this platform")
+    }
+
+    fn debugger(&mut self) -> Option<Box<dyn Debugger>> {
+        None
+    }
 }
 
 pub trait Archive {
     fn id(&self) -> String;
+    fn encoding(&self) -> TextEncoding;
+    fn set_encoding(&mut self, encoding: TextEncoding);
+
+    // best guess from the archive, the host may override it
+    fn device_profile(&self) -> DeviceProfile {
+        DeviceProfile::default()
+    }
+
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>>;
 }
 
 pub fn extract_zip(zip: &[u8]) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
+    extract_zip_inner(zip, |file| file.name().to_string())
+}
+
+// file names without utf-8 flag are stored in the encoding of the platform which made the zip
+pub fn extract_zip_with_encoding(zip: &[u8], encoding: TextEncoding) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
+    extract_zip_inner(zip, |file| match core::str::from_utf8(file.name_raw()) {
+        Ok(x) => x.to_string(),
+        Err(_) => encoding.decode(file.name_raw()),
+    })
+}
+
+fn extract_zip_inner(zip: &[u8], name: impl Fn(&zip::read::ZipFile) -> String) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
     use std::io::{Cursor, Read};
     use zip::ZipArchive;
 
@@ -46,7 +99,7 @@ pub fn extract_zip(zip: &[u8]) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
             let mut data = Vec::new();
             file.read_to_end(&mut data)?;
 
-            Ok((file.name().to_string(), data))
+            Ok((name(&file), data))
         })
         .collect::<anyhow::Result<_>>()
 }
@@ -0,0 +1,327 @@
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    vec::Vec,
+};
+use std::sync::Mutex;
+
+pub type StreamId = u32;
+
+pub const MIXER_CHANNELS: u8 = 2;
+const SOFT_CLIP_THRESHOLD: f32 = 0.75;
+
+struct Segment {
+    channels: u8,
+    sampling_rate: u32,
+    samples: Vec<i16>,
+    frame: usize,
+}
+
+impl Segment {
+    fn frame_count(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    fn stereo_frame(&self, frame: usize) -> (f32, f32) {
+        let base = frame * self.channels as usize;
+        let left = self.samples[base] as f32;
+        let right = if self.channels > 1 { self.samples[base + 1] as f32 } else { left };
+
+        (left, right)
+    }
+}
+
+struct Stream {
+    segments: VecDeque<Segment>,
+    phase: u32, // position between two input frames, in units of 1 / output rate
+    closed: bool,
+    volume: f32,
+    paused: bool,
+}
+
+impl Default for Stream {
+    fn default() -> Self {
+        Self {
+            segments: VecDeque::new(),
+            phase: 0,
+            closed: false,
+            volume: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl Stream {
+    // linear resampling to the output rate
+    fn next_frame(&mut self, output_rate: u32) -> Option<(f32, f32)> {
+        loop {
+            let segment = self.segments.front_mut()?;
+            if segment.frame < segment.frame_count() {
+                break;
+            }
+            self.segments.pop_front();
+        }
+
+        let segment = self.segments.front_mut().unwrap();
+
+        let current = segment.stereo_frame(segment.frame);
+        let next = if segment.frame + 1 < segment.frame_count() {
+            segment.stereo_frame(segment.frame + 1)
+        } else {
+            current
+        };
+        let weight = self.phase as f32 / output_rate as f32;
+
+        self.phase += segment.sampling_rate;
+        while self.phase >= output_rate {
+            self.phase -= output_rate;
+            segment.frame += 1;
+        }
+
+        Some((current.0 + (next.0 - current.0) * weight, current.1 + (next.1 - current.1) * weight))
+    }
+
+    fn is_drained(&self) -> bool {
+        self.segments.iter().all(|x| x.frame >= x.frame_count())
+    }
+}
+
+struct MixerInner {
+    output_rate: u32,
+    streams: BTreeMap<StreamId, Stream>,
+    last_stream_id: StreamId,
+    discarding: bool,
+}
+
+// Mixes any number of concurrent streams into one interleaved stereo output.
+// Producers queue samples at their own rate, and the platform pulls mixed samples from its audio thread.
+#[derive(Clone)]
+pub struct Mixer {
+    inner: Arc<Mutex<MixerInner>>,
+}
+
+impl Mixer {
+    pub fn new(output_rate: u32) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(MixerInner {
+                output_rate,
+                streams: BTreeMap::new(),
+                last_stream_id: 0,
+                discarding: false,
+            })),
+        }
+    }
+
+    pub fn output_rate(&self) -> u32 {
+        self.inner.lock().unwrap().output_rate
+    }
+
+    pub fn open_stream(&self) -> StreamId {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.last_stream_id += 1;
+        let stream_id = inner.last_stream_id;
+        inner.streams.insert(stream_id, Stream::default());
+
+        stream_id
+    }
+
+    // samples already queued are played before the stream goes away
+    pub fn close_stream(&self, stream_id: StreamId) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(stream) = inner.streams.get_mut(&stream_id) {
+            stream.closed = true;
+            if stream.is_drained() {
+                inner.streams.remove(&stream_id);
+            }
+        }
+    }
+
+    // volume and pause apply to samples already queued, so they take effect right away
+    pub fn set_volume(&self, stream_id: StreamId, volume: f32) {
+        if let Some(stream) = self.inner.lock().unwrap().streams.get_mut(&stream_id) {
+            stream.volume = volume.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn set_paused(&self, stream_id: StreamId, paused: bool) {
+        if let Some(stream) = self.inner.lock().unwrap().streams.get_mut(&stream_id) {
+            stream.paused = paused;
+        }
+    }
+
+    // drops the stream right away, including samples not played yet
+    pub fn remove_stream(&self, stream_id: StreamId) {
+        self.inner.lock().unwrap().streams.remove(&stream_id);
+    }
+
+    // for sinks without an output, nothing would ever pull queued samples out otherwise
+    pub fn discard(&self) {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.discarding = true;
+        inner.streams.retain(|_, x| {
+            x.segments.clear();
+
+            !x.closed
+        });
+    }
+
+    pub fn queue(&self, stream_id: StreamId, channels: u8, sampling_rate: u32, samples: &[i16]) {
+        if channels == 0 || sampling_rate == 0 || samples.is_empty() {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.discarding {
+            return;
+        }
+
+        if let Some(stream) = inner.streams.get_mut(&stream_id) {
+            stream.segments.push_back(Segment {
+                channels,
+                sampling_rate,
+                samples: samples.to_vec(),
+                frame: 0,
+            });
+        }
+    }
+
+    // fire and forget sound, overlapping everything else
+    pub fn play(&self, channels: u8, sampling_rate: u32, samples: &[i16]) {
+        let stream_id = self.open_stream();
+
+        self.queue(stream_id, channels, sampling_rate, samples);
+        self.close_stream(stream_id);
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.inner.lock().unwrap().streams.values().all(|x| x.is_drained())
+    }
+
+    // fills `output` with interleaved stereo samples at the output rate, silence if nothing is playing
+    pub fn mix(&self, output: &mut [i16]) {
+        let mut inner = self.inner.lock().unwrap();
+        let output_rate = inner.output_rate;
+
+        for frame in output.chunks_exact_mut(MIXER_CHANNELS as usize) {
+            let mut left = 0.0;
+            let mut right = 0.0;
+
+            for stream in inner.streams.values_mut().filter(|x| !x.paused) {
+                if let Some((l, r)) = stream.next_frame(output_rate) {
+                    left += l * stream.volume;
+                    right += r * stream.volume;
+                }
+            }
+
+            frame[0] = Self::soft_clip(left);
+            frame[1] = Self::soft_clip(right);
+        }
+
+        inner.streams.retain(|_, x| !(x.closed && x.is_drained()));
+    }
+
+    // samples below the threshold pass through, louder ones are compressed smoothly instead of wrapping or clipping hard
+    fn soft_clip(sample: f32) -> i16 {
+        let normalized = sample / i16::MAX as f32;
+        let magnitude = normalized.abs();
+
+        let clipped = if magnitude <= SOFT_CLIP_THRESHOLD {
+            magnitude
+        } else {
+            let headroom = 1.0 - SOFT_CLIP_THRESHOLD;
+
+            SOFT_CLIP_THRESHOLD + headroom * ((magnitude - SOFT_CLIP_THRESHOLD) / headroom).tanh()
+        };
+
+        (clipped.copysign(normalized) * i16::MAX as f32).round() as i16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::Mixer;
+
+    #[test]
+    fn test_resample() {
+        let mixer = Mixer::new(4000);
+
+        let stream = mixer.open_stream();
+        mixer.queue(stream, 1, 2000, &[0, 1000, 2000]);
+        mixer.close_stream(stream);
+
+        let mut output = vec![0; 16];
+        mixer.mix(&mut output);
+
+        assert_eq!(output, [0, 0, 500, 500, 1000, 1000, 1500, 1500, 2000, 2000, 2000, 2000, 0, 0, 0, 0]);
+        assert!(mixer.is_idle());
+    }
+
+    #[test]
+    fn test_mix() {
+        let mixer = Mixer::new(1000);
+
+        let stream = mixer.open_stream();
+        mixer.queue(stream, 2, 1000, &[1000, -1000, 1000, -1000]);
+        mixer.queue(stream, 2, 1000, &[2000, -2000]);
+        mixer.play(1, 1000, &[30000, 30000, 100]);
+
+        let mut output = vec![0; 8];
+        mixer.mix(&mut output);
+
+        // loud sums are compressed but keep their sign
+        assert!(output[0] < 31000 && output[0] > output[1]);
+        assert!(output[1] < 29000 && output[1] > 24576);
+        assert_eq!(&output[4..], [2100, -1900, 0, 0]);
+
+        mixer.remove_stream(stream);
+        assert!(mixer.is_idle());
+    }
+
+    #[test]
+    fn test_volume_pause() {
+        let mixer = Mixer::new(1000);
+
+        let stream = mixer.open_stream();
+        mixer.queue(stream, 1, 1000, &[1000; 4]);
+
+        mixer.set_volume(stream, 0.5);
+        let mut output = vec![0; 2];
+        mixer.mix(&mut output);
+        assert_eq!(output, [500, 500]);
+
+        // paused streams keep their samples
+        mixer.set_paused(stream, true);
+        mixer.mix(&mut output);
+        assert_eq!(output, [0, 0]);
+
+        mixer.set_paused(stream, false);
+        mixer.set_volume(stream, 1.0);
+        let mut output = vec![0; 8];
+        mixer.mix(&mut output);
+        assert_eq!(output, [1000, 1000, 1000, 1000, 1000, 1000, 0, 0]);
+    }
+
+    #[test]
+    fn test_discard() {
+        let mixer = Mixer::new(1000);
+
+        let stream = mixer.open_stream();
+        mixer.queue(stream, 1, 1000, &[1000; 100]);
+        mixer.play(1, 1000, &[1000; 100]);
+
+        mixer.discard();
+        mixer.queue(stream, 1, 1000, &[1000; 100]);
+        mixer.play(1, 1000, &[1000; 100]);
+
+        assert!(mixer.is_idle());
+        assert_eq!(mixer.inner.lock().unwrap().streams.len(), 1);
+
+        mixer.close_stream(stream);
+        assert!(mixer.inner.lock().unwrap().streams.is_empty());
+    }
+}
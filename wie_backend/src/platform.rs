@@ -1,8 +1,14 @@
-use crate::{audio_sink::AudioSink, database::DatabaseRepository, screen::Screen, time::Instant};
+use crate::{
+    audio_sink::AudioSink, database::DatabaseRepository, device_profile::DeviceProfile, file_storage::FileStorage, screen::Screen, time::Instant,
+};
 
 pub trait Platform {
     fn screen(&mut self) -> &mut dyn Screen;
     fn now(&self) -> Instant;
+    // time only moves when the host advances it, so ticks can't wait for it to pass
+    fn is_clock_virtual(&self) -> bool;
     fn database_repository(&self) -> &dyn DatabaseRepository;
+    fn file_storage(&self) -> &dyn FileStorage;
     fn audio_sink(&self) -> Box<dyn AudioSink>;
+    fn device_profile(&self) -> &DeviceProfile;
 }
@@ -0,0 +1,301 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use std::io::{Cursor, Write};
+
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    database::{DatabaseRepository, RecordId},
+    extract_zip,
+    file_storage::FileStorage,
+    normalize_path,
+};
+
+const SAVE_DATA_MANIFEST: &str = "manifest";
+const SAVE_DATA_FORMAT: &str = "wie-save-data 1";
+
+// Everything an app has persisted, as a portable zip archive:
+//   manifest                    `wie-save-data 1` line, then `app <app id>` line
+//   databases/<name>/           one directory per database, so empty ones survive the round trip
+//   databases/<name>/<record>   record data, file name is the decimal record id
+//   files/<path>                files written by the app, with normalized paths
+// Anything else in the archive is ignored.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SaveData {
+    pub app_id: String,
+    pub databases: BTreeMap<String, BTreeMap<RecordId, Vec<u8>>>,
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+impl SaveData {
+    pub fn collect(app_id: &str, database_repository: &dyn DatabaseRepository, file_storage: &dyn FileStorage) -> anyhow::Result<Self> {
+        let mut databases = BTreeMap::new();
+        for name in database_repository.list()? {
+            let database = database_repository.open(&name, false)?;
+
+            let records = database
+                .get_record_ids()?
+                .into_iter()
+                .map(|x| Ok((x, database.get(x)?)))
+                .collect::<anyhow::Result<_>>()?;
+
+            databases.insert(name, records);
+        }
+
+        let files = file_storage
+            .list()
+            .into_iter()
+            .map(|x| {
+                let data = file_storage.read(&x).ok_or_else(|| anyhow::anyhow!("Failed to read {}", x))?;
+
+                Ok((x, data))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            app_id: app_id.into(),
+            databases,
+            files,
+        })
+    }
+
+    // replaces whatever the app had saved before, which is put back if writing fails halfway
+    pub fn restore(&self, database_repository: &dyn DatabaseRepository, file_storage: &dyn FileStorage) -> anyhow::Result<()> {
+        self.validate()?;
+
+        let previous = Self::collect(&self.app_id, database_repository, file_storage)?;
+        if let Err(x) = self.write(database_repository, file_storage) {
+            if let Err(e) = previous.write(database_repository, file_storage) {
+                tracing::error!("Failed to roll back save data: {}", e);
+            }
+
+            return Err(x);
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        for name in self.databases.keys() {
+            validate_database_name(name)?;
+        }
+
+        for path in self.files.keys() {
+            if &sanitize_file_path(path)? != path {
+                anyhow::bail!("Invalid file path {}", path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write(&self, database_repository: &dyn DatabaseRepository, file_storage: &dyn FileStorage) -> anyhow::Result<()> {
+        Self::wipe(database_repository, file_storage)?;
+
+        for (name, records) in &self.databases {
+            let mut database = database_repository.open(name, true)?;
+
+            for (&id, data) in records {
+                database.set(id, data)?;
+            }
+        }
+
+        for (path, data) in &self.files {
+            if !file_storage.write(path, data) {
+                anyhow::bail!("Failed to write {}", path);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn wipe(database_repository: &dyn DatabaseRepository, file_storage: &dyn FileStorage) -> anyhow::Result<()> {
+        for name in database_repository.list()? {
+            database_repository.delete(&name)?;
+        }
+
+        for path in file_storage.list() {
+            if !file_storage.remove(&path) {
+                anyhow::bail!("Failed to remove {}", path);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_archive(&self) -> anyhow::Result<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default();
+
+        writer.start_file(SAVE_DATA_MANIFEST, options)?;
+        write!(writer, "{}\napp {}\n", SAVE_DATA_FORMAT, self.app_id)?;
+
+        for (name, records) in &self.databases {
+            writer.add_directory(format!("databases/{}/", name), options)?;
+
+            for (id, data) in records {
+                writer.start_file(format!("databases/{}/{}", name, id), options)?;
+                writer.write_all(data)?;
+            }
+        }
+
+        for (path, data) in &self.files {
+            writer.start_file(format!("files/{}", path), options)?;
+            writer.write_all(data)?;
+        }
+
+        Ok(writer.finish()?.into_inner())
+    }
+
+    pub fn from_archive(data: &[u8]) -> anyhow::Result<Self> {
+        let entries = extract_zip(data)?;
+
+        let manifest = entries
+            .get(SAVE_DATA_MANIFEST)
+            .ok_or_else(|| anyhow::anyhow!("Not a save data archive"))?;
+        let manifest = core::str::from_utf8(manifest)?;
+
+        let mut lines = manifest.lines();
+        if lines.next() != Some(SAVE_DATA_FORMAT) {
+            anyhow::bail!("Unsupported save data format");
+        }
+        let app_id = lines
+            .find_map(|x| x.strip_prefix("app "))
+            .ok_or_else(|| anyhow::anyhow!("Missing app id in save data"))?;
+
+        let mut result = Self {
+            app_id: app_id.into(),
+            ..Default::default()
+        };
+
+        for (path, data) in entries {
+            if let Some(x) = path.strip_prefix("databases/") {
+                let (name, record) = x.rsplit_once('/').ok_or_else(|| anyhow::anyhow!("Invalid database entry {}", path))?;
+                validate_database_name(name)?;
+
+                let records = result.databases.entry(name.into()).or_default();
+
+                // directory entry
+                if record.is_empty() {
+                    continue;
+                }
+
+                let id = record.parse().map_err(|_| anyhow::anyhow!("Invalid record id {}", path))?;
+                records.insert(id, data);
+            } else if let Some(x) = path.strip_prefix("files/") {
+                if !x.is_empty() && !x.ends_with('/') {
+                    result.files.insert(sanitize_file_path(x)?, data);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+// names and paths in an archive are untrusted, they must not point outside the storage of the app
+fn validate_database_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || name.contains(['/', '\\', ':']) || name.starts_with('.') {
+        anyhow::bail!("Invalid database name {}", name);
+    }
+
+    Ok(())
+}
+
+fn sanitize_file_path(path: &str) -> anyhow::Result<String> {
+    let is_absolute = path.starts_with(['/', '\\']);
+    let has_unsafe_component = path.split(['/', '\\']).any(|x| x == ".." || x.contains(':'));
+
+    let normalized = normalize_path(path);
+    if is_absolute || has_unsafe_component || normalized.is_empty() {
+        anyhow::bail!("Invalid file path {}", path);
+    }
+
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{DatabaseRepository, FileStorage, InMemoryDatabaseRepository, InMemoryFileStorage};
+
+    use super::SaveData;
+
+    fn archive_with(path: &str) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("manifest", FileOptions::default()).unwrap();
+        writer.write_all(b"wie-save-data 1\napp app\n").unwrap();
+        writer.start_file(path, FileOptions::default()).unwrap();
+        writer.write_all(b"evil").unwrap();
+
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_round_trip() -> anyhow::Result<()> {
+        let database_repository = InMemoryDatabaseRepository::new();
+        let file_storage = InMemoryFileStorage::new();
+
+        let mut database = database_repository.open("score", true)?;
+        database.add(b"100")?;
+        database.set(3, b"200")?;
+        database_repository.open("empty", true)?;
+        file_storage.write("slot/1.sav", b"progress");
+
+        let save_data = SaveData::collect("app", &database_repository, &file_storage)?;
+        let archive = save_data.to_archive()?;
+        assert_eq!(SaveData::from_archive(&archive)?, save_data);
+
+        // import replaces existing data
+        let other_repository = InMemoryDatabaseRepository::new();
+        let other_storage = InMemoryFileStorage::new();
+        other_repository.open("stale", true)?;
+        other_storage.write("stale.sav", b"");
+
+        SaveData::from_archive(&archive)?.restore(&other_repository, &other_storage)?;
+        assert_eq!(SaveData::collect("app", &other_repository, &other_storage)?, save_data);
+        assert_eq!(other_repository.open("score", false)?.get_record_ids()?, [0, 3]);
+
+        SaveData::wipe(&other_repository, &other_storage)?;
+        assert!(other_repository.list()?.is_empty() && other_storage.list().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_archive() {
+        assert!(SaveData::from_archive(b"not a zip").is_err());
+        assert!(SaveData::from_archive(&SaveData::default().to_archive().unwrap()[..10]).is_err());
+    }
+
+    #[test]
+    fn test_malicious_archive() -> anyhow::Result<()> {
+        for path in [
+            "files/../../x",
+            "files//etc/x",
+            "files/a/../../x",
+            "files/c:/x",
+            "databases/../x/0",
+            "databases//0",
+        ] {
+            assert!(SaveData::from_archive(&archive_with(path)).is_err(), "{}", path);
+        }
+
+        // rejected before anything existing is touched
+        let database_repository = InMemoryDatabaseRepository::new();
+        let file_storage = InMemoryFileStorage::new();
+        database_repository.open("score", true)?.add(b"100")?;
+        file_storage.write("slot/1.sav", b"progress");
+
+        let mut save_data = SaveData::default();
+        save_data.files.insert("../escape.sav".into(), b"evil".to_vec());
+        assert!(save_data.restore(&database_repository, &file_storage).is_err());
+
+        assert_eq!(database_repository.list()?, ["score"]);
+        assert_eq!(file_storage.read("slot/1.sav").unwrap(), b"progress");
+
+        Ok(())
+    }
+}
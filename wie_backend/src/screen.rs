@@ -1,8 +1,9 @@
-use crate::canvas::Image;
+use crate::canvas::{Image, Rect};
 
 pub trait Screen {
     fn request_redraw(&self) -> anyhow::Result<()>;
-    fn paint(&mut self, image: &dyn Image);
+    // only `area` of `image` has changed since the last paint, the rest of the screen is kept as is
+    fn paint(&mut self, image: &dyn Image, area: &Rect);
     fn width(&self) -> u32;
     fn height(&self) -> u32;
 }
@@ -1,33 +1,51 @@
 mod audio;
 mod event_queue;
 mod resource;
+mod vfs;
 
-use alloc::rc::Rc;
+use alloc::{collections::VecDeque, rc::Rc};
 use core::{
     any::Any,
-    cell::{Ref, RefCell, RefMut},
+    cell::{Cell, Ref, RefCell, RefMut},
     fmt::Debug,
 };
+use std::collections::HashMap;
+
+use wie_util::{SnapshotReader, SnapshotWriter};
 
 use crate::{
+    canvas::ImageFrameCache,
     executor::Executor,
     platform::Platform,
-    task::{SleepFuture, YieldFuture},
-    AsyncCallable, Instant,
+    task::{JoinHandle, SleepFuture, TaskInfo, TaskOptions, YieldFuture},
+    AsyncCallable, Instant, TextEncoding,
 };
 
 use self::{audio::Audio, event_queue::EventQueue, resource::Resource};
 
-pub use self::event_queue::{Event, KeyCode};
+pub use self::{
+    audio::{AudioError, AudioHandle, PcmFormat, PlaybackCompletion, PlaybackHandle, PlaybackStatus, SynthChip},
+    event_queue::{Event, KeyCode, NextEventFuture},
+    vfs::{normalize_path, Vfs},
+};
+
+// Save state read up front, so nothing is changed if the snapshot turns out to be invalid
+pub struct SystemState {
+    sleeping_tasks: HashMap<usize, Instant>,
+    events: VecDeque<Event>,
+}
 
 #[derive(Clone)]
 pub struct System {
     executor: Executor,
     platform: Rc<RefCell<Box<dyn Platform>>>,
     resource: Rc<RefCell<Resource>>,
+    vfs: Rc<RefCell<Vfs>>,
     event_queue: Rc<RefCell<EventQueue>>,
     audio: Option<Rc<RefCell<Audio>>>,
+    image_frames: Rc<RefCell<ImageFrameCache>>,
     context: Rc<RefCell<Box<dyn Any>>>,
+    encoding: Rc<Cell<TextEncoding>>,
 }
 
 impl System {
@@ -35,14 +53,19 @@ impl System {
         let audio_sink = platform.audio_sink();
 
         let platform = Rc::new(RefCell::new(platform));
+        let resource = Rc::new(RefCell::new(Resource::new()));
+        let vfs = Vfs::new(platform.clone(), resource.clone());
 
         let mut result = Self {
             executor: Executor::new(),
             platform: platform.clone(),
-            resource: Rc::new(RefCell::new(Resource::new())),
+            resource,
+            vfs: Rc::new(RefCell::new(vfs)),
             event_queue: Rc::new(RefCell::new(EventQueue::new())),
             audio: None,
+            image_frames: Rc::new(RefCell::new(ImageFrameCache::new())),
             context: Rc::new(RefCell::new(context)),
+            encoding: Rc::new(Cell::new(TextEncoding::EucKr)),
         };
 
         // late initialization
@@ -53,40 +76,69 @@ impl System {
 
     pub fn tick(&mut self) -> anyhow::Result<()> {
         let platform = self.platform.clone();
-        self.executor.tick(move || {
-            let platform = platform.borrow();
+        let virtual_clock = platform.borrow().is_clock_virtual();
 
-            platform.now()
-        })
+        self.executor.tick(
+            move || {
+                let platform = platform.borrow();
+
+                platform.now()
+            },
+            virtual_clock,
+        )
     }
 
-    pub fn spawn<C, R, E>(&mut self, callable: C)
+    pub fn spawn<C, R, E>(&mut self, callable: C) -> JoinHandle<R>
     where
         C: AsyncCallable<R, E> + 'static,
+        R: 'static,
         E: Debug,
     {
-        self.executor.spawn(callable);
+        self.executor.spawn(callable)
+    }
+
+    pub fn spawn_with<C, R, E>(&mut self, options: TaskOptions, callable: C) -> JoinHandle<R>
+    where
+        C: AsyncCallable<R, E> + 'static,
+        R: 'static,
+        E: Debug,
+    {
+        self.executor.spawn_with(options, callable)
+    }
+
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        self.executor.tasks()
     }
 
     pub fn sleep(&mut self, until: Instant) -> SleepFuture {
-        SleepFuture::new(until, &mut self.executor)
+        SleepFuture::new(until, &self.executor)
     }
 
     pub fn yield_now(&self) -> YieldFuture {
-        YieldFuture {}
+        YieldFuture::new()
     }
 
-    // TODO add encoding configuration..
-    pub fn encode_str(&self, string: &str) -> Vec<u8> {
-        use encoding_rs::EUC_KR;
+    // resolves as soon as an event is pushed, without polling the queue in between
+    pub fn next_event(&self) -> NextEventFuture {
+        NextEventFuture::new(self.event_queue.clone())
+    }
 
-        EUC_KR.encode(string).0.to_vec()
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding.get()
     }
 
-    pub fn decode_str(&self, bytes: &[u8]) -> String {
-        use encoding_rs::EUC_KR;
+    pub fn set_encoding(&self, encoding: TextEncoding) {
+        tracing::debug!("Using {} encoding", encoding.name());
 
-        EUC_KR.decode(bytes).0.to_string()
+        self.encoding.set(encoding)
+    }
+
+    pub fn encode_str(&self, string: &str) -> Vec<u8> {
+        self.encoding.get().encode(string)
+    }
+
+    pub fn decode_str(&self, bytes: &[u8]) -> String {
+        self.encoding.get().decode(bytes)
     }
 
     pub fn resource(&self) -> Ref<'_, Resource> {
@@ -97,6 +149,10 @@ impl System {
         self.resource.borrow_mut()
     }
 
+    pub fn vfs(&self) -> RefMut<'_, Vfs> {
+        self.vfs.borrow_mut()
+    }
+
     pub fn platform(&self) -> RefMut<'_, Box<dyn Platform>> {
         self.platform.borrow_mut()
     }
@@ -108,7 +164,35 @@ impl System {
     pub fn event_queue(&self) -> RefMut<'_, EventQueue> {
         self.event_queue.borrow_mut()
     }
+
+    pub fn image_frames(&self) -> RefMut<'_, ImageFrameCache> {
+        self.image_frames.borrow_mut()
+    }
+
     pub fn context(&self) -> RefMut<'_, Box<dyn Any>> {
         self.context.borrow_mut()
     }
+
+    pub fn save_state(&self, writer: &mut SnapshotWriter) {
+        let now = self.platform.borrow().now();
+
+        self.executor.save_state(writer, now);
+        self.event_queue.borrow().save_state(writer);
+    }
+
+    pub fn read_state(&self, reader: &mut SnapshotReader) -> anyhow::Result<SystemState> {
+        let now = self.platform.borrow().now();
+
+        Ok(SystemState {
+            sleeping_tasks: Executor::read_state(reader, now)?,
+            events: EventQueue::read_state(reader)?,
+        })
+    }
+
+    pub fn restore_state(&mut self, state: SystemState) {
+        self.executor.restore_state(state.sleeping_tasks);
+        self.event_queue.borrow_mut().restore_state(state.events);
+        // sources in the restored memory may differ
+        self.image_frames.borrow_mut().clear();
+    }
 }
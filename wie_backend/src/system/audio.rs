@@ -1,51 +1,39 @@
-use alloc::collections::BTreeMap;
-use core::{result::Result, time::Duration};
+mod midi;
+mod playback;
+mod synth;
+mod wave;
 
-use smaf::Smaf;
-use smaf_player::{play_smaf, AudioBackend};
+use alloc::{collections::BTreeMap, rc::Rc};
+use core::{
+    cell::{Cell, RefCell},
+    result::Result,
+};
 
-use crate::{audio_sink::AudioSink, System};
+use crate::{
+    audio_sink::AudioSink,
+    mixer::{Mixer, StreamId},
+    task::JoinHandle,
+    System, TaskOptions,
+};
 
-struct AudioBackendImpl {
-    system: System,
-    sink: Box<dyn AudioSink>,
-}
-
-#[async_trait::async_trait(?Send)]
-impl AudioBackend for AudioBackendImpl {
-    fn play_wave(&self, channel: u8, sampling_rate: u32, wave_data: &[i16]) {
-        self.sink.play_wave(channel, sampling_rate, wave_data);
-    }
-
-    fn midi_note_on(&self, _channel_id: u8, _note: u8, _velocity: u8) {
-        // TODO
-    }
-
-    fn midi_note_off(&self, _channel_id: u8, _note: u8, _velocity: u8) {
-        // TODO
-    }
+use self::{
+    midi::StandardMidiFile,
+    playback::{PlaybackBackend, PlaybackState, MAX_VOLUME},
+    wave::Pcm,
+};
 
-    fn midi_program_change(&self, _channel_id: u8, _program: u8) {
-        // TODO
-    }
-
-    fn midi_control_change(&self, _channel_id: u8, _control: u8, _value: u8) {
-        // TODO
-    }
-
-    async fn sleep(&self, duration: Duration) {
-        let now = self.system.platform().now();
-        let end = now + duration.as_millis() as _;
+pub use self::{
+    playback::{PlaybackCompletion, PlaybackStatus},
+    synth::SynthChip,
+    wave::PcmFormat,
+};
 
-        self.system.clone().sleep(end).await
-    }
-
-    fn now_millis(&self) -> u64 {
-        self.system.platform().now().raw()
-    }
-}
+const MIXER_SAMPLE_RATE: u32 = 44100;
 
 pub type AudioHandle = u32;
+pub type PlaybackHandle = u32;
+
+#[derive(Debug)]
 pub enum AudioError {
     InvalidHandle,
     InvalidAudio,
@@ -53,41 +41,361 @@ pub enum AudioError {
 
 enum AudioFile {
     Smaf(Vec<u8>),
+    Midi(StandardMidiFile),
+    Pcm(Pcm),
+}
+
+struct Playback {
+    file: Rc<AudioFile>,
+    state: Rc<RefCell<PlaybackState>>,
+    task: JoinHandle<()>,
+    stream: StreamId,
 }
 
 pub struct Audio {
-    backend: AudioBackendImpl,
-    files: BTreeMap<AudioHandle, AudioFile>,
+    system: System,
+    mixer: Mixer,
+    _sink: Box<dyn AudioSink>, // owns the host output, which has to live as long as we do
+    files: BTreeMap<AudioHandle, Rc<AudioFile>>,
+    playbacks: BTreeMap<PlaybackHandle, Playback>,
     last_audio_handle: AudioHandle,
+    last_playback_handle: PlaybackHandle,
+    synth_chip: SynthChip,
+    muted: Rc<Cell<bool>>,
 }
 
 impl Audio {
     pub fn new(sink: Box<dyn AudioSink>, system: System) -> Self {
+        let mixer = Mixer::new(MIXER_SAMPLE_RATE);
+        sink.attach(mixer.clone());
+
         Self {
-            backend: AudioBackendImpl { sink, system },
+            system,
+            mixer,
+            _sink: sink,
             files: BTreeMap::new(),
+            playbacks: BTreeMap::new(),
             last_audio_handle: 0,
+            last_playback_handle: 0,
+            synth_chip: SynthChip::Ma3,
+            muted: Rc::new(Cell::new(false)),
+        }
+    }
+
+    // applies to playbacks started after the change
+    pub fn set_synth_chip(&mut self, chip: SynthChip) {
+        self.synth_chip = chip;
+    }
+
+    // detects SMAF, standard MIDI and RIFF WAVE by their signatures
+    pub fn load(&mut self, data: &[u8]) -> Result<AudioHandle, AudioError> {
+        let file = if data.starts_with(b"MMMD") {
+            AudioFile::Smaf(data.to_vec())
+        } else if StandardMidiFile::is_midi(data) {
+            AudioFile::Midi(StandardMidiFile::parse(data).ok_or(AudioError::InvalidAudio)?)
+        } else if Pcm::is_wave(data) {
+            AudioFile::Pcm(Pcm::parse_wave(data).ok_or(AudioError::InvalidAudio)?)
+        } else {
+            return Err(AudioError::InvalidAudio);
+        };
+
+        Ok(self.insert(file))
+    }
+
+    // headerless PCM can't be detected, so the caller has to tell us its format
+    pub fn load_pcm(&mut self, data: &[u8], format: PcmFormat) -> Result<AudioHandle, AudioError> {
+        let pcm = Pcm::from_raw(data, format).ok_or(AudioError::InvalidAudio)?;
+
+        Ok(self.insert(AudioFile::Pcm(pcm)))
+    }
+
+    // playbacks already started keep playing the clip
+    pub fn unload(&mut self, audio_handle: AudioHandle) -> Result<(), AudioError> {
+        self.files.remove(&audio_handle).ok_or(AudioError::InvalidHandle)?;
+
+        Ok(())
+    }
+
+    // plays the clip `loop_count` times, or until stopped if `None`.
+    // handles are released once the playback is over, `completion` tells how it ended
+    pub fn play(&mut self, audio_handle: AudioHandle, loop_count: Option<u32>) -> Result<PlaybackHandle, AudioError> {
+        if loop_count == Some(0) {
+            return Err(AudioError::InvalidAudio);
+        }
+
+        let file = self.files.get(&audio_handle).ok_or(AudioError::InvalidHandle)?.clone();
+        let state = Rc::new(RefCell::new(PlaybackState::new(loop_count)));
+
+        self.last_playback_handle += 1;
+        let playback_handle = self.last_playback_handle;
+
+        let (task, stream) = self.spawn_playback(playback_handle, file.clone(), state.clone(), 0);
+        self.playbacks.insert(playback_handle, Playback { file, state, task, stream });
+
+        Ok(playback_handle)
+    }
+
+    pub fn stop(&mut self, playback_handle: PlaybackHandle) -> Result<(), AudioError> {
+        let playback = self.playbacks.remove(&playback_handle).ok_or(AudioError::InvalidHandle)?;
+
+        playback.task.cancel();
+        self.mixer.remove_stream(playback.stream);
+        if !playback.state.borrow().status.is_done() {
+            playback.state.borrow_mut().set_status(PlaybackStatus::Stopped);
         }
+
+        Ok(())
+    }
+
+    pub fn pause(&mut self, playback_handle: PlaybackHandle) -> Result<(), AudioError> {
+        let playback = self.playbacks.get(&playback_handle).ok_or(AudioError::InvalidHandle)?;
+
+        let mut state = playback.state.borrow_mut();
+        if state.status == PlaybackStatus::Playing {
+            state.set_status(PlaybackStatus::Paused);
+            self.mixer.set_paused(playback.stream, true);
+        }
+
+        Ok(())
+    }
+
+    pub fn resume(&mut self, playback_handle: PlaybackHandle) -> Result<(), AudioError> {
+        let playback = self.playbacks.get(&playback_handle).ok_or(AudioError::InvalidHandle)?;
+
+        let mut state = playback.state.borrow_mut();
+        if state.status == PlaybackStatus::Paused {
+            state.set_status(PlaybackStatus::Playing);
+            self.mixer.set_paused(playback.stream, false);
+        }
+
+        Ok(())
+    }
+
+    // restarts the clip and skips to the position without making sound
+    pub fn seek(&mut self, playback_handle: PlaybackHandle, millis: u64) -> Result<(), AudioError> {
+        let playback = self.playbacks.get_mut(&playback_handle).ok_or(AudioError::InvalidHandle)?;
+        if playback.state.borrow().status.is_done() {
+            return Err(AudioError::InvalidHandle);
+        }
+
+        playback.task.cancel();
+        self.mixer.remove_stream(playback.stream);
+
+        let (file, state) = (playback.file.clone(), playback.state.clone());
+        let (task, stream) = self.spawn_playback(playback_handle, file, state, millis);
+
+        let playback = self.playbacks.get_mut(&playback_handle).unwrap();
+        playback.task = task;
+        playback.stream = stream;
+
+        Ok(())
+    }
+
+    // 0 to 100
+    pub fn set_volume(&mut self, playback_handle: PlaybackHandle, volume: u8) -> Result<(), AudioError> {
+        let playback = self.playbacks.get(&playback_handle).ok_or(AudioError::InvalidHandle)?;
+
+        let volume = volume.min(MAX_VOLUME);
+        playback.state.borrow_mut().volume = volume;
+        self.mixer.set_volume(playback.stream, volume as f32 / MAX_VOLUME as f32);
+
+        Ok(())
+    }
+
+    pub fn status(&self, playback_handle: PlaybackHandle) -> Result<PlaybackStatus, AudioError> {
+        let playback = self.playbacks.get(&playback_handle).ok_or(AudioError::InvalidHandle)?;
+
+        Ok(playback.state.borrow().status)
     }
 
-    pub fn load_smaf(&mut self, data: &[u8]) -> Result<AudioHandle, AudioError> {
+    // in milliseconds from the start of the current loop
+    pub fn position(&self, playback_handle: PlaybackHandle) -> Result<u64, AudioError> {
+        let playback = self.playbacks.get(&playback_handle).ok_or(AudioError::InvalidHandle)?;
+
+        Ok(playback.state.borrow().position)
+    }
+
+    pub fn completion(&self, playback_handle: PlaybackHandle) -> Result<PlaybackCompletion, AudioError> {
+        let playback = self.playbacks.get(&playback_handle).ok_or(AudioError::InvalidHandle)?;
+
+        Ok(PlaybackCompletion::new(playback.state.clone()))
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted.set(muted);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted.get()
+    }
+
+    fn insert(&mut self, file: AudioFile) -> AudioHandle {
         let audio_handle = self.last_audio_handle;
 
         self.last_audio_handle += 1;
-        self.files.insert(audio_handle, AudioFile::Smaf(data.to_vec()));
+        self.files.insert(audio_handle, Rc::new(file));
+
+        audio_handle
+    }
+
+    // called by the playback task once it's over
+    pub(super) fn release_playback(&mut self, playback_handle: PlaybackHandle) {
+        self.playbacks.remove(&playback_handle);
+    }
+
+    fn spawn_playback(
+        &mut self,
+        playback_handle: PlaybackHandle,
+        file: Rc<AudioFile>,
+        state: Rc<RefCell<PlaybackState>>,
+        start: u64,
+    ) -> (JoinHandle<()>, StreamId) {
+        let stream = self.mixer.open_stream();
+        {
+            let state = state.borrow();
+            self.mixer.set_volume(stream, state.volume as f32 / MAX_VOLUME as f32);
+            self.mixer.set_paused(stream, state.status == PlaybackStatus::Paused);
+        }
+
+        let backend = PlaybackBackend::new(
+            playback_handle,
+            self.system.clone(),
+            self.mixer.clone(),
+            stream,
+            self.synth_chip,
+            state,
+            self.muted.clone(),
+            start,
+        );
+
+        let task = self
+            .system
+            .spawn_with(TaskOptions::new("audio"), move || async move { backend.run(file).await });
+
+        (task, stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc};
+    use core::cell::Cell;
+
+    use crate::{AudioCapture, HeadlessPlatform, PcmFormat, PlaybackStatus, System, VirtualClock};
+
+    // 8 bit mono PCM at 1000Hz, played in 100ms chunks
+    const FORMAT: PcmFormat = PcmFormat {
+        channels: 1,
+        sampling_rate: 1000,
+        bits_per_sample: 8,
+    };
+
+    fn system() -> (System, VirtualClock, AudioCapture) {
+        let clock = VirtualClock::new(0);
+        let capture = AudioCapture::new();
+        let platform = HeadlessPlatform::new(240, 320, clock.clone()).with_audio_capture(capture.clone());
+
+        (System::new(Box::new(platform), Box::new(())), clock, capture)
+    }
 
-        Ok(audio_handle)
+    // returns the level if the whole duration was one constant level
+    fn level(capture: &AudioCapture, millis: u64) -> Option<i16> {
+        let samples = capture.render(millis);
+
+        samples.iter().all(|&x| x == samples[0]).then_some(samples[0])
     }
 
-    pub async fn play(&self, audio_handle: AudioHandle) -> Result<(), AudioError> {
-        match self.files.get(&audio_handle) {
-            Some(AudioFile::Smaf(data)) => {
-                let smaf = Smaf::parse(data).map_err(|_| AudioError::InvalidAudio)?;
-                play_smaf(&smaf, &self.backend).await;
-            }
-            None => return Err(AudioError::InvalidHandle),
+    #[test]
+    fn test_playback_control() -> anyhow::Result<()> {
+        let (mut system, clock, capture) = system();
+
+        let clip = system.audio().load_pcm(&[0x90; 500], FORMAT).unwrap();
+        let playback = system.audio().play(clip, None).unwrap();
+
+        system.tick()?;
+        assert_eq!(level(&capture, 100), Some(4096));
+
+        system.audio().set_volume(playback, 50).unwrap();
+        clock.advance(100);
+        system.tick()?;
+        assert_eq!(level(&capture, 100), Some(2048));
+
+        system.audio().pause(playback).unwrap();
+        clock.advance(100);
+        system.tick()?;
+        clock.advance(500);
+        system.tick()?;
+        assert_eq!(level(&capture, 100), Some(0));
+        assert_eq!(system.audio().status(playback).unwrap(), PlaybackStatus::Paused);
+        assert_eq!(system.audio().position(playback).unwrap(), 200);
+
+        system.audio().resume(playback).unwrap();
+        system.tick()?;
+        assert_eq!(level(&capture, 100), Some(2048));
+
+        system.audio().seek(playback, 400).unwrap();
+        system.tick()?;
+        assert_eq!(system.audio().position(playback).unwrap(), 400);
+
+        let completion = system.audio().completion(playback).unwrap();
+        let status = Rc::new(Cell::new(None));
+
+        let status_clone = status.clone();
+        system.spawn(move || async move {
+            status_clone.set(Some(completion.await));
+
+            anyhow::Ok(())
+        });
+
+        // stopping cuts the sound right away
+        system.audio().stop(playback).unwrap();
+        system.tick()?;
+        assert_eq!(status.get(), Some(PlaybackStatus::Stopped));
+        assert_eq!(level(&capture, 100), Some(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_playback_mix() -> anyhow::Result<()> {
+        let (mut system, clock, capture) = system();
+
+        let clip = system.audio().load_pcm(&[0x90; 200], FORMAT).unwrap();
+        let looping = system.audio().play(clip, Some(2)).unwrap();
+        let other = system.audio().play(clip, Some(1)).unwrap();
+
+        // overlapping playbacks are mixed together
+        system.tick()?;
+        assert_eq!(level(&capture, 100), Some(8192));
+
+        clock.advance(100);
+        system.tick()?;
+        assert_eq!(level(&capture, 100), Some(8192));
+
+        for _ in 0..2 {
+            clock.advance(100);
+            system.tick()?;
+            assert_eq!(level(&capture, 100), Some(4096));
         }
 
+        let completion = system.audio().completion(looping).unwrap();
+        clock.advance(100);
+        system.tick()?;
+        assert_eq!(completion.status(), PlaybackStatus::Finished);
+        assert_eq!(level(&capture, 100), Some(0));
+
+        // finished playbacks are released
+        assert!(system.audio().status(looping).is_err() && system.audio().status(other).is_err());
+
+        system.audio().unload(clip).unwrap();
+        assert!(system.audio().play(clip, Some(1)).is_err());
+        let clip = system.audio().load_pcm(&[0x90; 200], FORMAT).unwrap();
+
+        system.audio().set_muted(true);
+        system.audio().play(clip, Some(1)).unwrap();
+        system.tick()?;
+        assert_eq!(level(&capture, 100), Some(0));
+
         Ok(())
     }
 }
@@ -0,0 +1,326 @@
+use alloc::vec::Vec;
+
+const DEFAULT_TEMPO: u64 = 500_000; // microseconds per quarter note, 120 bpm
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MidiMessage {
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    NoteOff { channel: u8, note: u8, velocity: u8 },
+    ProgramChange { channel: u8, program: u8 },
+    ControlChange { channel: u8, control: u8, value: u8 },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MidiEvent {
+    pub time: u64, // in microseconds from the start
+    pub message: MidiMessage,
+}
+
+enum TrackEvent {
+    Message(MidiMessage),
+    Tempo(u64),
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        let result = self.data.get(self.offset..self.offset.checked_add(count)?)?;
+        self.offset += count;
+
+        Some(result)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes(self.bytes(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.offset).copied()
+    }
+
+    fn variable_length(&mut self) -> Option<u32> {
+        let mut result = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            result = (result << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+
+        None
+    }
+}
+
+// Standard MIDI File, format 0 and 1
+pub struct StandardMidiFile {
+    events: Vec<MidiEvent>,
+}
+
+impl StandardMidiFile {
+    pub fn is_midi(data: &[u8]) -> bool {
+        data.starts_with(b"MThd")
+    }
+
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(data);
+        if reader.bytes(4)? != b"MThd" {
+            return None;
+        }
+        let header_length = reader.u32()? as usize;
+        if header_length < 6 {
+            return None;
+        }
+
+        let format = reader.u16()?;
+        let track_count = reader.u16()?;
+        let division = reader.u16()?;
+        // later revisions may add fields to the header
+        reader.bytes(header_length - 6)?;
+        if format > 1 || division == 0 {
+            return None;
+        }
+
+        // tracks are merged into one timeline ordered by tick, tempo changes apply to every track
+        let mut track_events = Vec::new();
+        let mut parsed_tracks = 0;
+        while parsed_tracks < track_count && !reader.is_empty() {
+            let chunk_type = reader.bytes(4)?;
+            let chunk_size = reader.u32()? as usize;
+            let chunk = reader.bytes(chunk_size)?;
+
+            if chunk_type == b"MTrk" {
+                Self::parse_track(chunk, &mut track_events)?;
+                parsed_tracks += 1;
+            }
+        }
+        track_events.sort_by_key(|(tick, _)| *tick);
+
+        let mut events = Vec::with_capacity(track_events.len());
+        let mut tempo = DEFAULT_TEMPO;
+        let mut last_tick = 0;
+        let mut time = 0;
+        for (tick, event) in track_events {
+            time += Self::ticks_to_micros(tick - last_tick, division, tempo);
+            last_tick = tick;
+
+            match event {
+                TrackEvent::Tempo(x) => tempo = x,
+                TrackEvent::Message(message) => events.push(MidiEvent { time, message }),
+            }
+        }
+
+        Some(Self { events })
+    }
+
+    pub fn events(&self) -> &[MidiEvent] {
+        &self.events
+    }
+
+    fn ticks_to_micros(ticks: u64, division: u16, tempo: u64) -> u64 {
+        if division & 0x8000 != 0 {
+            // SMPTE timing, negative frames per second in the high byte and ticks per frame in the low byte
+            let frames_per_second = ((division >> 8) as u8 as i8).unsigned_abs() as u64;
+            let ticks_per_frame = (division & 0xff) as u64;
+
+            ticks * 1_000_000 / (frames_per_second * ticks_per_frame).max(1)
+        } else {
+            ticks * tempo / division as u64
+        }
+    }
+
+    fn parse_track(data: &[u8], events: &mut Vec<(u64, TrackEvent)>) -> Option<()> {
+        let mut reader = Reader::new(data);
+        let mut tick = 0u64;
+        let mut running_status = None;
+
+        while !reader.is_empty() {
+            tick += reader.variable_length()? as u64;
+
+            let status = if reader.peek()? & 0x80 != 0 { reader.u8()? } else { running_status? };
+
+            match status {
+                0xff => {
+                    let meta_type = reader.u8()?;
+                    let length = reader.variable_length()? as usize;
+                    let meta = reader.bytes(length)?;
+
+                    match meta_type {
+                        0x2f => break, // end of track
+                        0x51 if length == 3 => events.push((
+                            tick,
+                            TrackEvent::Tempo(((meta[0] as u64) << 16) | ((meta[1] as u64) << 8) | meta[2] as u64),
+                        )),
+                        _ => {}
+                    }
+                }
+                0xf0 | 0xf7 => {
+                    let length = reader.variable_length()? as usize;
+                    reader.bytes(length)?;
+                }
+                0x80..=0xef => {
+                    running_status = Some(status);
+
+                    let channel = status & 0x0f;
+                    let message = match status >> 4 {
+                        0x8 => Some(MidiMessage::NoteOff {
+                            channel,
+                            note: reader.u8()?,
+                            velocity: reader.u8()?,
+                        }),
+                        0x9 => Some(MidiMessage::NoteOn {
+                            channel,
+                            note: reader.u8()?,
+                            velocity: reader.u8()?,
+                        }),
+                        0xb => Some(MidiMessage::ControlChange {
+                            channel,
+                            control: reader.u8()?,
+                            value: reader.u8()?,
+                        }),
+                        0xc => Some(MidiMessage::ProgramChange {
+                            channel,
+                            program: reader.u8()?,
+                        }),
+                        0xd => {
+                            reader.u8()?; // channel pressure
+
+                            None
+                        }
+                        _ => {
+                            reader.bytes(2)?; // key pressure, pitch bend
+
+                            None
+                        }
+                    };
+
+                    if let Some(x) = message {
+                        events.push((tick, TrackEvent::Message(x)));
+                    }
+                }
+                _ => return None,
+            }
+        }
+
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{MidiEvent, MidiMessage, StandardMidiFile};
+
+    fn track(events: &[u8]) -> Vec<u8> {
+        let mut result = b"MTrk".to_vec();
+        result.extend_from_slice(&(events.len() as u32).to_be_bytes());
+        result.extend_from_slice(events);
+
+        result
+    }
+
+    #[test]
+    fn test_parse_format_1() {
+        let mut data = b"MThd\x00\x00\x00\x06\x00\x01\x00\x02\x00\x60".to_vec();
+        // tempo track, 60 bpm after one quarter note
+        data.extend(track(&[
+            0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, 0x60, 0xff, 0x51, 0x03, 0x0f, 0x42, 0x40, 0x00, 0xff, 0x2f, 0x00,
+        ]));
+        // running status on the second note
+        data.extend(track(&[
+            0x00, 0xc1, 0x10, 0x00, 0x91, 0x3c, 0x64, 0x60, 0x3c, 0x00, 0x60, 0x81, 0x3e, 0x40, 0x00, 0xff, 0x2f, 0x00,
+        ]));
+
+        let midi = StandardMidiFile::parse(&data).unwrap();
+        assert_eq!(
+            midi.events(),
+            &[
+                MidiEvent {
+                    time: 0,
+                    message: MidiMessage::ProgramChange { channel: 1, program: 0x10 }
+                },
+                MidiEvent {
+                    time: 0,
+                    message: MidiMessage::NoteOn {
+                        channel: 1,
+                        note: 0x3c,
+                        velocity: 0x64
+                    }
+                },
+                MidiEvent {
+                    time: 500_000,
+                    message: MidiMessage::NoteOn {
+                        channel: 1,
+                        note: 0x3c,
+                        velocity: 0
+                    }
+                },
+                MidiEvent {
+                    time: 1_500_000,
+                    message: MidiMessage::NoteOff {
+                        channel: 1,
+                        note: 0x3e,
+                        velocity: 0x40
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_smpte() {
+        // longer header, 128 frames per second with 2 ticks per frame
+        let mut data = b"MThd\x00\x00\x00\x08\x00\x00\x00\x01\x80\x02\x00\x00".to_vec();
+        data.extend(track(&[0x00, 0x90, 0x3c, 0x64, 0x82, 0x00, 0x80, 0x3c, 0x40, 0x00, 0xff, 0x2f, 0x00]));
+
+        let midi = StandardMidiFile::parse(&data).unwrap();
+        assert_eq!(
+            midi.events(),
+            &[
+                MidiEvent {
+                    time: 0,
+                    message: MidiMessage::NoteOn {
+                        channel: 0,
+                        note: 0x3c,
+                        velocity: 0x64
+                    }
+                },
+                MidiEvent {
+                    time: 1_000_000,
+                    message: MidiMessage::NoteOff {
+                        channel: 0,
+                        note: 0x3c,
+                        velocity: 0x40
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(StandardMidiFile::parse(b"MThd\x00\x00\x00\x06\x00\x02\x00\x01\x00\x60").is_none());
+        assert!(StandardMidiFile::parse(&[b"MThd\x00\x00\x00\x06\x00\x00\x00\x01\x00\x60".as_slice(), &track(&[0x00, 0x3c])].concat()).is_none());
+    }
+}
@@ -0,0 +1,343 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use smaf::Smaf;
+use smaf_player::{play_smaf, AudioBackend};
+
+use crate::{
+    mixer::{Mixer, StreamId},
+    System,
+};
+
+use super::{
+    midi::{MidiMessage, StandardMidiFile},
+    synth::{SynthChip, Synthesizer},
+    wave::Pcm,
+    AudioFile, PlaybackHandle,
+};
+
+const SYNTH_SAMPLE_RATE: u32 = 22050;
+const SYNTH_MAX_TAIL: u64 = 2000;
+const PCM_CHUNK_MILLIS: u64 = 100; // position advances and paused playbacks stop queueing at chunk boundaries
+pub const MAX_VOLUME: u8 = 100;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    Finished,
+    Stopped,
+}
+
+impl PlaybackStatus {
+    pub fn is_done(&self) -> bool {
+        matches!(self, Self::Finished | Self::Stopped)
+    }
+}
+
+pub(super) struct PlaybackState {
+    pub status: PlaybackStatus,
+    pub volume: u8,
+    pub position: u64,
+    pub remaining_loops: Option<u32>,
+    waiters: Vec<Waker>,
+}
+
+impl PlaybackState {
+    pub fn new(loop_count: Option<u32>) -> Self {
+        Self {
+            status: PlaybackStatus::Playing,
+            volume: MAX_VOLUME,
+            position: 0,
+            remaining_loops: loop_count,
+            waiters: Vec::new(),
+        }
+    }
+
+    pub fn set_status(&mut self, status: PlaybackStatus) {
+        self.status = status;
+
+        for waker in self.waiters.drain(..) {
+            waker.wake();
+        }
+    }
+
+    fn register_waiter(&mut self, waker: &Waker) {
+        if !self.waiters.iter().any(|x| x.will_wake(waker)) {
+            self.waiters.push(waker.clone());
+        }
+    }
+}
+
+// Resolves with the final status once the playback has finished or was stopped
+pub struct PlaybackCompletion {
+    state: Rc<RefCell<PlaybackState>>,
+}
+
+impl PlaybackCompletion {
+    pub(super) fn new(state: Rc<RefCell<PlaybackState>>) -> Self {
+        Self { state }
+    }
+
+    // stays valid after the playback handle is released
+    pub fn status(&self) -> PlaybackStatus {
+        self.state.borrow().status
+    }
+}
+
+impl Future for PlaybackCompletion {
+    type Output = PlaybackStatus;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+
+        if state.status.is_done() {
+            Poll::Ready(state.status)
+        } else {
+            state.register_waiter(cx.waker());
+
+            Poll::Pending
+        }
+    }
+}
+
+struct ResumeFuture {
+    state: Rc<RefCell<PlaybackState>>,
+}
+
+impl Future for ResumeFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+
+        if state.status == PlaybackStatus::Paused {
+            state.register_waiter(cx.waker());
+
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    }
+}
+
+// Each playback gets its own synthesizer, so stopping one clip doesn't cut notes of another
+pub(super) struct PlaybackBackend {
+    playback_handle: PlaybackHandle,
+    system: System,
+    mixer: Mixer,
+    stream: StreamId,
+    synth: RefCell<Synthesizer>,
+    state: Rc<RefCell<PlaybackState>>,
+    muted: Rc<Cell<bool>>,
+    skip_until: Cell<u64>,
+}
+
+impl PlaybackBackend {
+    pub fn new(
+        playback_handle: PlaybackHandle,
+        system: System,
+        mixer: Mixer,
+        stream: StreamId,
+        chip: SynthChip,
+        state: Rc<RefCell<PlaybackState>>,
+        muted: Rc<Cell<bool>>,
+        start: u64,
+    ) -> Self {
+        Self {
+            playback_handle,
+            system,
+            mixer,
+            stream,
+            synth: RefCell::new(Synthesizer::new(SYNTH_SAMPLE_RATE, chip)),
+            state,
+            muted,
+            skip_until: Cell::new(start),
+        }
+    }
+
+    pub async fn run(self, file: Rc<AudioFile>) -> anyhow::Result<()> {
+        let result = self.play_loops(&file).await;
+
+        let status = if result.is_ok() {
+            PlaybackStatus::Finished
+        } else {
+            PlaybackStatus::Stopped
+        };
+        self.state.borrow_mut().set_status(status);
+        self.mixer.close_stream(self.stream);
+        self.system.audio().release_playback(self.playback_handle);
+
+        result
+    }
+
+    async fn play_loops(&self, file: &AudioFile) -> anyhow::Result<()> {
+        loop {
+            self.state.borrow_mut().position = 0;
+
+            match file {
+                AudioFile::Smaf(data) => {
+                    let smaf = Smaf::parse(data).map_err(|_| anyhow::anyhow!("Invalid SMAF"))?;
+                    play_smaf(&smaf, self).await;
+                }
+                AudioFile::Midi(midi) => self.play_midi(midi).await,
+                AudioFile::Pcm(pcm) => self.play_pcm(pcm).await,
+            }
+            self.render_synth_tail();
+
+            let mut state = self.state.borrow_mut();
+            match state.remaining_loops {
+                Some(x) if x <= 1 => break,
+                Some(x) => state.remaining_loops = Some(x - 1),
+                None => {}
+            }
+            self.skip_until.set(0);
+        }
+
+        Ok(())
+    }
+
+    async fn play_midi(&self, midi: &StandardMidiFile) {
+        let mut now = 0;
+        for event in midi.events() {
+            let time = event.time / 1000;
+            if time > now {
+                self.sleep(Duration::from_millis(time - now)).await;
+                now = time;
+            }
+
+            match event.message {
+                MidiMessage::NoteOn { channel, note, velocity } => self.midi_note_on(channel, note, velocity),
+                MidiMessage::NoteOff { channel, note, velocity } => self.midi_note_off(channel, note, velocity),
+                MidiMessage::ProgramChange { channel, program } => self.midi_program_change(channel, program),
+                MidiMessage::ControlChange { channel, control, value } => self.midi_control_change(channel, control, value),
+            }
+        }
+    }
+
+    async fn play_pcm(&self, pcm: &Pcm) {
+        let frames_per_chunk = (pcm.sampling_rate as u64 * PCM_CHUNK_MILLIS / 1000).max(1) as usize;
+        let samples_per_chunk = frames_per_chunk * pcm.channels as usize;
+
+        let start_frame = (self.skip_until.get() * pcm.sampling_rate as u64 / 1000) as usize;
+        let start = (start_frame * pcm.channels as usize).min(pcm.samples.len());
+        self.state.borrow_mut().position = self.skip_until.get();
+        self.skip_until.set(0);
+
+        for chunk in pcm.samples[start..].chunks(samples_per_chunk) {
+            self.play_wave(pcm.channels, pcm.sampling_rate, chunk);
+
+            let frames = (chunk.len() / pcm.channels as usize) as u64;
+            self.sleep(Duration::from_millis(frames * 1000 / pcm.sampling_rate as u64)).await;
+        }
+    }
+
+    fn is_skipping(&self) -> bool {
+        self.state.borrow().position < self.skip_until.get()
+    }
+
+    fn output(&self, channel: u8, sampling_rate: u32, wave_data: &[i16]) {
+        if self.muted.get() {
+            return;
+        }
+
+        // volume is applied by the mixer, so changes affect samples already queued
+        self.mixer.queue(self.stream, channel, sampling_rate, wave_data);
+    }
+
+    fn render_synth(&self, duration: u64) {
+        let mut synth = self.synth.borrow_mut();
+        if synth.is_silent() || duration == 0 {
+            return;
+        }
+
+        let frames = (synth.sample_rate() as u64 * duration / 1000) as usize;
+        let samples = synth.render(frames);
+
+        self.output(2, synth.sample_rate(), &samples);
+    }
+
+    // let released notes ring out after the sequence has ended
+    fn render_synth_tail(&self) {
+        let mut synth = self.synth.borrow_mut();
+        if synth.is_silent() {
+            return;
+        }
+
+        let frames = (synth.sample_rate() as u64 * SYNTH_MAX_TAIL / 1000) as usize;
+        let mut samples = synth.render(frames);
+
+        // trailing silence is not worth playing
+        let end = samples.iter().rposition(|&x| x != 0).map(|x| (x | 1) + 1).unwrap_or(0);
+        samples.truncate(end);
+
+        if !samples.is_empty() {
+            self.output(2, synth.sample_rate(), &samples);
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl AudioBackend for PlaybackBackend {
+    fn play_wave(&self, channel: u8, sampling_rate: u32, wave_data: &[i16]) {
+        if !self.is_skipping() {
+            self.output(channel, sampling_rate, wave_data);
+        }
+    }
+
+    fn midi_note_on(&self, channel_id: u8, note: u8, velocity: u8) {
+        // programs and controls still apply while seeking, so the sound is right when we get there
+        if !self.is_skipping() {
+            self.synth.borrow_mut().note_on(channel_id, note, velocity);
+        }
+    }
+
+    fn midi_note_off(&self, channel_id: u8, note: u8, _velocity: u8) {
+        self.synth.borrow_mut().note_off(channel_id, note);
+    }
+
+    fn midi_program_change(&self, channel_id: u8, program: u8) {
+        self.synth.borrow_mut().program_change(channel_id, program);
+    }
+
+    fn midi_control_change(&self, channel_id: u8, control: u8, value: u8) {
+        self.synth.borrow_mut().control_change(channel_id, control, value);
+    }
+
+    // the player sleeps between events, so we render the notes sounding during that time
+    async fn sleep(&self, duration: Duration) {
+        let mut duration = duration.as_millis() as u64;
+
+        let position = self.state.borrow().position;
+        let skip_until = self.skip_until.get();
+        if position < skip_until {
+            let skipped = duration.min(skip_until - position);
+            self.state.borrow_mut().position += skipped;
+            duration -= skipped;
+
+            if duration == 0 {
+                return;
+            }
+        }
+
+        self.render_synth(duration);
+
+        let mut system = self.system.clone();
+        let end = system.platform().now() + duration;
+        system.sleep(end).await;
+
+        self.state.borrow_mut().position += duration;
+
+        ResumeFuture { state: self.state.clone() }.await;
+    }
+
+    fn now_millis(&self) -> u64 {
+        self.system.platform().now().raw()
+    }
+}
@@ -0,0 +1,542 @@
+use alloc::vec::Vec;
+use core::f32::consts::{FRAC_PI_2, TAU};
+
+const MIDI_CHANNEL_COUNT: usize = 16;
+const PERCUSSION_CHANNEL: u8 = 9;
+const MASTER_GAIN: f32 = 0.3;
+
+// Yamaha MA-series sound chips found on WIPI handsets, only the polyphony is modelled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SynthChip {
+    Ma1,
+    Ma2,
+    Ma3,
+    Ma5,
+}
+
+impl SynthChip {
+    pub fn polyphony(&self) -> usize {
+        match self {
+            Self::Ma1 => 4,
+            Self::Ma2 => 16,
+            Self::Ma3 => 32,
+            Self::Ma5 => 64,
+        }
+    }
+}
+
+// Two operator FM patch, times are in seconds
+struct FmPatch {
+    ratio: f32,
+    index: f32,
+    attack: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+}
+
+// One patch per General MIDI instrument family (program / 8)
+const PATCHES: [FmPatch; 16] = [
+    // piano
+    FmPatch {
+        ratio: 1.0,
+        index: 1.5,
+        attack: 0.002,
+        decay: 1.2,
+        sustain: 0.0,
+        release: 0.3,
+    },
+    // chromatic percussion
+    FmPatch {
+        ratio: 3.5,
+        index: 2.0,
+        attack: 0.001,
+        decay: 0.6,
+        sustain: 0.0,
+        release: 0.2,
+    },
+    // organ
+    FmPatch {
+        ratio: 1.0,
+        index: 0.8,
+        attack: 0.01,
+        decay: 0.1,
+        sustain: 0.9,
+        release: 0.05,
+    },
+    // guitar
+    FmPatch {
+        ratio: 2.0,
+        index: 1.8,
+        attack: 0.002,
+        decay: 0.8,
+        sustain: 0.1,
+        release: 0.2,
+    },
+    // bass
+    FmPatch {
+        ratio: 0.5,
+        index: 1.2,
+        attack: 0.005,
+        decay: 0.5,
+        sustain: 0.4,
+        release: 0.1,
+    },
+    // strings
+    FmPatch {
+        ratio: 1.0,
+        index: 1.0,
+        attack: 0.08,
+        decay: 0.3,
+        sustain: 0.8,
+        release: 0.3,
+    },
+    // ensemble
+    FmPatch {
+        ratio: 1.0,
+        index: 0.7,
+        attack: 0.1,
+        decay: 0.4,
+        sustain: 0.8,
+        release: 0.4,
+    },
+    // brass
+    FmPatch {
+        ratio: 1.0,
+        index: 3.0,
+        attack: 0.03,
+        decay: 0.2,
+        sustain: 0.7,
+        release: 0.15,
+    },
+    // reed
+    FmPatch {
+        ratio: 3.0,
+        index: 1.5,
+        attack: 0.03,
+        decay: 0.2,
+        sustain: 0.7,
+        release: 0.1,
+    },
+    // pipe
+    FmPatch {
+        ratio: 1.0,
+        index: 0.4,
+        attack: 0.05,
+        decay: 0.2,
+        sustain: 0.8,
+        release: 0.15,
+    },
+    // synth lead
+    FmPatch {
+        ratio: 1.0,
+        index: 4.0,
+        attack: 0.005,
+        decay: 0.2,
+        sustain: 0.7,
+        release: 0.1,
+    },
+    // synth pad
+    FmPatch {
+        ratio: 0.5,
+        index: 1.0,
+        attack: 0.3,
+        decay: 0.5,
+        sustain: 0.7,
+        release: 0.6,
+    },
+    // synth effects
+    FmPatch {
+        ratio: 7.0,
+        index: 2.5,
+        attack: 0.1,
+        decay: 0.5,
+        sustain: 0.5,
+        release: 0.5,
+    },
+    // ethnic
+    FmPatch {
+        ratio: 4.0,
+        index: 2.0,
+        attack: 0.002,
+        decay: 0.5,
+        sustain: 0.1,
+        release: 0.2,
+    },
+    // percussive
+    FmPatch {
+        ratio: 1.4,
+        index: 3.0,
+        attack: 0.001,
+        decay: 0.3,
+        sustain: 0.0,
+        release: 0.1,
+    },
+    // sound effects
+    FmPatch {
+        ratio: 11.0,
+        index: 6.0,
+        attack: 0.01,
+        decay: 0.5,
+        sustain: 0.3,
+        release: 0.3,
+    },
+];
+
+const PERCUSSION_PATCH: FmPatch = FmPatch {
+    ratio: 1.0,
+    index: 0.0,
+    attack: 0.001,
+    decay: 0.15,
+    sustain: 0.0,
+    release: 0.05,
+};
+
+#[derive(Clone, Copy)]
+struct ChannelState {
+    program: u8,
+    volume: u8,
+    expression: u8,
+    pan: u8,
+    sustain: bool,
+}
+
+impl ChannelState {
+    fn new() -> Self {
+        Self {
+            program: 0,
+            volume: 100,
+            expression: 127,
+            pan: 64,
+            sustain: false,
+        }
+    }
+
+    // MIDI volume curves are roughly quadratic
+    fn gain(&self) -> f32 {
+        let volume = self.volume as f32 / 127.0;
+        let expression = self.expression as f32 / 127.0;
+
+        volume * volume * expression * expression
+    }
+
+    fn pan_gains(&self) -> (f32, f32) {
+        let pan = self.pan.saturating_sub(1) as f32 / 126.0 * FRAC_PI_2;
+
+        (pan.cos(), pan.sin())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum EnvelopeStage {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Off,
+}
+
+struct Voice {
+    channel: u8,
+    note: u8,
+    velocity: f32,
+    patch: &'static FmPatch,
+    percussion: bool,
+    frequency: f32,
+    carrier_phase: f32,
+    modulator_phase: f32,
+    stage: EnvelopeStage,
+    level: f32,
+    held: bool,
+    age: u64,
+    noise: u32,
+}
+
+impl Voice {
+    fn release(&mut self) {
+        if self.stage != EnvelopeStage::Off {
+            self.stage = EnvelopeStage::Release;
+        }
+    }
+
+    fn next_sample(&mut self, sample_rate: f32) -> f32 {
+        let patch = self.patch;
+
+        match self.stage {
+            EnvelopeStage::Attack => {
+                self.level += 1.0 / (patch.attack * sample_rate);
+                if self.level >= 1.0 {
+                    self.level = 1.0;
+                    self.stage = EnvelopeStage::Decay;
+                }
+            }
+            EnvelopeStage::Decay => {
+                self.level -= (1.0 - patch.sustain) / (patch.decay * sample_rate);
+                if self.level <= patch.sustain {
+                    self.level = patch.sustain;
+                    self.stage = if patch.sustain > 0.0 {
+                        EnvelopeStage::Sustain
+                    } else {
+                        EnvelopeStage::Off
+                    };
+                }
+            }
+            EnvelopeStage::Sustain => {}
+            EnvelopeStage::Release => {
+                self.level -= 1.0 / (patch.release * sample_rate);
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    self.stage = EnvelopeStage::Off;
+                }
+            }
+            EnvelopeStage::Off => return 0.0,
+        }
+
+        let sample = if self.percussion {
+            // xorshift noise over a short pitched thump
+            self.noise ^= self.noise << 13;
+            self.noise ^= self.noise >> 17;
+            self.noise ^= self.noise << 5;
+            let noise = (self.noise as f32 / u32::MAX as f32) * 2.0 - 1.0;
+
+            noise * 0.6 + (self.carrier_phase * TAU).sin() * 0.4
+        } else {
+            let modulator = (self.modulator_phase * TAU).sin() * patch.index * self.level;
+            self.modulator_phase = (self.modulator_phase + self.frequency * patch.ratio / sample_rate).fract();
+
+            (self.carrier_phase * TAU + modulator).sin()
+        };
+        self.carrier_phase = (self.carrier_phase + self.frequency / sample_rate).fract();
+
+        sample * self.level * self.velocity
+    }
+}
+
+// Software synthesizer rendering MIDI events from SMAF into interleaved stereo PCM
+pub struct Synthesizer {
+    sample_rate: u32,
+    chip: SynthChip,
+    channels: [ChannelState; MIDI_CHANNEL_COUNT],
+    voices: Vec<Voice>,
+    age: u64,
+}
+
+impl Synthesizer {
+    pub fn new(sample_rate: u32, chip: SynthChip) -> Self {
+        Self {
+            sample_rate,
+            chip,
+            channels: [ChannelState::new(); MIDI_CHANNEL_COUNT],
+            voices: Vec::new(),
+            age: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn note_on(&mut self, channel: u8, note: u8, velocity: u8) {
+        let channel = channel & 0x0f;
+        if velocity == 0 {
+            self.note_off(channel, note);
+
+            return;
+        }
+
+        let percussion = channel == PERCUSSION_CHANNEL;
+        let patch = if percussion {
+            &PERCUSSION_PATCH
+        } else {
+            &PATCHES[(self.channels[channel as usize].program / 8) as usize]
+        };
+
+        self.age += 1;
+        let voice = Voice {
+            channel,
+            note,
+            velocity: velocity as f32 / 127.0,
+            patch,
+            percussion,
+            frequency: 440.0 * ((note as f32 - 69.0) / 12.0).exp2(),
+            carrier_phase: 0.0,
+            modulator_phase: 0.0,
+            stage: EnvelopeStage::Attack,
+            level: 0.0,
+            held: false,
+            age: self.age,
+            noise: 0x1234_5678 ^ ((note as u32) << 8),
+        };
+
+        if let Some(index) = self.voices.iter().position(|x| x.channel == channel && x.note == note) {
+            self.voices[index] = voice;
+        } else if self.voices.len() < self.chip.polyphony() {
+            self.voices.push(voice);
+        } else {
+            // steal the quietest released voice, or the oldest one if nothing is released
+            let index = self
+                .voices
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    let a_key = (
+                        a.stage != EnvelopeStage::Release,
+                        if a.stage == EnvelopeStage::Release { a.level } else { 0.0 },
+                        a.age,
+                    );
+                    let b_key = (
+                        b.stage != EnvelopeStage::Release,
+                        if b.stage == EnvelopeStage::Release { b.level } else { 0.0 },
+                        b.age,
+                    );
+
+                    a_key.0.cmp(&b_key.0).then(a_key.1.total_cmp(&b_key.1)).then(a_key.2.cmp(&b_key.2))
+                })
+                .map(|(i, _)| i)
+                .unwrap();
+
+            self.voices[index] = voice;
+        }
+    }
+
+    pub fn note_off(&mut self, channel: u8, note: u8) {
+        let channel = channel & 0x0f;
+        let sustain = self.channels[channel as usize].sustain;
+
+        for voice in self.voices.iter_mut().filter(|x| x.channel == channel && x.note == note && !x.percussion) {
+            if sustain {
+                voice.held = true;
+            } else {
+                voice.release();
+            }
+        }
+    }
+
+    pub fn program_change(&mut self, channel: u8, program: u8) {
+        self.channels[(channel & 0x0f) as usize].program = program & 0x7f;
+    }
+
+    pub fn control_change(&mut self, channel: u8, control: u8, value: u8) {
+        let channel = channel & 0x0f;
+        let state = &mut self.channels[channel as usize];
+
+        match control {
+            7 => state.volume = value,
+            10 => state.pan = value,
+            11 => state.expression = value,
+            64 => {
+                state.sustain = value >= 64;
+                if !state.sustain {
+                    for voice in self.voices.iter_mut().filter(|x| x.channel == channel && x.held) {
+                        voice.held = false;
+                        voice.release();
+                    }
+                }
+            }
+            120 => self.voices.retain(|x| x.channel != channel), // all sound off
+            121 => {
+                // reset all controllers
+                *state = ChannelState {
+                    program: state.program,
+                    ..ChannelState::new()
+                };
+            }
+            123 => {
+                // all notes off
+                for voice in self.voices.iter_mut().filter(|x| x.channel == channel) {
+                    voice.release();
+                }
+            }
+            _ => tracing::trace!("Unhandled control change {} on channel {}", control, channel),
+        }
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.voices.is_empty()
+    }
+
+    pub fn render(&mut self, frames: usize) -> Vec<i16> {
+        let mut mix = alloc::vec![0f32; frames * 2];
+        let sample_rate = self.sample_rate as f32;
+
+        for voice in self.voices.iter_mut() {
+            let channel = &self.channels[voice.channel as usize];
+            let gain = channel.gain() * MASTER_GAIN;
+            let (left, right) = channel.pan_gains();
+
+            for frame in mix.chunks_exact_mut(2) {
+                let sample = voice.next_sample(sample_rate) * gain;
+
+                frame[0] += sample * left;
+                frame[1] += sample * right;
+            }
+        }
+
+        self.voices.retain(|x| x.stage != EnvelopeStage::Off);
+
+        mix.into_iter().map(|x| (x.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SynthChip, Synthesizer};
+
+    fn peak(samples: &[i16]) -> i16 {
+        samples.iter().map(|x| x.saturating_abs()).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn test_note_on_off() {
+        let mut synth = Synthesizer::new(22050, SynthChip::Ma3);
+        assert_eq!(peak(&synth.render(100)), 0);
+
+        synth.program_change(0, 80);
+        synth.note_on(0, 69, 127);
+        assert!(peak(&synth.render(2205)) > 1000);
+
+        synth.note_off(0, 69);
+        synth.render(22050);
+        assert!(synth.is_silent());
+        assert_eq!(peak(&synth.render(100)), 0);
+    }
+
+    #[test]
+    fn test_control_change() {
+        let mut synth = Synthesizer::new(22050, SynthChip::Ma3);
+
+        // hard right
+        synth.program_change(0, 16);
+        synth.control_change(0, 10, 127);
+        synth.note_on(0, 60, 127);
+
+        let samples = synth.render(1000);
+        let left = samples.iter().step_by(2).copied().collect::<alloc::vec::Vec<_>>();
+        let right = samples.iter().skip(1).step_by(2).copied().collect::<alloc::vec::Vec<_>>();
+        assert!(peak(&left) < 10);
+        assert!(peak(&right) > 1000);
+
+        synth.control_change(0, 7, 0);
+        assert_eq!(peak(&synth.render(100)), 0);
+
+        synth.control_change(0, 120, 0);
+        assert!(synth.is_silent());
+    }
+
+    #[test]
+    fn test_polyphony() {
+        let mut synth = Synthesizer::new(22050, SynthChip::Ma1);
+
+        for note in 60..70 {
+            synth.note_on(0, note, 100);
+        }
+        assert_eq!(synth.voices.len(), 4);
+        assert!(synth.voices.iter().all(|x| x.note >= 66));
+
+        let mut synth = Synthesizer::new(22050, SynthChip::Ma5);
+        for note in 0..100 {
+            synth.note_on(1, note, 100);
+        }
+        assert_eq!(synth.voices.len(), 64);
+    }
+}
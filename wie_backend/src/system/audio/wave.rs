@@ -0,0 +1,243 @@
+use alloc::vec::Vec;
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IMA_ADPCM: u16 = 0x11;
+
+const IMA_INDEX_TABLE: [i8; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+const IMA_STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66, 73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190,
+    209, 230, 253, 279, 307, 337, 371, 408, 449, 494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272, 2499,
+    2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493, 10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350,
+    22385, 24623, 27086, 29794, 32767,
+];
+
+// Layout of headerless PCM data, 8 bit samples are unsigned and 16 bit samples are signed little endian
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PcmFormat {
+    pub channels: u8,
+    pub sampling_rate: u32,
+    pub bits_per_sample: u8,
+}
+
+// Decoded clip, samples are interleaved
+pub struct Pcm {
+    pub channels: u8,
+    pub sampling_rate: u32,
+    pub samples: Vec<i16>,
+}
+
+impl Pcm {
+    pub fn is_wave(data: &[u8]) -> bool {
+        data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WAVE"
+    }
+
+    pub fn from_raw(data: &[u8], format: PcmFormat) -> Option<Self> {
+        if format.channels == 0 || format.sampling_rate == 0 {
+            return None;
+        }
+
+        let samples = match format.bits_per_sample {
+            8 => data.iter().map(|&x| ((x as i16) - 128) << 8).collect(),
+            16 => data.chunks_exact(2).map(|x| i16::from_le_bytes([x[0], x[1]])).collect(),
+            _ => return None,
+        };
+
+        Some(Self {
+            channels: format.channels,
+            sampling_rate: format.sampling_rate,
+            samples,
+        })
+    }
+
+    pub fn parse_wave(data: &[u8]) -> Option<Self> {
+        if !Self::is_wave(data) {
+            return None;
+        }
+
+        let mut format = None;
+        let mut wave_data = None;
+
+        let mut offset = 12;
+        while data.len().saturating_sub(offset) >= 8 {
+            let chunk_type = &data[offset..offset + 4];
+            let chunk_size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().ok()?) as usize;
+            let chunk_end = offset.checked_add(8).and_then(|x| x.checked_add(chunk_size));
+            let chunk = chunk_end.and_then(|x| data.get(offset + 8..x)).unwrap_or(&data[offset + 8..]); // some encoders write bogus sizes on the last chunk
+
+            match chunk_type {
+                b"fmt " if chunk.len() >= 16 => format = Some(chunk),
+                b"data" => wave_data = Some(chunk),
+                _ => {}
+            }
+
+            // chunks are word aligned
+            match chunk_end.and_then(|x| x.checked_add(chunk_size & 1)) {
+                Some(x) => offset = x,
+                None => break,
+            }
+        }
+
+        let format = format?;
+        let wave_data = wave_data?;
+
+        let format_tag = u16::from_le_bytes([format[0], format[1]]);
+        let channels = u16::from_le_bytes([format[2], format[3]]);
+        let sampling_rate = u32::from_le_bytes(format[4..8].try_into().ok()?);
+        let block_align = u16::from_le_bytes([format[12], format[13]]);
+        let bits_per_sample = u16::from_le_bytes([format[14], format[15]]);
+
+        if channels == 0 || channels > 2 {
+            return None;
+        }
+
+        match format_tag {
+            WAVE_FORMAT_PCM => Self::from_raw(
+                wave_data,
+                PcmFormat {
+                    channels: channels as _,
+                    sampling_rate,
+                    bits_per_sample: bits_per_sample as _,
+                },
+            ),
+            WAVE_FORMAT_IMA_ADPCM if bits_per_sample == 4 => Some(Self {
+                channels: channels as _,
+                sampling_rate,
+                samples: Self::decode_ima_adpcm(wave_data, channels as _, block_align as _)?,
+            }),
+            _ => None,
+        }
+    }
+
+    // Each block starts with a header per channel, followed by 4 byte groups of 8 nibbles per channel
+    fn decode_ima_adpcm(data: &[u8], channels: usize, block_align: usize) -> Option<Vec<i16>> {
+        if block_align <= 4 * channels {
+            return None;
+        }
+
+        let mut result = Vec::new();
+        for block in data.chunks(block_align) {
+            if block.len() <= 4 * channels {
+                break;
+            }
+
+            let mut states = (0..channels)
+                .map(|x| {
+                    let header = &block[x * 4..x * 4 + 4];
+                    let predictor = i16::from_le_bytes([header[0], header[1]]) as i32;
+                    let index = (header[2] as i32).min(88);
+
+                    (predictor, index)
+                })
+                .collect::<Vec<_>>();
+
+            result.extend(states.iter().map(|x| x.0 as i16));
+
+            let body = &block[4 * channels..];
+            let group_count = body.len() / (4 * channels);
+            let mut decoded = alloc::vec![0i16; group_count * 8 * channels];
+
+            for (group_index, group) in body.chunks_exact(4 * channels).enumerate() {
+                for (channel, channel_bytes) in group.chunks_exact(4).enumerate() {
+                    for (byte_index, &byte) in channel_bytes.iter().enumerate() {
+                        for (nibble_index, nibble) in [byte & 0x0f, byte >> 4].into_iter().enumerate() {
+                            let sample = Self::decode_ima_nibble(&mut states[channel], nibble);
+                            let frame = group_index * 8 + byte_index * 2 + nibble_index;
+
+                            decoded[frame * channels + channel] = sample;
+                        }
+                    }
+                }
+            }
+
+            result.extend(decoded);
+        }
+
+        Some(result)
+    }
+
+    fn decode_ima_nibble(state: &mut (i32, i32), nibble: u8) -> i16 {
+        let (predictor, index) = state;
+        let step = IMA_STEP_TABLE[*index as usize];
+
+        let mut diff = step >> 3;
+        if nibble & 4 != 0 {
+            diff += step;
+        }
+        if nibble & 2 != 0 {
+            diff += step >> 1;
+        }
+        if nibble & 1 != 0 {
+            diff += step >> 2;
+        }
+        if nibble & 8 != 0 {
+            diff = -diff;
+        }
+
+        *predictor = (*predictor + diff).clamp(i16::MIN as i32, i16::MAX as i32);
+        *index = (*index + IMA_INDEX_TABLE[nibble as usize] as i32).clamp(0, 88);
+
+        *predictor as i16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{Pcm, PcmFormat};
+
+    fn wave(format_tag: u16, channels: u16, block_align: u16, bits_per_sample: u16, data: &[u8]) -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&format_tag.to_le_bytes());
+        fmt.extend_from_slice(&channels.to_le_bytes());
+        fmt.extend_from_slice(&8000u32.to_le_bytes());
+        fmt.extend_from_slice(&16000u32.to_le_bytes());
+        fmt.extend_from_slice(&block_align.to_le_bytes());
+        fmt.extend_from_slice(&bits_per_sample.to_le_bytes());
+
+        let mut result = b"RIFF\x00\x00\x00\x00WAVE".to_vec();
+        result.extend_from_slice(b"fmt \x10\x00\x00\x00");
+        result.extend(fmt);
+        result.extend_from_slice(b"LIST\x03\x00\x00\x00abc\x00"); // odd sized chunk with padding
+        result.extend_from_slice(b"data");
+        result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        result.extend_from_slice(data);
+
+        result
+    }
+
+    #[test]
+    fn test_pcm() {
+        let pcm = Pcm::parse_wave(&wave(1, 1, 2, 16, &[0x00, 0x80, 0xff, 0x7f, 0x01, 0x00])).unwrap();
+        assert_eq!(pcm.channels, 1);
+        assert_eq!(pcm.sampling_rate, 8000);
+        assert_eq!(pcm.samples, [i16::MIN, i16::MAX, 1]);
+
+        let pcm = Pcm::from_raw(
+            &[0x00, 0x80, 0xff],
+            PcmFormat {
+                channels: 1,
+                sampling_rate: 8000,
+                bits_per_sample: 8,
+            },
+        )
+        .unwrap();
+        assert_eq!(pcm.samples, [i16::MIN, 0, 0x7f00]);
+        assert!(Pcm::parse_wave(b"RIFF\x00\x00\x00\x00WAVE").is_none());
+
+        // bogus size on the last chunk
+        let mut data = wave(1, 1, 2, 16, &[0x01, 0x00]);
+        let size_offset = data.len() - 6;
+        data[size_offset..size_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(Pcm::parse_wave(&data).unwrap().samples, [1]);
+    }
+
+    #[test]
+    fn test_ima_adpcm() {
+        // header with predictor 0 and step index 0, then nibbles 7, 7, 0xf, 0xf
+        let pcm = Pcm::parse_wave(&wave(0x11, 1, 8, 4, &[0x00, 0x00, 0x00, 0x00, 0x77, 0xff, 0x00, 0x00])).unwrap();
+
+        assert_eq!(pcm.samples.len(), 9);
+        assert_eq!(&pcm.samples[..5], [0, 11, 41, -22, -158]);
+    }
+}
@@ -1,4 +1,12 @@
-use alloc::collections::VecDeque;
+use alloc::{collections::VecDeque, rc::Rc, vec::Vec};
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use wie_util::{SnapshotError, SnapshotReader, SnapshotResult, SnapshotWriter};
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -47,6 +55,30 @@ impl KeyCode {
             _ => unimplemented!("Unknown key: {}", string),
         }
     }
+
+    fn from_raw(raw: u8) -> Option<KeyCode> {
+        const KEYS: [KeyCode; 17] = [
+            KeyCode::UP,
+            KeyCode::DOWN,
+            KeyCode::LEFT,
+            KeyCode::RIGHT,
+            KeyCode::OK,
+            KeyCode::NUM0,
+            KeyCode::NUM1,
+            KeyCode::NUM2,
+            KeyCode::NUM3,
+            KeyCode::NUM4,
+            KeyCode::NUM5,
+            KeyCode::NUM6,
+            KeyCode::NUM7,
+            KeyCode::NUM8,
+            KeyCode::NUM9,
+            KeyCode::HASH,
+            KeyCode::STAR,
+        ];
+
+        KEYS.get(raw as usize).cloned()
+    }
 }
 
 #[derive(Debug)]
@@ -59,18 +91,96 @@ pub enum Event {
 #[derive(Default)]
 pub struct EventQueue {
     events: VecDeque<Event>,
+    waiters: Vec<Waker>,
 }
 
 impl EventQueue {
     pub fn new() -> Self {
-        Self { events: VecDeque::new() }
+        Self {
+            events: VecDeque::new(),
+            waiters: Vec::new(),
+        }
     }
 
     pub fn push(&mut self, event: Event) {
         self.events.push_back(event);
+
+        for waker in self.waiters.drain(..) {
+            waker.wake();
+        }
     }
 
     pub fn pop(&mut self) -> Option<Event> {
         self.events.pop_front()
     }
+
+    fn register_waiter(&mut self, waker: &Waker) {
+        if !self.waiters.iter().any(|x| x.will_wake(waker)) {
+            self.waiters.push(waker.clone());
+        }
+    }
+
+    pub(crate) fn save_state(&self, writer: &mut SnapshotWriter) {
+        writer.write_u32(self.events.len() as _);
+        for event in &self.events {
+            match event {
+                Event::Redraw => writer.write_u8(0),
+                Event::Keydown(x) => {
+                    writer.write_u8(1);
+                    writer.write_u8(*x as _);
+                }
+                Event::Keyup(x) => {
+                    writer.write_u8(2);
+                    writer.write_u8(*x as _);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn read_state(reader: &mut SnapshotReader) -> SnapshotResult<VecDeque<Event>> {
+        let count = reader.read_u32()?;
+
+        let mut events = VecDeque::new();
+        for _ in 0..count {
+            let event = match reader.read_u8()? {
+                0 => Event::Redraw,
+                1 => Event::Keydown(KeyCode::from_raw(reader.read_u8()?).ok_or(SnapshotError::InvalidData)?),
+                2 => Event::Keyup(KeyCode::from_raw(reader.read_u8()?).ok_or(SnapshotError::InvalidData)?),
+                _ => return Err(SnapshotError::InvalidData),
+            };
+            events.push_back(event);
+        }
+
+        Ok(events)
+    }
+
+    pub(crate) fn restore_state(&mut self, events: VecDeque<Event>) {
+        self.events = events;
+    }
+}
+
+pub struct NextEventFuture {
+    event_queue: Rc<RefCell<EventQueue>>,
+}
+
+impl NextEventFuture {
+    pub(crate) fn new(event_queue: Rc<RefCell<EventQueue>>) -> Self {
+        Self { event_queue }
+    }
+}
+
+impl Future for NextEventFuture {
+    type Output = Event;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut event_queue = self.event_queue.borrow_mut();
+
+        if let Some(x) = event_queue.pop() {
+            Poll::Ready(x)
+        } else {
+            event_queue.register_waiter(cx.waker());
+
+            Poll::Pending
+        }
+    }
 }
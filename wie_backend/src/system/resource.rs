@@ -1,9 +1,15 @@
 use alloc::string::String;
+use std::collections::{HashMap, HashSet};
 
-use crate::extract_zip;
+use crate::{extract_zip_with_encoding, TextEncoding};
 
+use super::vfs::{normalize_path, parent_directories};
+
+// Read-only files from the archive. Lookups ignore leading slashes and case.
 pub struct Resource {
     files: Vec<(String, Vec<u8>)>,
+    index: HashMap<String, u32>,
+    directories: HashSet<String>,
 }
 
 impl Default for Resource {
@@ -14,31 +20,44 @@ impl Default for Resource {
 
 impl Resource {
     pub fn new() -> Self {
-        Self { files: Vec::new() }
+        Self {
+            files: Vec::new(),
+            index: HashMap::new(),
+            directories: HashSet::new(),
+        }
     }
 
     pub fn add(&mut self, path: &str, data: Vec<u8>) {
         tracing::trace!("Adding resource {}, {}b", path, data.len());
 
+        let normalized = normalize_path(path);
+        if path.ends_with('/') {
+            // directory entry of zip
+            self.directories.extend(parent_directories(&normalized));
+            self.directories.insert(normalized);
+            return;
+        }
+
+        // later files replace earlier ones with the same path
+        if let Some(&id) = self.index.get(&normalized) {
+            self.files[id as usize] = (path.to_string(), data);
+            return;
+        }
+
+        self.directories.extend(parent_directories(&normalized));
+        self.index.insert(normalized, self.files.len() as _);
         self.files.push((path.to_string(), data));
     }
 
     pub fn id(&self, path: &str) -> Option<u32> {
         tracing::trace!("Looking for resource {}", path);
 
-        if let Some(x) = path.strip_prefix('/') {
-            return self.id(x);
-        }
-
-        for (id, file) in self.files.iter().enumerate() {
-            if file.0 == path {
-                return Some(id as _);
-            }
+        let id = self.index.get(&normalize_path(path)).copied();
+        if id.is_none() {
+            tracing::warn!("No such resource {}", path);
         }
 
-        tracing::warn!("No such resource {}", path);
-
-        None
+        id
     }
 
     pub fn size(&self, id: u32) -> u32 {
@@ -53,8 +72,20 @@ impl Resource {
         self.files.iter().map(|file| file.0.as_ref())
     }
 
-    pub fn mount_zip(&mut self, zip: &[u8]) -> anyhow::Result<()> {
-        let files = extract_zip(zip)?;
+    pub fn is_directory(&self, normalized_path: &str) -> bool {
+        self.directories.contains(normalized_path)
+    }
+
+    pub(super) fn normalized_paths(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(|x| x.as_ref())
+    }
+
+    pub(super) fn get(&self, normalized_path: &str) -> Option<&[u8]> {
+        self.index.get(normalized_path).map(|&x| self.data(x))
+    }
+
+    pub fn mount_zip(&mut self, zip: &[u8], encoding: TextEncoding) -> anyhow::Result<()> {
+        let files = extract_zip_with_encoding(zip, encoding)?;
 
         for (path, data) in files {
             self.add(&path, data);
@@ -0,0 +1,266 @@
+use alloc::{
+    collections::BTreeSet,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::cell::RefCell;
+use std::collections::HashSet;
+
+use crate::platform::Platform;
+
+use super::resource::Resource;
+
+// lowercase, slash separated without leading or trailing slash, `.` and `..` resolved
+pub fn normalize_path(path: &str) -> String {
+    let mut components = Vec::new();
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            x => components.push(x),
+        }
+    }
+
+    components.join("/").to_ascii_lowercase()
+}
+
+// every ancestor of a normalized path, except the root
+pub(super) fn parent_directories(path: &str) -> impl Iterator<Item = String> + '_ {
+    path.match_indices('/').map(|(x, _)| path[..x].to_string())
+}
+
+// Archive resources overlaid with a writable layer for the app, which is persisted by the platform.
+// Files written by the app shadow resources of the same path, resources themselves can't be changed.
+pub struct Vfs {
+    platform: Rc<RefCell<Box<dyn Platform>>>,
+    resource: Rc<RefCell<Resource>>,
+    files: HashSet<String>,
+    directories: HashSet<String>,
+}
+
+impl Vfs {
+    pub(super) fn new(platform: Rc<RefCell<Box<dyn Platform>>>, resource: Rc<RefCell<Resource>>) -> Self {
+        let files = platform
+            .borrow()
+            .file_storage()
+            .list()
+            .iter()
+            .map(|x| normalize_path(x))
+            .collect::<HashSet<_>>();
+        let directories = files.iter().flat_map(|x| parent_directories(x)).collect();
+
+        Self {
+            platform,
+            resource,
+            files,
+            directories,
+        }
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.is_file(path) || self.is_directory(path)
+    }
+
+    pub fn is_file(&self, path: &str) -> bool {
+        let path = normalize_path(path);
+
+        self.files.contains(&path) || self.resource.borrow().get(&path).is_some()
+    }
+
+    pub fn is_directory(&self, path: &str) -> bool {
+        let path = normalize_path(path);
+
+        path.is_empty() || self.directories.contains(&path) || self.resource.borrow().is_directory(&path)
+    }
+
+    // files written by the app can be changed and removed, resources can only be shadowed
+    pub fn is_writable(&self, path: &str) -> bool {
+        let path = normalize_path(path);
+
+        !path.is_empty() && !self.is_directory(&path) && !self.has_file_parent(&path)
+    }
+
+    fn has_file_parent(&self, path: &str) -> bool {
+        parent_directories(path).any(|x| self.is_file(&x))
+    }
+
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let path = normalize_path(path);
+
+        if self.files.contains(&path) {
+            self.platform.borrow().file_storage().read(&path)
+        } else {
+            self.resource.borrow().get(&path).map(|x| x.to_vec())
+        }
+    }
+
+    pub fn size(&self, path: &str) -> Option<usize> {
+        let path = normalize_path(path);
+
+        if self.files.contains(&path) {
+            self.read(&path).map(|x| x.len())
+        } else {
+            self.resource.borrow().get(&path).map(|x| x.len())
+        }
+    }
+
+    pub fn write(&mut self, path: &str, data: &[u8]) -> bool {
+        let path = normalize_path(path);
+        if !self.is_writable(&path) {
+            tracing::warn!("Can't write to {}", path);
+
+            return false;
+        }
+
+        tracing::trace!("Writing file {}, {}b", path, data.len());
+
+        if !self.platform.borrow().file_storage().write(&path, data) {
+            return false;
+        }
+
+        self.directories.extend(parent_directories(&path));
+        self.files.insert(path);
+
+        true
+    }
+
+    pub fn remove(&mut self, path: &str) -> bool {
+        let path = normalize_path(path);
+        if !self.files.contains(&path) {
+            return false;
+        }
+
+        tracing::trace!("Removing file {}", path);
+
+        if !self.platform.borrow().file_storage().remove(&path) {
+            return false;
+        }
+        self.files.remove(&path);
+
+        // same as after a restart, parents left without anything in them are gone
+        let parents = parent_directories(&path).collect::<Vec<_>>();
+        for parent in parents.iter().rev() {
+            let prefix = format!("{}/", parent);
+            if self.files.iter().chain(self.directories.iter()).any(|x| x.starts_with(&prefix)) {
+                break;
+            }
+
+            self.directories.remove(parent);
+        }
+
+        true
+    }
+
+    // directories only exist as parents of files in the storage, so an empty one is gone after a restart
+    pub fn create_directory(&mut self, path: &str) -> bool {
+        let path = normalize_path(path);
+        if path.is_empty() || self.is_file(&path) || self.has_file_parent(&path) {
+            return false;
+        }
+
+        self.directories.extend(parent_directories(&path));
+        self.directories.insert(path);
+
+        true
+    }
+
+    // names of files and directories directly under the path
+    pub fn list(&self, path: &str) -> Vec<String> {
+        let path = normalize_path(path);
+        let prefix = if path.is_empty() { String::new() } else { format!("{}/", path) };
+
+        let resource = self.resource.borrow();
+        let entries = self
+            .files
+            .iter()
+            .chain(self.directories.iter())
+            .map(|x| x.as_str())
+            .chain(resource.normalized_paths())
+            .filter_map(|x| x.strip_prefix(&prefix))
+            .filter_map(|x| x.split('/').next())
+            .filter(|x| !x.is_empty())
+            .collect::<BTreeSet<_>>();
+
+        entries.into_iter().map(|x| x.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use crate::{HeadlessPlatform, InMemoryFileStorage, System, VirtualClock};
+
+    use super::normalize_path;
+
+    fn system(file_storage: &InMemoryFileStorage) -> System {
+        let platform = HeadlessPlatform::new(240, 320, VirtualClock::new(0)).with_file_storage(file_storage.clone());
+        let system = System::new(Box::new(platform), Box::new(()));
+
+        system.resource_mut().add("Data/Stage1.dat", b"stage".to_vec());
+        system.resource_mut().add("/save.dat", b"initial".to_vec());
+
+        system
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/Data//Stage1.DAT"), "data/stage1.dat");
+        assert_eq!(normalize_path("\\data\\.\\a\\..\\b/"), "data/b");
+        assert_eq!(normalize_path("/"), "");
+    }
+
+    #[test]
+    fn test_overlay() {
+        let file_storage = InMemoryFileStorage::new();
+        let system = system(&file_storage);
+
+        let mut vfs = system.vfs();
+        assert_eq!(vfs.read("/DATA/stage1.dat").unwrap(), b"stage");
+        assert!(vfs.is_directory("data") && !vfs.is_file("data"));
+        assert!(!vfs.exists("missing.dat"));
+
+        // written files shadow resources
+        assert!(vfs.write("save.dat", b"saved"));
+        assert!(vfs.write("slot/1.sav", b"1"));
+        assert_eq!(vfs.read("/save.dat").unwrap(), b"saved");
+        assert_eq!(vfs.size("slot/1.sav"), Some(1));
+        assert_eq!(vfs.list("/"), ["data", "save.dat", "slot"]);
+
+        assert!(!vfs.write("data", b"not a file"));
+        assert!(!vfs.remove("data/stage1.dat"));
+
+        // removing the written file uncovers the resource again
+        assert!(vfs.remove("save.dat"));
+        assert_eq!(vfs.read("save.dat").unwrap(), b"initial");
+
+        assert!(vfs.create_directory("empty"));
+        assert!(vfs.is_directory("empty") && vfs.list("empty").is_empty());
+
+        // files can't be used as directories
+        assert!(!vfs.write("slot/1.sav/x", b"") && !vfs.create_directory("slot/1.sav/x"));
+
+        // removing the last file removes its parents too
+        assert!(vfs.write("a/b/c.sav", b""));
+        assert!(vfs.remove("a/b/c.sav"));
+        assert!(!vfs.exists("a/b") && !vfs.exists("a"));
+        assert!(vfs.remove("slot/1.sav"));
+        assert!(!vfs.exists("slot"));
+    }
+
+    #[test]
+    fn test_persist() {
+        let file_storage = InMemoryFileStorage::new();
+
+        system(&file_storage).vfs().write("/Slot/1.sav", b"progress");
+
+        let system = system(&file_storage);
+        let vfs = system.vfs();
+        assert_eq!(vfs.read("slot/1.sav").unwrap(), b"progress");
+        assert_eq!(vfs.list("slot"), ["1.sav"]);
+    }
+}
@@ -1,30 +1,56 @@
+use alloc::{
+    rc::Rc,
+    string::{String, ToString},
+};
 use core::{
+    cell::RefCell,
     future::Future,
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
 };
 
 use crate::{executor::Executor, time::Instant};
 
-pub struct YieldFuture {}
+#[derive(Default)]
+pub struct YieldFuture {
+    yielded: bool,
+}
+
+impl YieldFuture {
+    pub fn new() -> Self {
+        Self { yielded: false }
+    }
+}
 
 impl Future for YieldFuture {
     type Output = ();
 
-    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
-        Poll::Ready(())
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            // let other ready tasks run, but come back on the next step
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+
+            Poll::Pending
+        }
     }
 }
 
 pub struct SleepFuture {
-    polled: bool,
+    executor: Executor,
+    until: Instant,
+    registered: bool,
 }
 
 impl SleepFuture {
-    pub fn new(until: Instant, executor: &mut Executor) -> Self {
-        executor.sleep(until);
-
-        Self { polled: false }
+    pub fn new(until: Instant, executor: &Executor) -> Self {
+        Self {
+            executor: executor.clone(),
+            until,
+            registered: false,
+        }
     }
 }
 
@@ -32,9 +58,14 @@ impl Future for SleepFuture {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if !self.polled {
-            self.polled = true;
+        if !self.registered {
+            self.registered = true;
+            let until = self.until;
+            self.executor.sleep(until);
 
+            Poll::Pending
+        } else if self.executor.is_sleeping() {
+            // woken by someone else before the timer fired
             Poll::Pending
         } else {
             Poll::Ready(())
@@ -43,3 +74,112 @@ impl Future for SleepFuture {
 }
 
 impl Unpin for SleepFuture {}
+
+#[derive(Clone, Debug)]
+pub struct TaskOptions {
+    name: String,
+    critical: bool,
+}
+
+impl TaskOptions {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            critical: false,
+        }
+    }
+
+    // failing critical task stops the emulator
+    pub fn critical(mut self) -> Self {
+        self.critical = true;
+
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TaskState {
+    Ready,
+    Sleeping(Instant),
+    Waiting,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaskInfo {
+    pub id: usize,
+    pub name: String,
+    pub critical: bool,
+    pub state: TaskState,
+}
+
+pub(crate) struct JoinState<R> {
+    result: Option<anyhow::Result<R>>,
+    waker: Option<Waker>,
+}
+
+impl<R> JoinState<R> {
+    pub fn new() -> Self {
+        Self { result: None, waker: None }
+    }
+
+    pub fn complete(&mut self, result: anyhow::Result<R>) {
+        self.result = Some(result);
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+// Dropping the handle detaches the task, it keeps running
+pub struct JoinHandle<R> {
+    task_id: usize,
+    state: Rc<RefCell<JoinState<R>>>,
+    executor: Executor,
+}
+
+impl<R> JoinHandle<R> {
+    pub(crate) fn new(task_id: usize, state: Rc<RefCell<JoinState<R>>>, executor: Executor) -> Self {
+        Self { task_id, state, executor }
+    }
+
+    pub fn id(&self) -> usize {
+        self.task_id
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.executor.is_alive(self.task_id)
+    }
+
+    pub fn cancel(&mut self) {
+        if self.executor.cancel(self.task_id) {
+            self.state.borrow_mut().complete(Err(anyhow::anyhow!("Task {} cancelled", self.task_id)));
+        }
+    }
+}
+
+impl<R> Future for JoinHandle<R> {
+    type Output = anyhow::Result<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+
+        if let Some(x) = state.result.take() {
+            Poll::Ready(x)
+        } else {
+            state.waker = Some(cx.waker().clone());
+
+            Poll::Pending
+        }
+    }
+}
+
+impl<R> Unpin for JoinHandle<R> {}
@@ -1,13 +1,90 @@
-use rodio::{buffer::SamplesBuffer, OutputStream, Sink};
+use core::{cell::RefCell, time::Duration};
 
-pub struct AudioSink;
+use rodio::{OutputStream, Sink, Source};
+
+use wie_backend::Mixer;
+
+const MIXER_BLOCK_FRAMES: usize = 512;
+
+// Endless source feeding rodio from the mixer, silence is played while nothing is queued
+struct MixerSource {
+    mixer: Mixer,
+    buffer: Vec<i16>,
+    position: usize,
+}
+
+impl MixerSource {
+    fn new(mixer: Mixer) -> Self {
+        Self {
+            mixer,
+            buffer: vec![0; MIXER_BLOCK_FRAMES * 2],
+            position: MIXER_BLOCK_FRAMES * 2,
+        }
+    }
+}
+
+impl Iterator for MixerSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.buffer.len() {
+            self.mixer.mix(&mut self.buffer);
+            self.position = 0;
+        }
+
+        let sample = self.buffer[self.position];
+        self.position += 1;
+
+        Some(sample)
+    }
+}
+
+impl Source for MixerSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.mixer.output_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct AudioSink {
+    output: RefCell<Option<(OutputStream, Sink)>>, // dropping the stream stops the playback
+}
+
+impl AudioSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
 
 impl wie_backend::AudioSink for AudioSink {
-    fn play_wave(&self, channel: u8, sampling_rate: u32, wave_data: &[i16]) {
-        let buffer = SamplesBuffer::new(channel as _, sampling_rate as _, wave_data);
+    fn attach(&self, mixer: Mixer) {
+        let Ok((output_stream, stream_handle)) = OutputStream::try_default() else {
+            tracing::warn!("No audio output device, audio is disabled");
+            mixer.discard();
+
+            return;
+        };
+        let Ok(sink) = Sink::try_new(&stream_handle) else {
+            tracing::warn!("Failed to create audio sink, audio is disabled");
+            mixer.discard();
+
+            return;
+        };
+
+        sink.append(MixerSource::new(mixer));
 
-        let (_output_stream, stream_handle) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&stream_handle).unwrap();
-        sink.append(buffer);
+        *self.output.borrow_mut() = Some((output_stream, sink));
     }
 }
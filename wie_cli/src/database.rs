@@ -1,8 +1,13 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 use directories::ProjectDirs;
 
-use wie_backend::RecordId;
+use wie_backend::{DatabaseError, DatabaseFile, DatabaseResult, RecordId};
+
+const DATABASE_EXTENSION: &str = "db";
 
 pub struct DatabaseRepository {
     base_path: PathBuf,
@@ -17,91 +22,178 @@ impl DatabaseRepository {
         Self { base_path }
     }
 
-    fn get_path_for_database(&self, name: &str) -> PathBuf {
+    fn get_path_for_database(&self, name: &str) -> DatabaseResult<PathBuf> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(DatabaseError::InvalidName);
+        }
+
+        Ok(self.base_path.join(format!("{}.{}", name, DATABASE_EXTENSION)))
+    }
+
+    // older versions stored each record as a numbered file in a directory named after the database
+    fn get_legacy_path_for_database(&self, name: &str) -> PathBuf {
         self.base_path.join(name)
     }
-}
 
-impl wie_backend::DatabaseRepository for DatabaseRepository {
-    fn open(&self, name: &str) -> Box<dyn wie_backend::Database> {
-        let path = self.get_path_for_database(name);
+    fn migrate_legacy(&self, name: &str, path: &Path) -> DatabaseResult<Option<DatabaseFile>> {
+        let legacy_path = self.get_legacy_path_for_database(name);
+        if !legacy_path.is_dir() {
+            return Ok(None);
+        }
+
+        tracing::info!("Migrating database {:?} to {:?}", legacy_path, path);
 
-        Box::new(Database::new(path).unwrap())
+        let mut file = DatabaseFile::new();
+        for entry in fs::read_dir(&legacy_path).map_err(io_error)?.flatten() {
+            let Some(id) = entry.file_name().to_str().and_then(|x| x.parse::<RecordId>().ok()) else {
+                continue;
+            };
+
+            file.set(id, &fs::read(entry.path()).map_err(io_error)?)?;
+        }
+
+        Database::save(path, &file)?;
+        if let Err(x) = fs::remove_dir_all(&legacy_path) {
+            tracing::warn!("Failed to remove legacy database {:?}: {}", legacy_path, x);
+        }
+
+        Ok(Some(file))
     }
 }
 
-pub struct Database {
-    base_path: PathBuf,
-}
+impl wie_backend::DatabaseRepository for DatabaseRepository {
+    fn open(&self, name: &str, create: bool) -> DatabaseResult<Box<dyn wie_backend::Database>> {
+        let path = self.get_path_for_database(name)?;
 
-impl Database {
-    pub fn new(base_path: PathBuf) -> anyhow::Result<Self> {
-        tracing::trace!("Opening database at {:?}", base_path);
+        tracing::trace!("Opening database at {:?}", path);
+
+        let file = match fs::read(&path) {
+            Ok(x) => DatabaseFile::parse(&x)?,
+            Err(x) if x.kind() == io::ErrorKind::NotFound => match self.migrate_legacy(name, &path)? {
+                Some(x) => x,
+                None if create => {
+                    fs::create_dir_all(&self.base_path).map_err(io_error)?;
+
+                    let file = DatabaseFile::new();
+                    Database::save(&path, &file)?;
 
-        fs::create_dir_all(&base_path)?;
+                    file
+                }
+                None => return Err(DatabaseError::NotFound),
+            },
+            Err(x) => return Err(io_error(x)),
+        };
 
-        Ok(Self { base_path })
+        Ok(Box::new(Database { path, file }))
     }
 
-    fn find_empty_record_id(&mut self) -> RecordId {
-        let mut record_id = 0;
+    fn list(&self) -> DatabaseResult<Vec<String>> {
+        let entries = match fs::read_dir(&self.base_path) {
+            Ok(x) => x,
+            Err(x) if x.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(x) => return Err(io_error(x)),
+        };
+
+        let mut result = entries
+            .flatten()
+            .filter_map(|x| {
+                let path = x.path();
+                if path.is_dir() {
+                    // not migrated yet
+                    path.file_name()?.to_str().map(|x| x.to_owned())
+                } else if path.extension()? == DATABASE_EXTENSION {
+                    path.file_stem()?.to_str().map(|x| x.to_owned())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        result.sort();
+        result.dedup();
+
+        Ok(result)
+    }
 
-        loop {
-            let path = self.base_path.join(record_id.to_string());
+    fn delete(&self, name: &str) -> DatabaseResult<()> {
+        let path = self.get_path_for_database(name)?;
+        let legacy_path = self.get_legacy_path_for_database(name);
 
-            if !path.exists() {
-                return record_id;
-            }
+        tracing::trace!("Deleting database at {:?}", path);
 
-            record_id += 1;
+        if legacy_path.is_dir() {
+            fs::remove_dir_all(legacy_path).map_err(io_error)?;
+        } else if !path.exists() {
+            return Err(DatabaseError::NotFound);
+        }
+
+        match fs::remove_file(path) {
+            Err(x) if x.kind() != io::ErrorKind::NotFound => Err(io_error(x)),
+            _ => Ok(()),
         }
-    }
-    fn get_path_for_record(&self, id: RecordId) -> PathBuf {
-        self.base_path.join(id.to_string())
     }
 }
 
-impl wie_backend::Database for Database {
-    fn add(&mut self, data: &[u8]) -> RecordId {
-        let id = self.find_empty_record_id();
-
-        tracing::trace!("Adding record {} to database {:?}", id, &self.base_path);
+pub struct Database {
+    path: PathBuf,
+    file: DatabaseFile,
+}
 
-        let path = self.get_path_for_record(id);
-        fs::write(path, data).unwrap();
+impl Database {
+    // the file is replaced only after the new contents are fully written, so a crash leaves either the old or the new one
+    fn save(path: &Path, file: &DatabaseFile) -> DatabaseResult<()> {
+        let temp_path = path.with_extension(format!("{}.tmp", DATABASE_EXTENSION));
 
-        id
+        fs::write(&temp_path, file.serialize()).map_err(io_error)?;
+        fs::rename(&temp_path, path).map_err(io_error)
     }
 
-    fn get(&self, id: RecordId) -> Option<Vec<u8>> {
-        let path = self.get_path_for_record(id);
+    fn update<T>(&mut self, f: impl FnOnce(&mut DatabaseFile) -> DatabaseResult<T>) -> DatabaseResult<T> {
+        let mut file = self.file.clone();
+        let result = f(&mut file)?;
+
+        Self::save(&self.path, &file)?;
+        self.file = file;
 
-        tracing::trace!("Read record {} from database {:?}", id, &self.base_path);
+        Ok(result)
+    }
+}
 
-        fs::read(path).ok()
+impl wie_backend::Database for Database {
+    fn add(&mut self, data: &[u8]) -> DatabaseResult<RecordId> {
+        tracing::trace!("Adding record to database {:?}", &self.path);
+
+        self.update(|x| x.add(data))
     }
 
-    fn set(&mut self, id: RecordId, data: &[u8]) -> bool {
-        let path = self.get_path_for_record(id);
+    fn get(&self, id: RecordId) -> DatabaseResult<Vec<u8>> {
+        tracing::trace!("Read record {} from database {:?}", id, &self.path);
+
+        self.file.get(id).map(|x| x.to_vec())
+    }
 
-        tracing::trace!("Set record {} to database {:?}", id, &self.base_path);
+    fn set(&mut self, id: RecordId, data: &[u8]) -> DatabaseResult<()> {
+        tracing::trace!("Set record {} to database {:?}", id, &self.path);
 
-        fs::write(path, data).is_ok()
+        self.update(|x| x.set(id, data))
     }
 
-    fn delete(&mut self, id: RecordId) -> bool {
-        let path = self.get_path_for_record(id);
+    fn delete(&mut self, id: RecordId) -> DatabaseResult<()> {
+        tracing::trace!("Delete record {} from database {:?}", id, &self.path);
 
-        tracing::trace!("Delete record {} from database {:?}", id, &self.base_path);
+        self.update(|x| x.delete(id))
+    }
 
-        fs::remove_file(path).is_ok()
+    fn get_record_ids(&self) -> DatabaseResult<Vec<RecordId>> {
+        Ok(self.file.record_ids())
     }
 
-    fn get_record_ids(&self) -> Vec<RecordId> {
-        fs::read_dir(&self.base_path)
-            .unwrap()
-            .filter(|x| x.as_ref().unwrap().path().is_file())
-            .map(|x| x.unwrap().file_name().to_str().unwrap().parse().unwrap())
-            .collect()
+    fn get_record_size(&self, id: RecordId) -> DatabaseResult<usize> {
+        self.file.get(id).map(|x| x.len())
     }
 }
+
+fn io_error(error: io::Error) -> DatabaseError {
+    tracing::warn!("Database I/O error: {}", error);
+
+    DatabaseError::Io
+}
@@ -0,0 +1,90 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+
+// Files are kept under a directory of their own, so they can't collide with databases of the app
+pub struct FileStorage {
+    base_path: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(app_id: &str) -> Self {
+        let base_dir = ProjectDirs::from("net", "dlunch", "wie").unwrap();
+
+        let base_path = base_dir.data_dir().join("files").join(app_id);
+
+        Self { base_path }
+    }
+
+    // only plain relative paths, so nothing can be written outside of the base path
+    fn get_path_for_file(&self, path: &str) -> Option<PathBuf> {
+        let path = Path::new(path);
+        if path.as_os_str().is_empty() || !path.components().all(|x| matches!(x, Component::Normal(_))) {
+            tracing::warn!("Invalid file path {:?}", path);
+
+            return None;
+        }
+
+        Some(self.base_path.join(path))
+    }
+
+    fn list_directory(&self, path: PathBuf, result: &mut Vec<String>) {
+        let Ok(entries) = fs::read_dir(&path) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                self.list_directory(path, result);
+            } else if let Ok(relative) = path.strip_prefix(&self.base_path) {
+                let components = relative.components().map(|x| x.as_os_str().to_string_lossy()).collect::<Vec<_>>();
+
+                result.push(components.join("/"));
+            }
+        }
+    }
+}
+
+impl wie_backend::FileStorage for FileStorage {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        tracing::trace!("Read file {} from {:?}", path, &self.base_path);
+
+        fs::read(self.get_path_for_file(path)?).ok()
+    }
+
+    fn write(&self, path: &str, data: &[u8]) -> bool {
+        tracing::trace!("Write file {} to {:?}", path, &self.base_path);
+
+        let Some(path) = self.get_path_for_file(path) else {
+            return false;
+        };
+        if let Some(parent) = path.parent() {
+            if fs::create_dir_all(parent).is_err() {
+                return false;
+            }
+        }
+
+        fs::write(path, data).is_ok()
+    }
+
+    fn remove(&self, path: &str) -> bool {
+        tracing::trace!("Remove file {} from {:?}", path, &self.base_path);
+
+        let Some(path) = self.get_path_for_file(path) else {
+            return false;
+        };
+
+        fs::remove_file(path).is_ok()
+    }
+
+    fn list(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        self.list_directory(self.base_path.clone(), &mut result);
+
+        result
+    }
+}
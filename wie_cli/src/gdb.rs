@@ -0,0 +1,63 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use wie_backend::Debugger;
+
+pub struct GdbServer {
+    stream: Option<TcpStream>,
+    debugger: Box<dyn Debugger>,
+}
+
+impl GdbServer {
+    pub fn listen(port: u16, debugger: Box<dyn Debugger>) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+
+        tracing::info!("Waiting for gdb connection on port {}", port);
+
+        let (stream, address) = listener.accept()?;
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        tracing::info!("gdb connected from {}", address);
+
+        Ok(Self {
+            stream: Some(stream),
+            debugger,
+        })
+    }
+
+    pub fn poll(&mut self) -> anyhow::Result<()> {
+        let stream = if let Some(x) = &mut self.stream {
+            x
+        } else {
+            return Ok(());
+        };
+
+        let mut buf = [0; 4096];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) => {
+                    tracing::info!("gdb disconnected");
+
+                    self.debugger.detach();
+                    self.stream = None;
+
+                    return Ok(());
+                }
+                Ok(x) => {
+                    let output = self.debugger.handle_input(&buf[..x]);
+                    stream.write_all(&output)?;
+                }
+                Err(x) if x.kind() == ErrorKind::WouldBlock => break,
+                Err(x) => return Err(x.into()),
+            }
+        }
+
+        let output = self.debugger.poll();
+        stream.write_all(&output)?;
+
+        Ok(())
+    }
+}
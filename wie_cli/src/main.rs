@@ -2,19 +2,28 @@ extern crate alloc;
 
 mod audio_sink;
 mod database;
+mod file_storage;
+mod gdb;
+mod save_data;
+mod screenshot;
 mod window;
 
 use std::{
     collections::HashSet,
     fs,
     io::stderr,
+    path::{Path, PathBuf},
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use clap::Parser;
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use directories::ProjectDirs;
 use winit::keyboard::{KeyCode as WinitKeyCode, PhysicalKey};
 
-use wie_backend::{extract_zip, Archive, Event, Instant, KeyCode, Platform, Screen};
+use wie_backend::{
+    extract_zip_with_encoding, App, Archive, Carrier, DeviceProfile, Event, HeadlessPlatform, Instant, KeyCode, KeyLayout, Platform, Screen,
+    TextEncoding, VirtualClock,
+};
 use wie_j2me::J2MEArchive;
 use wie_ktf::KtfArchive;
 use wie_lgt::LgtArchive;
@@ -23,19 +32,28 @@ use wie_skt::SktArchive;
 use self::{
     audio_sink::AudioSink,
     database::DatabaseRepository,
+    file_storage::FileStorage,
+    gdb::GdbServer,
+    save_data::SaveDataCommand,
     window::{WindowCallbackEvent, WindowImpl},
 };
 
+const HEADLESS_TICK_INTERVAL: u64 = 16;
+
 struct WieCliPlatform {
     database_repository: DatabaseRepository,
+    file_storage: FileStorage,
     window: Box<dyn Screen>,
+    device_profile: DeviceProfile,
 }
 
 impl WieCliPlatform {
-    fn new(app_id: &str, window: Box<dyn Screen>) -> Self {
+    fn new(app_id: &str, window: Box<dyn Screen>, device_profile: DeviceProfile) -> Self {
         Self {
             database_repository: DatabaseRepository::new(app_id),
+            file_storage: FileStorage::new(app_id),
             window,
+            device_profile,
         }
     }
 }
@@ -52,18 +70,131 @@ impl Platform for WieCliPlatform {
         Instant::from_epoch_millis(since_the_epoch.as_millis() as _)
     }
 
+    fn is_clock_virtual(&self) -> bool {
+        false
+    }
+
     fn database_repository(&self) -> &dyn wie_backend::DatabaseRepository {
         &self.database_repository
     }
 
+    fn file_storage(&self) -> &dyn wie_backend::FileStorage {
+        &self.file_storage
+    }
+
     fn audio_sink(&self) -> Box<dyn wie_backend::AudioSink> {
-        Box::new(AudioSink)
+        Box::new(AudioSink::new())
+    }
+
+    fn device_profile(&self) -> &DeviceProfile {
+        &self.device_profile
     }
 }
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    filename: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(required = true)]
+    filename: Option<String>,
+    /// Run without a window, driven by a virtual clock
+    #[arg(long)]
+    headless: bool,
+    /// Number of ticks to run in headless mode
+    #[arg(long, default_value_t = 600)]
+    ticks: u64,
+    /// Wait for gdb remote connection on given port before starting
+    #[arg(long)]
+    gdb: Option<u16>,
+    /// Text encoding of the app, detected from the archive if not given (euc-kr, utf-8, iso-8859-1, shift_jis)
+    #[arg(long, value_parser = parse_encoding)]
+    encoding: Option<TextEncoding>,
+    /// Save the last frame to given png or bmp file in headless mode
+    #[arg(long)]
+    screenshot: Option<PathBuf>,
+    /// Save every frame to given directory in headless mode
+    #[arg(long)]
+    dump_frames: Option<PathBuf>,
+    #[command(flatten)]
+    device_profile: DeviceProfileArgs,
+}
+
+// overrides of the device profile detected from the archive
+#[derive(ClapArgs)]
+pub struct DeviceProfileArgs {
+    /// Carrier of the emulated handset (ktf, skt, lgt, generic)
+    #[arg(long, value_parser = parse_carrier)]
+    carrier: Option<Carrier>,
+    /// Screen size of the emulated handset, such as 128x160, 176x220, 240x320 or 240x400
+    #[arg(long, value_parser = parse_screen_size)]
+    screen: Option<(u32, u32)>,
+    /// Key layout of the emulated handset (dpad, numpad)
+    #[arg(long, value_parser = parse_key_layout)]
+    key_layout: Option<KeyLayout>,
+    /// Handset property reported to the app, as NAME=VALUE. Can be given multiple times
+    #[arg(long = "property", value_parser = parse_property)]
+    properties: Vec<(String, String)>,
+}
+
+impl DeviceProfileArgs {
+    fn apply(&self, mut device_profile: DeviceProfile) -> DeviceProfile {
+        if let Some(x) = self.carrier {
+            device_profile.carrier = x;
+        }
+        if let Some((width, height)) = self.screen {
+            device_profile = device_profile.with_screen_size(width, height);
+        }
+        if let Some(x) = self.key_layout {
+            device_profile = device_profile.with_key_layout(x);
+        }
+        for (name, value) in &self.properties {
+            device_profile = device_profile.with_property(name, value);
+        }
+
+        tracing::info!(
+            "Using {} device profile, {}x{} screen",
+            device_profile.carrier.name(),
+            device_profile.screen_width,
+            device_profile.screen_height
+        );
+
+        device_profile
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage databases and files saved by an app
+    Save {
+        filename: String,
+        #[command(subcommand)]
+        command: SaveDataCommand,
+    },
+}
+
+fn parse_encoding(name: &str) -> Result<TextEncoding, String> {
+    TextEncoding::from_name(name).ok_or_else(|| format!("Unknown encoding {}", name))
+}
+
+fn parse_carrier(name: &str) -> Result<Carrier, String> {
+    Carrier::from_name(name).ok_or_else(|| format!("Unknown carrier {}", name))
+}
+
+fn parse_screen_size(value: &str) -> Result<(u32, u32), String> {
+    DeviceProfile::parse_screen_size(value).ok_or_else(|| format!("Invalid screen size {}", value))
+}
+
+fn parse_key_layout(name: &str) -> Result<KeyLayout, String> {
+    KeyLayout::from_name(name).ok_or_else(|| format!("Unknown key layout {}", name))
+}
+
+fn parse_property(value: &str) -> Result<(String, String), String> {
+    let (name, value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Property {} is not in NAME=VALUE form", value))?;
+
+    Ok((name.to_owned(), value.to_owned()))
 }
 
 fn main() -> anyhow::Result<()> {
@@ -72,20 +203,39 @@ fn main() -> anyhow::Result<()> {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    start(&Args::parse().filename)
+    let args = Args::parse();
+
+    if let Some(Command::Save { filename, command }) = args.command {
+        let archive = load_archive(&filename, args.encoding)?;
+
+        return save_data::run(&archive.id(), command);
+    }
+
+    let filename = args.filename.unwrap(); // required without a subcommand
+    if args.headless {
+        let output = HeadlessOutput {
+            screenshot: args.screenshot,
+            dump_frames: args.dump_frames,
+        };
+
+        start_headless(&filename, args.ticks, args.gdb, args.encoding, &args.device_profile, output)
+    } else {
+        start(&filename, args.gdb, args.encoding, &args.device_profile)
+    }
 }
 
-pub fn start(filename: &str) -> anyhow::Result<()> {
+fn load_archive(filename: &str, encoding: Option<TextEncoding>) -> anyhow::Result<Box<dyn Archive>> {
     let buf = fs::read(filename)?;
-    let archive: Box<dyn Archive> = if filename.ends_with("zip") {
-        let files = extract_zip(&buf).unwrap();
+    let mut archive: Box<dyn Archive> = if filename.ends_with("zip") {
+        // descriptor and jar names are ascii, so only other entries depend on the encoding
+        let files = extract_zip_with_encoding(&buf, encoding.unwrap_or(TextEncoding::EucKr))?;
 
         if KtfArchive::is_ktf_archive(&files) {
-            Box::new(KtfArchive::from_zip(files)?)
+            Box::new(KtfArchive::from_zip(files, encoding)?)
         } else if LgtArchive::is_lgt_archive(&files) {
-            Box::new(LgtArchive::from_zip(files)?)
+            Box::new(LgtArchive::from_zip(files, encoding)?)
         } else if SktArchive::is_skt_archive(&files) {
-            Box::new(SktArchive::from_zip(files)?)
+            Box::new(SktArchive::from_zip(files, encoding)?)
         } else {
             anyhow::bail!("Unknown archive format");
         }
@@ -110,20 +260,63 @@ pub fn start(filename: &str) -> anyhow::Result<()> {
         anyhow::bail!("Unknown file format");
     };
 
-    let window = WindowImpl::new(240, 320).unwrap(); // TODO hardcoded size
-    let platform = WieCliPlatform::new(&archive.id(), Box::new(window.handle()));
+    if let Some(x) = encoding {
+        archive.set_encoding(x);
+    }
+    tracing::info!("Using {} encoding", archive.encoding().name());
+
+    Ok(archive)
+}
+
+pub fn start(filename: &str, gdb_port: Option<u16>, encoding: Option<TextEncoding>, device_profile: &DeviceProfileArgs) -> anyhow::Result<()> {
+    let archive = load_archive(filename, encoding)?;
+    let device_profile = device_profile.apply(archive.device_profile());
+    let key_layout = device_profile.key_layout;
+
+    let window = WindowImpl::new(device_profile.screen_width, device_profile.screen_height).unwrap();
+    let platform = WieCliPlatform::new(&archive.id(), Box::new(window.handle()), device_profile);
+    let state_path = quick_save_path(&archive.id());
+    let app_id = archive.id();
+    let frame = window.frame();
 
     let mut app = archive.load_app(Box::new(platform))?;
+    let mut gdb = gdb_port.map(|x| start_gdb(app.as_mut(), x)).transpose()?;
 
     app.start()?;
 
     let mut key_events = HashSet::new();
     window.run(move |event| {
         match event {
-            WindowCallbackEvent::Update => app.tick()?,
+            WindowCallbackEvent::Update => {
+                if let Some(x) = &mut gdb {
+                    x.poll()?;
+                }
+                app.tick()?
+            }
             WindowCallbackEvent::Redraw => app.on_event(Event::Redraw),
+            WindowCallbackEvent::Keydown(PhysicalKey::Code(WinitKeyCode::F5)) => {
+                if let Err(x) = quick_save(app.as_mut(), &state_path) {
+                    tracing::error!(target: "wie", "Quick save failed: {:?}", x);
+                }
+            }
+            WindowCallbackEvent::Keydown(PhysicalKey::Code(WinitKeyCode::F9)) => {
+                if let Err(x) = quick_load(app.as_mut(), &state_path) {
+                    tracing::error!(target: "wie", "Quick load failed: {:?}", x);
+                }
+            }
+            WindowCallbackEvent::Keydown(PhysicalKey::Code(WinitKeyCode::F12)) => {
+                let frame = frame.borrow();
+                if frame.width != 0 {
+                    let path = screenshot::screenshot_path(&app_id, SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis());
+
+                    match screenshot::save_frame(&frame, &path) {
+                        Ok(()) => tracing::info!("Saved screenshot to {:?}", path),
+                        Err(x) => tracing::error!(target: "wie", "Screenshot failed: {:?}", x),
+                    }
+                }
+            }
             WindowCallbackEvent::Keydown(x) => {
-                if let Some(keycode) = convert_key(x) {
+                if let Some(keycode) = convert_key(x, key_layout) {
                     if !key_events.contains(&keycode) {
                         app.on_event(Event::Keydown(keycode));
                         key_events.insert(keycode);
@@ -131,7 +324,7 @@ pub fn start(filename: &str) -> anyhow::Result<()> {
                 }
             }
             WindowCallbackEvent::Keyup(x) => {
-                if let Some(keycode) = convert_key(x) {
+                if let Some(keycode) = convert_key(x, key_layout) {
                     if key_events.contains(&keycode) {
                         key_events.remove(&keycode);
                     }
@@ -144,7 +337,106 @@ pub fn start(filename: &str) -> anyhow::Result<()> {
     })
 }
 
-fn convert_key(key: PhysicalKey) -> Option<KeyCode> {
+fn quick_save_path(app_id: &str) -> PathBuf {
+    let base_dir = ProjectDirs::from("net", "dlunch", "wie").unwrap();
+
+    base_dir.data_dir().join("states").join(format!("{}.state", app_id))
+}
+
+fn quick_save(app: &mut dyn App, path: &Path) -> anyhow::Result<()> {
+    let state = app.save_state()?;
+
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, state)?;
+
+    tracing::info!("Saved state to {:?}", path);
+
+    Ok(())
+}
+
+fn quick_load(app: &mut dyn App, path: &Path) -> anyhow::Result<()> {
+    let state = fs::read(path)?;
+
+    app.load_state(&state)?;
+
+    tracing::info!("Loaded state from {:?}", path);
+
+    Ok(())
+}
+
+fn start_gdb(app: &mut dyn App, port: u16) -> anyhow::Result<GdbServer> {
+    let debugger = app
+        .debugger()
+        .ok_or_else(|| anyhow::anyhow!("Debugging is not supported on this platform"))?;
+
+    GdbServer::listen(port, debugger)
+}
+
+pub struct HeadlessOutput {
+    pub screenshot: Option<PathBuf>,
+    pub dump_frames: Option<PathBuf>,
+}
+
+pub fn start_headless(
+    filename: &str,
+    ticks: u64,
+    gdb_port: Option<u16>,
+    encoding: Option<TextEncoding>,
+    device_profile: &DeviceProfileArgs,
+    output: HeadlessOutput,
+) -> anyhow::Result<()> {
+    let archive = load_archive(filename, encoding)?;
+    let device_profile = device_profile.apply(archive.device_profile());
+
+    let clock = VirtualClock::new(0);
+    let platform =
+        HeadlessPlatform::new(device_profile.screen_width, device_profile.screen_height, clock.clone()).with_device_profile(device_profile);
+    let frames = platform.frames();
+
+    let mut app = archive.load_app(Box::new(platform))?;
+    let mut gdb = gdb_port.map(|x| start_gdb(app.as_mut(), x)).transpose()?;
+
+    app.start()?;
+
+    for _ in 0..ticks {
+        if let Some(x) = &mut gdb {
+            x.poll()?;
+        }
+        app.tick()?;
+        app.on_event(Event::Redraw);
+
+        clock.advance(HEADLESS_TICK_INTERVAL);
+    }
+
+    tracing::info!("Ran {} ticks, captured {} frames", ticks, frames.borrow().len());
+
+    if let Some(x) = output.dump_frames {
+        screenshot::dump_frames(&frames.borrow(), &x)?;
+    }
+    if let Some(x) = output.screenshot {
+        let frames = frames.borrow();
+        let frame = frames.last().ok_or_else(|| anyhow::anyhow!("No frame was drawn"))?;
+
+        screenshot::save_frame(frame, &x)?;
+        tracing::info!("Saved screenshot to {:?}", x);
+    }
+
+    Ok(())
+}
+
+// directional keys stand for the number keys around 5 on handsets without them
+fn convert_key(key: PhysicalKey, key_layout: KeyLayout) -> Option<KeyCode> {
+    if key_layout == KeyLayout::NumberPad {
+        match key {
+            PhysicalKey::Code(WinitKeyCode::ArrowUp) => return Some(KeyCode::NUM2),
+            PhysicalKey::Code(WinitKeyCode::ArrowDown) => return Some(KeyCode::NUM8),
+            PhysicalKey::Code(WinitKeyCode::ArrowLeft) => return Some(KeyCode::NUM4),
+            PhysicalKey::Code(WinitKeyCode::ArrowRight) => return Some(KeyCode::NUM6),
+            PhysicalKey::Code(WinitKeyCode::Space) => return Some(KeyCode::NUM5),
+            _ => {}
+        }
+    }
+
     match key {
         PhysicalKey::Code(WinitKeyCode::Digit1) => Some(KeyCode::NUM1),
         PhysicalKey::Code(WinitKeyCode::Digit2) => Some(KeyCode::NUM2),
@@ -0,0 +1,80 @@
+use std::fs;
+
+use clap::Subcommand;
+
+use wie_backend::{DatabaseRepository as _, FileStorage as _, SaveData};
+
+use crate::{database::DatabaseRepository, file_storage::FileStorage};
+
+#[derive(Subcommand)]
+pub enum SaveDataCommand {
+    /// List databases and files the app has saved
+    List,
+    /// Export save data to a portable zip archive
+    Export { output: String },
+    /// Import save data from an archive, replacing the current one
+    Import {
+        input: String,
+        /// Import even if the archive was exported from another app
+        #[arg(long)]
+        force: bool,
+    },
+    /// Delete all save data of the app
+    Wipe,
+}
+
+pub fn run(app_id: &str, command: SaveDataCommand) -> anyhow::Result<()> {
+    let database_repository = DatabaseRepository::new(app_id);
+    let file_storage = FileStorage::new(app_id);
+
+    match command {
+        SaveDataCommand::List => {
+            for name in database_repository.list()? {
+                let database = database_repository.open(&name, false)?;
+                let ids = database.get_record_ids()?;
+                let size = ids.iter().map(|&x| database.get_record_size(x)).sum::<Result<usize, _>>()?;
+
+                println!("database {}: {} records, {} bytes", name, ids.len(), size);
+            }
+
+            for path in file_storage.list() {
+                let size = file_storage.read(&path).map(|x| x.len()).unwrap_or(0);
+
+                println!("file {}: {} bytes", path, size);
+            }
+        }
+        SaveDataCommand::Export { output } => {
+            let save_data = SaveData::collect(app_id, &database_repository, &file_storage)?;
+            fs::write(&output, save_data.to_archive()?)?;
+
+            println!(
+                "Exported {} databases and {} files to {}",
+                save_data.databases.len(),
+                save_data.files.len(),
+                output
+            );
+        }
+        SaveDataCommand::Import { input, force } => {
+            let save_data = SaveData::from_archive(&fs::read(&input)?)?;
+            if save_data.app_id != app_id && !force {
+                anyhow::bail!("Save data is for {}, not {}. Use --force to import anyway", save_data.app_id, app_id);
+            }
+
+            save_data.restore(&database_repository, &file_storage)?;
+
+            println!(
+                "Imported {} databases and {} files from {}",
+                save_data.databases.len(),
+                save_data.files.len(),
+                input
+            );
+        }
+        SaveDataCommand::Wipe => {
+            SaveData::wipe(&database_repository, &file_storage)?;
+
+            println!("Deleted save data of {}", app_id);
+        }
+    }
+
+    Ok(())
+}
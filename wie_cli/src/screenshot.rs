@@ -0,0 +1,49 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+
+use wie_backend::{
+    canvas::{encode_image, ArgbPixel, Image, ImageFormat, VecImageBuffer},
+    CapturedFrame,
+};
+
+// format is taken from the extension, png if there is none we know
+pub fn save_image(image: &dyn Image, path: &Path) -> anyhow::Result<()> {
+    let format = path
+        .extension()
+        .and_then(|x| x.to_str())
+        .and_then(ImageFormat::from_extension)
+        .unwrap_or(ImageFormat::Png);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, encode_image(image, format)?)?;
+
+    Ok(())
+}
+
+pub fn save_frame(frame: &CapturedFrame, path: &Path) -> anyhow::Result<()> {
+    let image = VecImageBuffer::<ArgbPixel>::from_raw(frame.width, frame.height, frame.data.clone());
+
+    save_image(&image, path)
+}
+
+pub fn dump_frames(frames: &[CapturedFrame], directory: &Path) -> anyhow::Result<()> {
+    for (i, frame) in frames.iter().enumerate() {
+        save_frame(frame, &directory.join(format!("frame_{:05}.png", i)))?;
+    }
+
+    tracing::info!("Dumped {} frames to {:?}", frames.len(), directory);
+
+    Ok(())
+}
+
+pub fn screenshot_path(app_id: &str, timestamp: u128) -> PathBuf {
+    let base_dir = ProjectDirs::from("net", "dlunch", "wie").unwrap();
+
+    base_dir.data_dir().join("screenshots").join(format!("{}_{}.png", app_id, timestamp))
+}
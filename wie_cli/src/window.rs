@@ -1,5 +1,5 @@
 use alloc::rc::Rc;
-use core::{fmt::Debug, num::NonZeroU32};
+use core::{cell::RefCell, fmt::Debug, num::NonZeroU32};
 
 use softbuffer::{Context, Surface};
 use winit::{
@@ -10,12 +10,15 @@ use winit::{
     window::{Window as WinitWindow, WindowBuilder},
 };
 
-use wie_backend::{canvas::Image, Screen};
+use wie_backend::{
+    canvas::{Image, Rect},
+    CapturedFrame, Screen,
+};
 
 #[derive(Debug)]
 pub enum WindowInternalEvent {
     RequestRedraw,
-    Paint(Vec<u32>),
+    Paint(Rect),
 }
 
 pub enum WindowCallbackEvent {
@@ -25,10 +28,14 @@ pub enum WindowCallbackEvent {
     Keyup(PhysicalKey),
 }
 
+// contents of the window, also used for screenshots as the window surface can't be read back
+pub type SharedFrame = Rc<RefCell<CapturedFrame>>;
+
 pub struct WindowHandle {
     width: u32,
     height: u32,
     event_loop_proxy: EventLoopProxy<WindowInternalEvent>,
+    frame: SharedFrame,
 }
 
 impl WindowHandle {
@@ -52,20 +59,19 @@ impl Screen for WindowHandle {
         self.height
     }
 
-    fn paint(&mut self, image: &dyn Image) {
-        let data = image
-            .colors()
-            .iter()
-            .map(|x| ((x.a as u32) << 24) | ((x.r as u32) << 16) | ((x.g as u32) << 8) | (x.b as u32))
-            .collect::<Vec<_>>();
+    fn paint(&mut self, image: &dyn Image, area: &Rect) {
+        let area = self.frame.borrow_mut().update(image, area);
 
-        self.send_event(WindowInternalEvent::Paint(data)).unwrap()
+        if !area.is_empty() {
+            self.send_event(WindowInternalEvent::Paint(area)).unwrap()
+        }
     }
 }
 
 pub struct WindowImpl {
     window: Rc<WinitWindow>,
     event_loop: EventLoop<WindowInternalEvent>,
+    frame: SharedFrame,
 }
 
 impl WindowImpl {
@@ -81,6 +87,7 @@ impl WindowImpl {
         Ok(Self {
             window: Rc::new(window),
             event_loop,
+            frame: Rc::new(RefCell::new(CapturedFrame::empty())),
         })
     }
 
@@ -89,9 +96,14 @@ impl WindowImpl {
             width: self.window.inner_size().width,
             height: self.window.inner_size().height,
             event_loop_proxy: self.event_loop.create_proxy(),
+            frame: self.frame.clone(),
         }
     }
 
+    pub fn frame(&self) -> SharedFrame {
+        self.frame.clone()
+    }
+
     fn callback<C, E>(event: WindowCallbackEvent, elwt: &EventLoopWindowTarget<WindowInternalEvent>, callback: &mut C)
     where
         C: FnMut(WindowCallbackEvent) -> Result<(), E> + 'static,
@@ -127,11 +139,17 @@ impl WindowImpl {
                 WindowInternalEvent::RequestRedraw => {
                     self.window.request_redraw();
                 }
-                WindowInternalEvent::Paint(data) => {
+                WindowInternalEvent::Paint(area) => {
                     let mut buffer = surface.buffer_mut().unwrap();
-                    buffer.copy_from_slice(&data);
-
-                    buffer.present().unwrap();
+                    buffer.copy_from_slice(&self.frame.borrow().data);
+
+                    let damage = softbuffer::Rect {
+                        x: area.x as _,
+                        y: area.y as _,
+                        width: NonZeroU32::new(area.width as _).unwrap(),
+                        height: NonZeroU32::new(area.height as _).unwrap(),
+                    };
+                    buffer.present_with_damage(&[damage]).unwrap();
                 }
             },
 
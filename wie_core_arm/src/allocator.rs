@@ -1,103 +1,386 @@
+use alloc::vec;
 use core::mem::size_of;
 
 use bytemuck::{Pod, Zeroable};
 
-use wie_util::{read_generic, round_up, write_generic};
+use wie_util::{read_generic, round_up, write_generic, ByteRead, ByteWrite};
 
 use crate::{
     core::{ArmCore, HEAP_BASE},
-    ArmCoreResult,
+    ArmCoreError, ArmCoreResult,
 };
 
 const HEAP_SIZE: u32 = 0x1000000;
 
+const BIN_COUNT: usize = 64;
+const SMALL_BIN_LIMIT: u32 = 0x100;
+const BLOCK_ALIGN: usize = 8;
+const MIN_BLOCK_SIZE: u32 = 16;
+
+const FLAG_IN_USE: u32 = 1;
+const FLAG_PREV_IN_USE: u32 = 2;
+const FLAG_MASK: u32 = 7;
+
+// heap bookkeeping lives on the top of the heap, so it is preserved along with emulated memory
 #[derive(Clone, Copy, Pod, Zeroable)]
 #[repr(C)]
-struct AllocationHeader {
+struct HeapControl {
+    bin_bitmap: [u32; 2],
+    used: u32,
+    peak_used: u32,
+    allocation_count: u32,
+    failed_allocation_count: u32,
+}
+
+const CONTROL_SIZE: u32 = (size_of::<HeapControl>() + BIN_COUNT * size_of::<u32>()) as u32;
+const CONTROL_BASE: u32 = HEAP_BASE + HEAP_SIZE - CONTROL_SIZE;
+const BINS_BASE: u32 = CONTROL_BASE + size_of::<HeapControl>() as u32;
+
+// in-use block with no payload, stops coalescing at the end of the arena
+const SENTINEL_SIZE: u32 = BLOCK_ALIGN as u32;
+const ARENA_END: u32 = CONTROL_BASE - SENTINEL_SIZE;
+const ARENA_SIZE: u32 = ARENA_END - HEAP_BASE;
+
+// Every block starts with a header containing its size and flags.
+// Free blocks additionally contain links to neighbours in the bin at +4, +8 and its size at the end, to find previous block on coalescing.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct BlockHeader {
     data: u32,
 }
 
-impl AllocationHeader {
-    pub fn new(size: u32, in_use: bool) -> Self {
+impl BlockHeader {
+    pub fn new(size: u32, in_use: bool, prev_in_use: bool) -> Self {
         Self {
-            data: size | (in_use as u32) << 31,
+            data: size | if in_use { FLAG_IN_USE } else { 0 } | if prev_in_use { FLAG_PREV_IN_USE } else { 0 },
         }
     }
 
     pub fn size(&self) -> u32 {
-        self.data & 0x7FFFFFFF
+        self.data & !FLAG_MASK
     }
 
     pub fn in_use(&self) -> bool {
-        self.data & 0x80000000 != 0
+        self.data & FLAG_IN_USE != 0
+    }
+
+    pub fn prev_in_use(&self) -> bool {
+        self.data & FLAG_PREV_IN_USE != 0
     }
 }
 
-// crude, slow allocator.. we need to refactor it to faster one
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AllocatorStats {
+    pub total: u32,
+    pub used: u32,
+    pub free: u32,
+    pub peak_used: u32,
+    pub largest_free_block: u32,
+    pub allocation_count: u32,
+    pub failed_allocation_count: u32,
+}
+
+// segregated free list allocator with boundary tags
 pub struct Allocator {}
 
 impl Allocator {
     pub fn init(core: &mut ArmCore) -> ArmCoreResult<(u32, u32)> {
         core.map(HEAP_BASE, HEAP_SIZE)?;
 
-        let header = AllocationHeader::new(HEAP_SIZE, false);
+        core.write_bytes(CONTROL_BASE, &vec![0; CONTROL_SIZE as usize])?;
 
-        write_generic(core, HEAP_BASE, header)?;
+        write_generic(core, HEAP_BASE, BlockHeader::new(ARENA_SIZE, false, true))?;
+        write_generic(core, ARENA_END, BlockHeader::new(SENTINEL_SIZE, true, false))?;
+        Self::insert_free(core, HEAP_BASE, ARENA_SIZE)?;
 
         Ok((HEAP_BASE, HEAP_SIZE))
     }
 
     pub fn alloc(core: &mut ArmCore, size: u32) -> ArmCoreResult<u32> {
-        let alloc_size = round_up(size as usize + size_of::<AllocationHeader>(), 4) as u32;
+        let Some(block_size) = Self::block_size(size) else {
+            return Self::out_of_memory(core, size);
+        };
 
-        let address = Self::find_address(core, alloc_size).unwrap();
+        let Some((block, free_size)) = Self::find_free(core, block_size)? else {
+            return Self::out_of_memory(core, size);
+        };
 
-        let previous_header: AllocationHeader = read_generic(core, address)?;
+        Self::unlink_free(core, block, free_size)?;
 
-        let header = AllocationHeader::new(alloc_size, true);
-        write_generic(core, address, header)?;
+        let header: BlockHeader = read_generic(core, block)?;
+        let allocated_size = Self::split(core, block, free_size, block_size, header.prev_in_use())?;
 
-        // write next
-        if previous_header.size() > alloc_size {
-            let next_header = AllocationHeader::new(previous_header.size() - alloc_size, false);
-            write_generic(core, address + alloc_size, next_header)?;
-        }
+        let mut control = Self::control(core)?;
+        control.used += allocated_size;
+        control.peak_used = control.peak_used.max(control.used);
+        control.allocation_count += 1;
+        write_generic(core, CONTROL_BASE, control)?;
 
-        tracing::trace!("Allocated {:#x} bytes at {:#x}", size, address + size_of::<AllocationHeader>() as u32);
+        tracing::trace!("Allocated {:#x} bytes at {:#x}", size, block + size_of::<BlockHeader>() as u32);
 
-        Ok(address + size_of::<AllocationHeader>() as u32)
+        Ok(block + size_of::<BlockHeader>() as u32)
     }
 
     pub fn free(core: &mut ArmCore, address: u32) -> ArmCoreResult<()> {
-        let base_address = address - size_of::<AllocationHeader>() as u32;
-
         tracing::trace!("Freeing {:#x}", address);
 
-        let header: AllocationHeader = read_generic(core, base_address)?;
-        assert!(header.in_use());
+        let (mut block, header) = Self::allocated_block(core, address)?;
+        let mut size = header.size();
+        let mut prev_in_use = header.prev_in_use();
+
+        let mut control = Self::control(core)?;
+        control.used -= size;
+        control.allocation_count -= 1;
+        write_generic(core, CONTROL_BASE, control)?;
+
+        let next_header: BlockHeader = read_generic(core, block + size)?;
+        if !next_header.in_use() {
+            Self::unlink_free(core, block + size, next_header.size())?;
+            size += next_header.size();
+        }
+
+        if !prev_in_use {
+            let prev_size: u32 = read_generic(core, block - size_of::<u32>() as u32)?;
+            let prev_header: BlockHeader = read_generic(core, block - prev_size)?;
 
-        let header = AllocationHeader::new(header.size(), false);
-        write_generic(core, base_address, header)?;
+            // clear the stale header so freeing this address again can be detected
+            write_generic(core, block, BlockHeader::new(0, false, false))?;
+
+            block -= prev_size;
+            size += prev_size;
+            prev_in_use = prev_header.prev_in_use();
+
+            Self::unlink_free(core, block, prev_size)?;
+        }
+
+        write_generic(core, block, BlockHeader::new(size, false, prev_in_use))?;
+        Self::insert_free(core, block, size)?;
+        Self::set_prev_in_use(core, block + size, false)?;
 
         Ok(())
     }
 
-    fn find_address(core: &ArmCore, request_size: u32) -> Option<u32> {
-        let mut cursor = HEAP_BASE;
-        loop {
-            let header: AllocationHeader = read_generic(core, cursor).ok()?;
-            if !header.in_use() && header.size() >= request_size {
-                return Some(cursor);
-            } else {
-                cursor += header.size();
+    pub fn realloc(core: &mut ArmCore, address: u32, size: u32) -> ArmCoreResult<u32> {
+        if address == 0 {
+            return Self::alloc(core, size);
+        }
+        if size == 0 {
+            Self::free(core, address)?;
+
+            return Ok(0);
+        }
+
+        let (block, header) = Self::allocated_block(core, address)?;
+        let Some(block_size) = Self::block_size(size) else {
+            return Self::out_of_memory(core, size);
+        };
+
+        let current_size = header.size();
+        let next_header: BlockHeader = read_generic(core, block + current_size)?;
+
+        let available_size = if block_size <= current_size {
+            current_size
+        } else if !next_header.in_use() && current_size + next_header.size() >= block_size {
+            Self::unlink_free(core, block + current_size, next_header.size())?;
+
+            current_size + next_header.size()
+        } else {
+            let new_address = Self::alloc(core, size)?;
+
+            let data = core.read_bytes(address, (current_size - size_of::<BlockHeader>() as u32).min(size))?;
+            core.write_bytes(new_address, &data)?;
+            Self::free(core, address)?;
+
+            return Ok(new_address);
+        };
+
+        let allocated_size = Self::split(core, block, available_size, block_size, header.prev_in_use())?;
+
+        let mut control = Self::control(core)?;
+        control.used = control.used - current_size + allocated_size;
+        control.peak_used = control.peak_used.max(control.used);
+        write_generic(core, CONTROL_BASE, control)?;
+
+        tracing::trace!("Reallocated {:#x} to {:#x} bytes", address, size);
+
+        Ok(address)
+    }
+
+    pub fn stats(core: &ArmCore) -> ArmCoreResult<AllocatorStats> {
+        let control = Self::control(core)?;
+
+        let mut largest_free_block = 0;
+        if let Some(index) = (0..BIN_COUNT).rev().find(|&x| control.bin_bitmap[x / 32] & (1 << (x % 32)) != 0) {
+            let mut cursor: u32 = read_generic(core, Self::bin_address(index))?;
+            while cursor != 0 {
+                let header: BlockHeader = read_generic(core, cursor)?;
+                largest_free_block = largest_free_block.max(header.size() - size_of::<BlockHeader>() as u32);
+
+                cursor = read_generic(core, cursor + 4)?;
+            }
+        }
+
+        Ok(AllocatorStats {
+            total: ARENA_SIZE,
+            used: control.used,
+            free: ARENA_SIZE - control.used,
+            peak_used: control.peak_used,
+            largest_free_block,
+            allocation_count: control.allocation_count,
+            failed_allocation_count: control.failed_allocation_count,
+        })
+    }
+
+    fn block_size(size: u32) -> Option<u32> {
+        if size > ARENA_SIZE {
+            return None;
+        }
+
+        Some((round_up(size as usize + size_of::<BlockHeader>(), BLOCK_ALIGN) as u32).max(MIN_BLOCK_SIZE))
+    }
+
+    fn out_of_memory(core: &mut ArmCore, size: u32) -> ArmCoreResult<u32> {
+        let mut control = Self::control(core)?;
+        control.failed_allocation_count += 1;
+        write_generic(core, CONTROL_BASE, control)?;
+
+        tracing::warn!("Out of memory while allocating {:#x} bytes", size);
+
+        Err(ArmCoreError::OutOfMemory)
+    }
+
+    fn allocated_block(core: &ArmCore, address: u32) -> ArmCoreResult<(u32, BlockHeader)> {
+        let block = address.wrapping_sub(size_of::<BlockHeader>() as u32);
+        if !(HEAP_BASE..ARENA_END).contains(&block) || block & (BLOCK_ALIGN as u32 - 1) != 0 {
+            return Err(ArmCoreError::InvalidFree(address));
+        }
+
+        let header: BlockHeader = read_generic(core, block)?;
+        if !header.in_use() || header.size() < MIN_BLOCK_SIZE || block + header.size() > ARENA_END {
+            return Err(ArmCoreError::InvalidFree(address));
+        }
+
+        Ok((block, header))
+    }
+
+    // marks `size` bytes of free block in use, and returns the rest to the bins if it's large enough to be a block
+    fn split(core: &mut ArmCore, block: u32, free_size: u32, size: u32, prev_in_use: bool) -> ArmCoreResult<u32> {
+        if free_size - size < MIN_BLOCK_SIZE {
+            write_generic(core, block, BlockHeader::new(free_size, true, prev_in_use))?;
+            Self::set_prev_in_use(core, block + free_size, true)?;
+
+            return Ok(free_size);
+        }
+
+        write_generic(core, block, BlockHeader::new(size, true, prev_in_use))?;
+
+        let mut rest = free_size - size;
+        let next_header: BlockHeader = read_generic(core, block + free_size)?;
+        if !next_header.in_use() {
+            Self::unlink_free(core, block + free_size, next_header.size())?;
+            rest += next_header.size();
+        }
+
+        write_generic(core, block + size, BlockHeader::new(rest, false, true))?;
+        Self::insert_free(core, block + size, rest)?;
+        Self::set_prev_in_use(core, block + size + rest, false)?;
+
+        Ok(size)
+    }
+
+    fn find_free(core: &ArmCore, size: u32) -> ArmCoreResult<Option<(u32, u32)>> {
+        let index = Self::bin_index(size);
+
+        // large bins hold a range of sizes, so we need to look for a fitting one
+        let mut cursor: u32 = read_generic(core, Self::bin_address(index))?;
+        while cursor != 0 {
+            let header: BlockHeader = read_generic(core, cursor)?;
+            if header.size() >= size {
+                return Ok(Some((cursor, header.size())));
             }
 
-            if cursor >= HEAP_BASE + HEAP_SIZE {
-                break;
+            cursor = read_generic(core, cursor + 4)?;
+        }
+
+        // every block in larger bins fits
+        let control = Self::control(core)?;
+        if let Some(index) = (index + 1..BIN_COUNT).find(|&x| control.bin_bitmap[x / 32] & (1 << (x % 32)) != 0) {
+            let block: u32 = read_generic(core, Self::bin_address(index))?;
+            let header: BlockHeader = read_generic(core, block)?;
+
+            return Ok(Some((block, header.size())));
+        }
+
+        Ok(None)
+    }
+
+    fn insert_free(core: &mut ArmCore, block: u32, size: u32) -> ArmCoreResult<()> {
+        let index = Self::bin_index(size);
+        let head: u32 = read_generic(core, Self::bin_address(index))?;
+
+        write_generic(core, block + 4, head)?;
+        write_generic(core, block + 8, 0u32)?;
+        write_generic(core, block + size - size_of::<u32>() as u32, size)?;
+        if head != 0 {
+            write_generic(core, head + 8, block)?;
+        }
+        write_generic(core, Self::bin_address(index), block)?;
+
+        let mut control = Self::control(core)?;
+        control.bin_bitmap[index / 32] |= 1 << (index % 32);
+        write_generic(core, CONTROL_BASE, control)?;
+
+        Ok(())
+    }
+
+    fn unlink_free(core: &mut ArmCore, block: u32, size: u32) -> ArmCoreResult<()> {
+        let index = Self::bin_index(size);
+        let next: u32 = read_generic(core, block + 4)?;
+        let prev: u32 = read_generic(core, block + 8)?;
+
+        if prev != 0 {
+            write_generic(core, prev + 4, next)?;
+        } else {
+            write_generic(core, Self::bin_address(index), next)?;
+
+            if next == 0 {
+                let mut control = Self::control(core)?;
+                control.bin_bitmap[index / 32] &= !(1 << (index % 32));
+                write_generic(core, CONTROL_BASE, control)?;
             }
         }
+        if next != 0 {
+            write_generic(core, next + 8, prev)?;
+        }
 
-        None
+        Ok(())
+    }
+
+    fn set_prev_in_use(core: &mut ArmCore, block: u32, prev_in_use: bool) -> ArmCoreResult<()> {
+        let header: BlockHeader = read_generic(core, block)?;
+
+        write_generic(core, block, BlockHeader::new(header.size(), header.in_use(), prev_in_use))?;
+
+        Ok(())
+    }
+
+    fn control(core: &ArmCore) -> ArmCoreResult<HeapControl> {
+        Ok(read_generic(core, CONTROL_BASE)?)
+    }
+
+    fn bin_index(size: u32) -> usize {
+        if size < SMALL_BIN_LIMIT {
+            (size / BLOCK_ALIGN as u32) as usize
+        } else {
+            let small_bins = (SMALL_BIN_LIMIT / BLOCK_ALIGN as u32) as usize;
+            let log2 = (31 - size.leading_zeros()) as usize;
+
+            (small_bins + log2 - SMALL_BIN_LIMIT.trailing_zeros() as usize).min(BIN_COUNT - 1)
+        }
+    }
+
+    fn bin_address(index: usize) -> u32 {
+        BINS_BASE + (index * size_of::<u32>()) as u32
     }
 }
 
@@ -105,12 +388,14 @@ impl Allocator {
 mod tests {
     use alloc::boxed::Box;
 
-    use crate::{Allocator, ArmCore, ArmCoreResult};
+    use wie_util::{ByteRead, ByteWrite};
+
+    use crate::{Allocator, ArmCore, ArmCoreError, ArmCoreResult};
 
-    use test_utils::TestPlatform;
+    use test_utils::test_platform;
 
     pub fn test_arm_core() -> ArmCore {
-        ArmCore::new(wie_backend::System::new(Box::new(TestPlatform), Box::new(()))).unwrap()
+        ArmCore::new(wie_backend::System::new(Box::new(test_platform()), Box::new(()))).unwrap()
     }
 
     #[test]
@@ -124,4 +409,88 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_allocator_coalesce() -> ArmCoreResult<()> {
+        let mut core = test_arm_core();
+
+        Allocator::init(&mut core)?;
+        let initial = Allocator::stats(&core)?;
+
+        let a = Allocator::alloc(&mut core, 0x100)?;
+        let b = Allocator::alloc(&mut core, 0x20)?;
+        let c = Allocator::alloc(&mut core, 0x100)?;
+        let d = Allocator::alloc(&mut core, 0x10)?;
+        assert!(a < b && b < c && c < d);
+
+        // freed block is reused
+        Allocator::free(&mut core, b)?;
+        assert_eq!(Allocator::alloc(&mut core, 0x18)?, b);
+
+        // a, b and c are merged into one block
+        Allocator::free(&mut core, a)?;
+        Allocator::free(&mut core, c)?;
+        Allocator::free(&mut core, b)?;
+        assert_eq!(Allocator::alloc(&mut core, 0x220)?, a);
+
+        let stats = Allocator::stats(&core)?;
+        assert_eq!(stats.allocation_count, 2);
+
+        Allocator::free(&mut core, a)?;
+        Allocator::free(&mut core, d)?;
+
+        let stats = Allocator::stats(&core)?;
+        assert_eq!(stats.used, 0);
+        assert_eq!(stats.largest_free_block, initial.largest_free_block);
+        assert_eq!(stats.free, stats.total);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allocator_realloc() -> ArmCoreResult<()> {
+        let mut core = test_arm_core();
+
+        Allocator::init(&mut core)?;
+
+        let a = Allocator::alloc(&mut core, 0x10)?;
+        core.write_bytes(a, &[1, 2, 3, 4])?;
+
+        // grows in place while next block is free
+        let a = Allocator::realloc(&mut core, a, 0x100)?;
+        assert_eq!(a, 0x40000004);
+
+        let b = Allocator::alloc(&mut core, 0x10)?;
+
+        // moves if it can't grow
+        let a = Allocator::realloc(&mut core, a, 0x200)?;
+        assert!(a > b);
+        assert_eq!(core.read_bytes(a, 4)?, [1, 2, 3, 4]);
+
+        // shrinks in place
+        assert_eq!(Allocator::realloc(&mut core, a, 0x10)?, a);
+        assert_eq!(core.read_bytes(a, 4)?, [1, 2, 3, 4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allocator_errors() -> ArmCoreResult<()> {
+        let mut core = test_arm_core();
+
+        Allocator::init(&mut core)?;
+
+        assert!(matches!(Allocator::alloc(&mut core, 0x2000000), Err(ArmCoreError::OutOfMemory)));
+
+        let total = Allocator::stats(&core)?.largest_free_block;
+        let a = Allocator::alloc(&mut core, total)?;
+        assert!(matches!(Allocator::alloc(&mut core, 4), Err(ArmCoreError::OutOfMemory)));
+        assert_eq!(Allocator::stats(&core)?.failed_allocation_count, 2);
+
+        Allocator::free(&mut core, a)?;
+        assert!(matches!(Allocator::free(&mut core, a), Err(ArmCoreError::InvalidFree(_))));
+        assert!(matches!(Allocator::free(&mut core, 0x1234), Err(ArmCoreError::InvalidFree(_))));
+
+        Ok(())
+    }
 }
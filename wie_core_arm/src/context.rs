@@ -1,5 +1,7 @@
 use core::clone::Clone;
 
+use wie_util::{SnapshotReader, SnapshotResult, SnapshotWriter};
+
 #[derive(Clone)]
 pub struct ArmCoreContext {
     pub r0: u32,
@@ -44,3 +46,51 @@ impl ArmCoreContext {
         }
     }
 }
+
+impl ArmCoreContext {
+    pub(crate) fn save_state(&self, writer: &mut SnapshotWriter) {
+        for value in self.as_array() {
+            writer.write_u32(value);
+        }
+    }
+
+    pub(crate) fn load_state(reader: &mut SnapshotReader) -> SnapshotResult<Self> {
+        let mut values = [0; 17];
+        for value in values.iter_mut() {
+            *value = reader.read_u32()?;
+        }
+
+        Ok(Self::from_array(values))
+    }
+
+    pub(crate) fn from_array(values: [u32; 17]) -> Self {
+        let [r0, r1, r2, r3, r4, r5, r6, r7, r8, sb, sl, fp, ip, sp, lr, pc, cpsr] = values;
+
+        Self {
+            r0,
+            r1,
+            r2,
+            r3,
+            r4,
+            r5,
+            r6,
+            r7,
+            r8,
+            sb,
+            sl,
+            fp,
+            ip,
+            sp,
+            lr,
+            pc,
+            cpsr,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> [u32; 17] {
+        [
+            self.r0, self.r1, self.r2, self.r3, self.r4, self.r5, self.r6, self.r7, self.r8, self.sb, self.sl, self.fp, self.ip, self.sp, self.lr,
+            self.pc, self.cpsr,
+        ]
+    }
+}
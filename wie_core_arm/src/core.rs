@@ -1,27 +1,44 @@
 use alloc::{borrow::ToOwned, boxed::Box, collections::BTreeMap, format, rc::Rc, string::String, vec::Vec};
 use core::{cell::RefCell, fmt::Debug, mem::size_of};
 
-use wie_backend::{AsyncCallable, System};
-use wie_util::{read_generic, round_up, ByteRead, ByteWrite};
+use wie_backend::{AsyncCallable, JoinHandle, System, TaskOptions};
+use wie_util::{read_generic, round_up, ByteRead, ByteWrite, SnapshotReader, SnapshotWriter};
 
 use crate::{
     context::ArmCoreContext,
-    engine::{ArmEngine, ArmRegister, MemoryPermission},
+    debug::{DebugExecution, DebugState},
+    engine::{ArmEngine, ArmRegister, MemoryPermission, StopReason, WatchpointKind},
     function::{EmulatedFunction, RegisteredFunction, RegisteredFunctionHolder, ResultWriter},
     future::SpawnFuture,
-    ArmCoreResult,
+    symbol::SymbolTable,
+    ArmCoreError, ArmCoreResult,
 };
 
 const FUNCTIONS_BASE: u32 = 0x71000000;
 pub const RUN_FUNCTION_LR: u32 = 0x7f000000;
 pub const HEAP_BASE: u32 = 0x40000000;
 pub const PEB_BASE: u32 = 0x7ff00000;
+const DEBUGGER_POLL_INTERVAL: u64 = 10;
+const MAX_CALL_STACK_DEPTH: usize = 64;
+// prologues are at the start of functions, don't scan further into long ones
+const MAX_PROLOGUE_SCAN: u32 = 0x400;
 
 struct ArmCoreInner {
     engine: Box<dyn ArmEngine>,
     system: System,
     functions: BTreeMap<u32, Rc<Box<dyn RegisteredFunction>>>,
     functions_count: usize,
+    task_contexts: BTreeMap<u32, Rc<RefCell<ArmCoreContext>>>,
+    debug: DebugState,
+    symbols: SymbolTable,
+    image_base: Option<u32>,
+}
+
+// Save state read up front, so nothing is changed if the snapshot turns out to be invalid
+pub struct ArmCoreState {
+    context: ArmCoreContext,
+    task_contexts: BTreeMap<u32, ArmCoreContext>,
+    pages: Vec<(u32, MemoryPermission, Vec<u8>)>,
 }
 
 #[derive(Clone)]
@@ -41,6 +58,10 @@ impl ArmCore {
             system,
             functions: BTreeMap::new(),
             functions_count: 0,
+            task_contexts: BTreeMap::new(),
+            debug: DebugState::new(),
+            symbols: SymbolTable::new(),
+            image_base: None,
         };
 
         Ok(Self {
@@ -48,22 +69,51 @@ impl ArmCore {
         })
     }
 
-    pub fn load(&mut self, data: &[u8], address: u32, map_size: usize) -> ArmCoreResult<()> {
+    // pages shared with an earlier load keep the permissions of both
+    pub fn load(&mut self, data: &[u8], address: u32, map_size: usize, permission: MemoryPermission) -> ArmCoreResult<()> {
         let mut inner = self.inner.borrow_mut();
 
-        inner
-            .engine
-            .mem_map(address, round_up(map_size, 0x1000), MemoryPermission::ReadWriteExecute);
+        inner.engine.mem_map(address, round_up(map_size, 0x1000), permission);
         inner.engine.mem_write(address, data)?;
+        inner.image_base = Some(inner.image_base.map_or(address, |x| x.min(address)));
 
         Ok(())
     }
 
+    // lowest address code was loaded at
+    pub fn image_base(&self) -> u32 {
+        self.inner.borrow().image_base.unwrap_or(0)
+    }
+
+    pub fn set_symbols(&mut self, symbols: SymbolTable) {
+        tracing::debug!("Loaded {} symbols", symbols.len());
+
+        self.inner.borrow_mut().symbols = symbols;
+    }
+
+    // `function+offset` if address is covered by loaded symbols
+    pub fn describe_address(&self, address: u32) -> Option<String> {
+        self.inner.borrow().symbols.describe(address)
+    }
+
     #[allow(clippy::await_holding_refcell_ref)] // We manually drop RefMut https://github.com/rust-lang/rust-clippy/issues/6353
     async fn run_some(&mut self) -> ArmCoreResult<()> {
+        self.wait_while_halted().await;
+
         let mut inner = self.inner.borrow_mut();
 
-        inner.engine.run(RUN_FUNCTION_LR, FUNCTIONS_BASE..FUNCTIONS_BASE + 0x1000, 1000)?;
+        let stop_reason = if let Some(x) = inner.debug.pending_halt.take() {
+            Some(x)
+        } else {
+            inner.engine.run(RUN_FUNCTION_LR, FUNCTIONS_BASE..FUNCTIONS_BASE + 0x1000, 1000)?
+        };
+
+        if let Some(x) = stop_reason {
+            drop(inner);
+            self.halt(x).await?;
+
+            inner = self.inner.borrow_mut();
+        }
 
         let cur_pc = inner.engine.reg_read(ArmRegister::PC);
 
@@ -81,10 +131,83 @@ impl ArmCore {
         Ok(())
     }
 
+    // other tasks must not run while the debugger holds the core
+    async fn wait_while_halted(&mut self) {
+        loop {
+            let mut system = {
+                let inner = self.inner.borrow();
+                if !inner.debug.is_halted() {
+                    break;
+                }
+
+                inner.system.clone()
+            };
+
+            let until = system.platform().now() + DEBUGGER_POLL_INTERVAL;
+            system.sleep(until).await;
+        }
+    }
+
+    async fn halt(&mut self, mut reason: StopReason) -> ArmCoreResult<()> {
+        loop {
+            let mut system = {
+                let mut inner = self.inner.borrow_mut();
+
+                tracing::debug!("Halted: {:?}", reason);
+
+                let context = Self::read_context(&*inner.engine);
+                inner.debug.execution = DebugExecution::Halted;
+                inner.debug.halted_context = Some(context);
+                inner.debug.pending_report = Some(reason);
+                inner.debug.last_stop = Some(reason);
+
+                inner.system.clone()
+            };
+
+            let step = loop {
+                let until = system.platform().now() + DEBUGGER_POLL_INTERVAL;
+                system.sleep(until).await;
+
+                if let DebugExecution::Resuming { step } = self.inner.borrow().debug.execution {
+                    break step;
+                }
+            };
+
+            let mut inner = self.inner.borrow_mut();
+            let context = inner.debug.halted_context.take().unwrap();
+            Self::write_context(&mut *inner.engine, &context);
+
+            // native functions are not steppable, let run_some call it and halt after that
+            let pc = inner.engine.reg_read(ArmRegister::PC);
+            if pc == RUN_FUNCTION_LR || (FUNCTIONS_BASE..FUNCTIONS_BASE + 0x1000).contains(&pc) {
+                inner.debug.execution = DebugExecution::Running;
+                if step {
+                    inner.debug.pending_halt = Some(StopReason::Step);
+                }
+
+                return Ok(());
+            }
+
+            // step over current instruction ignoring breakpoint on it
+            let result = inner.engine.step()?;
+            match (result, step) {
+                (Some(x), _) => reason = x,
+                (None, true) => reason = StopReason::Step,
+                (None, false) => {
+                    inner.debug.execution = DebugExecution::Running;
+
+                    return Ok(());
+                }
+            }
+        }
+    }
+
     pub async fn run_function<R>(&mut self, address: u32, params: &[u32]) -> ArmCoreResult<R>
     where
         R: RunFunctionResult<R>,
     {
+        tracing::trace!("Run function at {:#x} {}", address, self.describe_address(address).unwrap_or_default());
+
         let previous_context = self.save_context(); // do we have to save context?
         {
             let mut inner = self.inner.borrow_mut();
@@ -130,14 +253,25 @@ impl ArmCore {
         Ok(result)
     }
 
-    pub fn spawn<C, R, E>(&mut self, callable: C)
+    pub fn spawn<C, R, E>(&mut self, callable: C) -> JoinHandle<R>
+    where
+        C: AsyncCallable<R, E> + 'static,
+        R: 'static,
+        E: Debug + 'static,
+    {
+        self.spawn_with(TaskOptions::new("arm"), callable)
+    }
+
+    pub fn spawn_with<C, R, E>(&mut self, options: TaskOptions, callable: C) -> JoinHandle<R>
     where
         C: AsyncCallable<R, E> + 'static,
         R: 'static,
         E: Debug + 'static,
     {
         let self_cloned = self.clone();
-        self.inner.borrow_mut().system.spawn(move || SpawnFuture::new(self_cloned, callable));
+        let mut system = self.inner.borrow().system.clone();
+
+        system.spawn_with(options, move || SpawnFuture::new(self_cloned, callable))
     }
 
     pub fn register_function<F, P, E, R>(&mut self, function: F) -> ArmCoreResult<u32>
@@ -174,58 +308,245 @@ impl ArmCore {
         Ok(())
     }
 
+    // Native code of running tasks lives on emulated memory, but futures driving them are not serializable.
+    // So loading is only consistent if the same tasks are alive, in the same state, as when the snapshot was taken.
+    pub fn save_state(&self, writer: &mut SnapshotWriter) {
+        let inner = self.inner.borrow();
+
+        writer.write_u32(inner.functions_count as _);
+        self.save_context().save_state(writer);
+
+        writer.write_u32(inner.task_contexts.len() as _);
+        for (stack_base, context) in &inner.task_contexts {
+            writer.write_u32(*stack_base);
+            context.borrow().save_state(writer);
+        }
+
+        let pages = inner.engine.mem_pages();
+        writer.write_u32(pages.len() as _);
+        for (address, permission, data) in pages {
+            writer.write_u32(address);
+            writer.write_u8(permission as _);
+            if data.iter().all(|&x| x == 0) {
+                writer.write_bytes(&[]);
+            } else {
+                writer.write_bytes(&data);
+            }
+        }
+    }
+
+    pub fn read_state(&self, reader: &mut SnapshotReader) -> ArmCoreResult<ArmCoreState> {
+        let functions_count = reader.read_u32()?;
+        if functions_count as usize != self.inner.borrow().functions_count {
+            return Err(ArmCoreError::InvalidSnapshot);
+        }
+
+        let context = ArmCoreContext::load_state(reader)?;
+
+        let task_count = reader.read_u32()?;
+        let mut task_contexts = BTreeMap::new();
+        for _ in 0..task_count {
+            let stack_base = reader.read_u32()?;
+            task_contexts.insert(stack_base, ArmCoreContext::load_state(reader)?);
+        }
+
+        let page_count = reader.read_u32()?;
+        let mut pages = Vec::new();
+        for _ in 0..page_count {
+            let address = reader.read_u32()?;
+            let permission = MemoryPermission::from_raw(reader.read_u8()?).ok_or(ArmCoreError::InvalidSnapshot)?;
+            pages.push((address, permission, reader.read_bytes()?.to_vec()));
+        }
+
+        Ok(ArmCoreState {
+            context,
+            task_contexts,
+            pages,
+        })
+    }
+
+    pub fn restore_state(&mut self, state: ArmCoreState) -> ArmCoreResult<()> {
+        let mut inner = self.inner.borrow_mut();
+        let ArmCoreState {
+            context,
+            mut task_contexts,
+            pages,
+        } = state;
+
+        // running futures would resume on registers and stacks of other tasks
+        if !inner.task_contexts.keys().eq(task_contexts.keys()) {
+            tracing::warn!(
+                "Tasks in snapshot {:x?} differ from running tasks {:x?}",
+                task_contexts.keys().collect::<Vec<_>>(),
+                inner.task_contexts.keys().collect::<Vec<_>>()
+            );
+
+            return Err(ArmCoreError::InvalidSnapshot);
+        }
+
+        for (stack_base, context) in &inner.task_contexts {
+            *context.borrow_mut() = task_contexts.remove(stack_base).unwrap();
+        }
+
+        inner.engine.mem_restore_pages(pages);
+        Self::write_context(&mut *inner.engine, &context);
+
+        Ok(())
+    }
+
+    pub fn debug_request_halt(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+
+        if !inner.debug.is_halted() {
+            inner.debug.pending_halt = Some(StopReason::Interrupt);
+        }
+    }
+
+    pub fn debug_resume(&mut self, step: bool) {
+        let mut inner = self.inner.borrow_mut();
+
+        if inner.debug.execution == DebugExecution::Halted {
+            inner.debug.execution = DebugExecution::Resuming { step };
+        } else {
+            inner.debug.pending_halt = step.then_some(StopReason::Step);
+        }
+    }
+
+    // drops every breakpoint, watchpoint and pending step, and lets the halted task run freely
+    pub fn debug_detach(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+
+        inner.engine.clear_breakpoints();
+        inner.engine.clear_watchpoints();
+        inner.debug.pending_halt = None;
+        inner.debug.pending_report = None;
+
+        if inner.debug.is_halted() {
+            inner.debug.execution = DebugExecution::Resuming { step: false };
+        }
+    }
+
+    pub fn debug_take_stop_event(&mut self) -> Option<StopReason> {
+        self.inner.borrow_mut().debug.pending_report.take()
+    }
+
+    pub fn debug_last_stop(&self) -> Option<StopReason> {
+        self.inner.borrow().debug.last_stop
+    }
+
+    pub fn debug_is_halted(&self) -> bool {
+        self.inner.borrow().debug.is_halted()
+    }
+
+    // registers of halted task, in r0..r15, cpsr order
+    pub fn debug_read_registers(&self) -> [u32; 17] {
+        let inner = self.inner.borrow();
+
+        match &inner.debug.halted_context {
+            Some(x) => x.as_array(),
+            None => Self::read_context(&*inner.engine).as_array(),
+        }
+    }
+
+    pub fn debug_write_registers(&mut self, registers: [u32; 17]) {
+        let mut inner = self.inner.borrow_mut();
+
+        let context = ArmCoreContext::from_array(registers);
+        if inner.debug.halted_context.is_some() {
+            inner.debug.halted_context = Some(context);
+        } else {
+            Self::write_context(&mut *inner.engine, &context);
+        }
+    }
+
+    pub fn debug_set_breakpoint(&mut self, address: u32, enabled: bool) {
+        let mut inner = self.inner.borrow_mut();
+
+        if enabled {
+            inner.engine.add_breakpoint(address);
+        } else {
+            inner.engine.remove_breakpoint(address);
+        }
+    }
+
+    pub fn debug_set_watchpoint(&mut self, address: u32, size: u32, kind: WatchpointKind, enabled: bool) {
+        let mut inner = self.inner.borrow_mut();
+
+        if enabled {
+            inner.engine.add_watchpoint(address, size, kind);
+        } else {
+            inner.engine.remove_watchpoint(address, size, kind);
+        }
+    }
+
+    pub(crate) fn register_task_context(&self, stack_base: u32, context: Rc<RefCell<ArmCoreContext>>) {
+        self.inner.borrow_mut().task_contexts.insert(stack_base, context);
+    }
+
+    pub(crate) fn unregister_task_context(&self, stack_base: u32) {
+        self.inner.borrow_mut().task_contexts.remove(&stack_base);
+    }
+
     pub fn dump_reg_stack(&self, image_base: u32) -> String {
         format!(
-            "\n{}\nPossible call stack:\n{}\nStack:\n{}",
+            "\n{}\n{}\nStack:\n{}",
             self.dump_regs(),
-            self.dump_call_stack(image_base).unwrap(),
-            self.dump_stack().unwrap()
+            self.dump_call_stack(image_base).unwrap_or_else(|x| format!("{}\n", x)),
+            self.dump_stack().unwrap_or_else(|x| format!("{}\n", x))
         )
     }
 
     pub fn restore_context(&mut self, context: &ArmCoreContext) {
         let mut inner = self.inner.borrow_mut();
 
-        inner.engine.reg_write(ArmRegister::R0, context.r0);
-        inner.engine.reg_write(ArmRegister::R1, context.r1);
-        inner.engine.reg_write(ArmRegister::R2, context.r2);
-        inner.engine.reg_write(ArmRegister::R3, context.r3);
-        inner.engine.reg_write(ArmRegister::R4, context.r4);
-        inner.engine.reg_write(ArmRegister::R5, context.r5);
-        inner.engine.reg_write(ArmRegister::R6, context.r6);
-        inner.engine.reg_write(ArmRegister::R7, context.r7);
-        inner.engine.reg_write(ArmRegister::R8, context.r8);
-        inner.engine.reg_write(ArmRegister::SB, context.sb);
-        inner.engine.reg_write(ArmRegister::SL, context.sl);
-        inner.engine.reg_write(ArmRegister::FP, context.fp);
-        inner.engine.reg_write(ArmRegister::IP, context.ip);
-        inner.engine.reg_write(ArmRegister::SP, context.sp);
-        inner.engine.reg_write(ArmRegister::LR, context.lr);
-        inner.engine.reg_write(ArmRegister::PC, context.pc);
-        inner.engine.reg_write(ArmRegister::Cpsr, context.cpsr);
+        Self::write_context(&mut *inner.engine, context);
+    }
+
+    fn write_context(engine: &mut dyn ArmEngine, context: &ArmCoreContext) {
+        engine.reg_write(ArmRegister::R0, context.r0);
+        engine.reg_write(ArmRegister::R1, context.r1);
+        engine.reg_write(ArmRegister::R2, context.r2);
+        engine.reg_write(ArmRegister::R3, context.r3);
+        engine.reg_write(ArmRegister::R4, context.r4);
+        engine.reg_write(ArmRegister::R5, context.r5);
+        engine.reg_write(ArmRegister::R6, context.r6);
+        engine.reg_write(ArmRegister::R7, context.r7);
+        engine.reg_write(ArmRegister::R8, context.r8);
+        engine.reg_write(ArmRegister::SB, context.sb);
+        engine.reg_write(ArmRegister::SL, context.sl);
+        engine.reg_write(ArmRegister::FP, context.fp);
+        engine.reg_write(ArmRegister::IP, context.ip);
+        engine.reg_write(ArmRegister::SP, context.sp);
+        engine.reg_write(ArmRegister::LR, context.lr);
+        engine.reg_write(ArmRegister::PC, context.pc);
+        engine.reg_write(ArmRegister::Cpsr, context.cpsr);
     }
 
     pub fn save_context(&self) -> ArmCoreContext {
         let inner = self.inner.borrow();
 
+        Self::read_context(&*inner.engine)
+    }
+
+    fn read_context(engine: &dyn ArmEngine) -> ArmCoreContext {
         ArmCoreContext {
-            r0: inner.engine.reg_read(ArmRegister::R0),
-            r1: inner.engine.reg_read(ArmRegister::R1),
-            r2: inner.engine.reg_read(ArmRegister::R2),
-            r3: inner.engine.reg_read(ArmRegister::R3),
-            r4: inner.engine.reg_read(ArmRegister::R4),
-            r5: inner.engine.reg_read(ArmRegister::R5),
-            r6: inner.engine.reg_read(ArmRegister::R6),
-            r7: inner.engine.reg_read(ArmRegister::R7),
-            r8: inner.engine.reg_read(ArmRegister::R8),
-            sb: inner.engine.reg_read(ArmRegister::SB),
-            sl: inner.engine.reg_read(ArmRegister::SL),
-            fp: inner.engine.reg_read(ArmRegister::FP),
-            ip: inner.engine.reg_read(ArmRegister::IP),
-            sp: inner.engine.reg_read(ArmRegister::SP),
-            lr: inner.engine.reg_read(ArmRegister::LR),
-            pc: inner.engine.reg_read(ArmRegister::PC),
-            cpsr: inner.engine.reg_read(ArmRegister::Cpsr),
+            r0: engine.reg_read(ArmRegister::R0),
+            r1: engine.reg_read(ArmRegister::R1),
+            r2: engine.reg_read(ArmRegister::R2),
+            r3: engine.reg_read(ArmRegister::R3),
+            r4: engine.reg_read(ArmRegister::R4),
+            r5: engine.reg_read(ArmRegister::R5),
+            r6: engine.reg_read(ArmRegister::R6),
+            r7: engine.reg_read(ArmRegister::R7),
+            r8: engine.reg_read(ArmRegister::R8),
+            sb: engine.reg_read(ArmRegister::SB),
+            sl: engine.reg_read(ArmRegister::SL),
+            fp: engine.reg_read(ArmRegister::FP),
+            ip: engine.reg_read(ArmRegister::IP),
+            sp: engine.reg_read(ArmRegister::SP),
+            lr: engine.reg_read(ArmRegister::LR),
+            pc: engine.reg_read(ArmRegister::PC),
+            cpsr: engine.reg_read(ArmRegister::Cpsr),
         }
     }
 
@@ -299,9 +620,13 @@ impl ArmCore {
     }
 
     fn is_code_address(address: u32, image_base: u32) -> bool {
+        address % 2 == 1 && Self::is_in_code(address, image_base)
+    }
+
+    fn is_in_code(address: u32, image_base: u32) -> bool {
         // TODO image size temp
 
-        address % 2 == 1 && ((image_base..image_base + 0x100000).contains(&address) || (FUNCTIONS_BASE..FUNCTIONS_BASE + 0x10000).contains(&address))
+        (image_base..image_base + 0x100000).contains(&address) || (FUNCTIONS_BASE..FUNCTIONS_BASE + 0x10000).contains(&address)
     }
 
     fn dump_regs(&self) -> String {
@@ -310,8 +635,10 @@ impl ArmCore {
         Self::dump_regs_inner(&*inner.engine)
     }
 
-    fn format_callstack_address(address: u32, image_base: u32) -> String {
-        let description = if (image_base..image_base + 0x100000).contains(&address) {
+    fn format_callstack_address(address: u32, image_base: u32, symbols: &SymbolTable) -> String {
+        let description = if let Some(x) = symbols.describe(address) {
+            x
+        } else if (image_base..image_base + 0x100000).contains(&address) {
             format!("<Base>+{:#x}", address - image_base)
         } else if (FUNCTIONS_BASE..FUNCTIONS_BASE + 0x10000).contains(&address) {
             "<Native function>".to_owned()
@@ -324,14 +651,33 @@ impl ArmCore {
 
     fn dump_call_stack(&self, image_base: u32) -> ArmCoreResult<String> {
         let mut inner = self.inner.borrow_mut();
+        let inner = &mut *inner;
 
         let sp = inner.engine.reg_read(ArmRegister::SP);
         let pc = inner.engine.reg_read(ArmRegister::PC);
         let lr = inner.engine.reg_read(ArmRegister::LR);
 
-        let mut call_stack = Self::format_callstack_address(pc, image_base);
+        let mut call_stack = Self::format_callstack_address(pc, image_base, &inner.symbols);
+
+        let frames = Self::unwind_frames(&mut *inner.engine, image_base);
+        if !frames.is_empty() {
+            // frame chain starts at the caller if current function hasn't saved lr yet
+            let thumb = inner.engine.reg_read(ArmRegister::Cpsr) & 0x20 != 0;
+            if Self::is_before_prologue(&mut *inner.engine, &inner.symbols, pc, thumb)
+                && lr != RUN_FUNCTION_LR
+                && Self::is_in_code(lr & !1, image_base)
+            {
+                call_stack += &Self::format_callstack_address(lr - 5, image_base, &inner.symbols);
+            }
+            for return_address in frames {
+                call_stack += &Self::format_callstack_address(return_address - 5, image_base, &inner.symbols);
+            }
+
+            return Ok(format!("Call stack:\n{}", call_stack));
+        }
+
         if lr != RUN_FUNCTION_LR && lr != 0 {
-            call_stack += &Self::format_callstack_address(lr - 5, image_base);
+            call_stack += &Self::format_callstack_address(lr - 5, image_base, &inner.symbols);
         }
 
         for i in 0..128 {
@@ -340,11 +686,76 @@ impl ArmCore {
             let value_u32 = u32::from_le_bytes(value.try_into().unwrap());
 
             if value_u32 > 5 && Self::is_code_address(value_u32 - 4, image_base) {
-                call_stack += &Self::format_callstack_address(value_u32 - 5, image_base);
+                call_stack += &Self::format_callstack_address(value_u32 - 5, image_base, &inner.symbols);
             }
         }
 
-        Ok(call_stack)
+        Ok(format!("Possible call stack:\n{}", call_stack))
+    }
+
+    // No `push {.., lr}` between the start of the function and pc. Unknown without a symbol for the function
+    fn is_before_prologue(engine: &mut dyn ArmEngine, symbols: &SymbolTable, pc: u32, thumb: bool) -> bool {
+        let Some((_, offset)) = symbols.lookup(pc) else {
+            return false;
+        };
+        if offset > MAX_PROLOGUE_SCAN {
+            return false;
+        }
+
+        let Ok(code) = engine.mem_read(pc - offset, offset as usize) else {
+            return false;
+        };
+
+        if thumb {
+            // push {.., lr}
+            !code.chunks_exact(2).any(|x| u16::from_le_bytes([x[0], x[1]]) & 0xff00 == 0xb500)
+        } else {
+            // stmdb sp!, {.., lr} or str lr, [sp, #-4]!
+            !code
+                .chunks_exact(4)
+                .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+                .any(|x| x & 0xffff4000 == 0xe92d4000 || x == 0xe52de004)
+        }
+    }
+
+    // Follows saved frame pointer and return address pairs, as pushed by `push {r7, lr}; mov r7, sp` in thumb
+    // or `push {fp, lr}; add fp, sp, #4` in arm. Empty if the frame pointer doesn't lead to a valid chain.
+    fn unwind_frames(engine: &mut dyn ArmEngine, image_base: u32) -> Vec<u32> {
+        let thumb = engine.reg_read(ArmRegister::Cpsr) & 0x20 != 0;
+        let sp = engine.reg_read(ArmRegister::SP);
+        let mut fp = engine.reg_read(if thumb { ArmRegister::R7 } else { ArmRegister::FP });
+
+        let mut read_u32 = |address: u32| {
+            engine
+                .mem_read(address, size_of::<u32>())
+                .ok()
+                .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+        };
+
+        let mut frames = Vec::new();
+        while frames.len() < MAX_CALL_STACK_DEPTH && fp >= sp && fp % 4 == 0 {
+            let (next_fp, return_address) = if thumb {
+                (read_u32(fp), fp.checked_add(4).and_then(&mut read_u32))
+            } else {
+                (fp.checked_sub(4).and_then(&mut read_u32), read_u32(fp))
+            };
+            let (Some(next_fp), Some(return_address)) = (next_fp, return_address) else {
+                break;
+            };
+
+            if return_address == RUN_FUNCTION_LR || return_address <= 5 || !Self::is_in_code(return_address & !1, image_base) {
+                break;
+            }
+            frames.push(return_address);
+
+            // caller frames are always above on the stack
+            if next_fp <= fp {
+                break;
+            }
+            fp = next_fp;
+        }
+
+        frames
     }
 
     fn dump_stack(&self) -> ArmCoreResult<String> {
@@ -401,3 +812,158 @@ impl RunFunctionResult<u32> for u32 {
 impl RunFunctionResult<()> for () {
     fn get(_: &ArmCore) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc, vec::Vec};
+    use core::cell::RefCell;
+
+    use wie_backend::{Event, System};
+    use wie_util::{ByteRead, ByteWrite, SnapshotReader, SnapshotWriter};
+
+    use test_utils::test_platform;
+
+    use crate::{Allocator, ArmCore, ArmCoreError, ArmCoreResult, MemoryAccessKind, MemoryPermission};
+
+    fn save(core: &ArmCore, system: &System) -> Vec<u8> {
+        let mut writer = SnapshotWriter::new();
+        core.save_state(&mut writer);
+        system.save_state(&mut writer);
+
+        writer.into_inner()
+    }
+
+    #[test]
+    fn test_state_round_trip() -> anyhow::Result<()> {
+        let mut system = System::new(Box::new(test_platform()), Box::new(()));
+        let mut core = ArmCore::new(system.clone())?;
+
+        core.map(0x10000, 0x1000)?;
+        core.write_bytes(0x10000, b"saved")?;
+        let mut context = core.save_context();
+        context.r0 = 1234;
+        core.restore_context(&context);
+        system.event_queue().push(Event::Redraw);
+
+        let state = save(&core, &system);
+
+        core.write_bytes(0x10000, b"later")?;
+        context.r0 = 0;
+        core.restore_context(&context);
+        system.event_queue().pop();
+
+        let mut reader = SnapshotReader::new(&state);
+        let core_state = core.read_state(&mut reader)?;
+        let system_state = system.read_state(&mut reader)?;
+        reader.finish()?;
+        core.restore_state(core_state)?;
+        system.restore_state(system_state);
+
+        assert_eq!(core.read_bytes(0x10000, 5)?, b"saved");
+        assert_eq!(core.save_context().r0, 1234);
+        assert!(matches!(system.event_queue().pop(), Some(Event::Redraw)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_task_mismatch() -> anyhow::Result<()> {
+        let mut system = System::new(Box::new(test_platform()), Box::new(()));
+        let mut core = ArmCore::new(system.clone())?;
+        Allocator::init(&mut core)?;
+
+        core.map(0x10000, 0x1000)?;
+        core.write_bytes(0x10000, b"saved")?;
+
+        let state = save(&core, &system);
+
+        core.spawn(|| async {
+            core::future::pending::<()>().await;
+
+            Ok::<_, anyhow::Error>(())
+        });
+        system.tick()?;
+        core.write_bytes(0x10000, b"later")?;
+
+        let mut reader = SnapshotReader::new(&state);
+        let core_state = core.read_state(&mut reader)?;
+        assert!(matches!(core.restore_state(core_state), Err(ArmCoreError::InvalidSnapshot)));
+        assert_eq!(core.read_bytes(0x10000, 5)?, b"later");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spawn_out_of_memory() -> anyhow::Result<()> {
+        let mut system = System::new(Box::new(test_platform()), Box::new(()));
+        let mut core = ArmCore::new(system.clone())?;
+        Allocator::init(&mut core)?;
+
+        let free = Allocator::stats(&core)?.largest_free_block;
+        Allocator::alloc(&mut core, free)?;
+
+        let handle = core.spawn(|| async { Ok::<_, anyhow::Error>(()) });
+
+        let result = Rc::new(RefCell::new(None));
+        let result_clone = result.clone();
+        system.spawn(move || async move {
+            *result_clone.borrow_mut() = Some(handle.await);
+
+            Ok::<_, anyhow::Error>(())
+        });
+
+        // only the spawned task fails
+        system.tick()?;
+        assert!(matches!(*result.borrow(), Some(Err(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_permission() -> anyhow::Result<()> {
+        let mut system = System::new(Box::new(test_platform()), Box::new(()));
+        let mut core = ArmCore::new(system.clone())?;
+        Allocator::init(&mut core)?;
+
+        // ldr r1, [pc, #4]; str r0, [r1]; bx lr; .word 0x100000
+        let code = [0xe59f1004u32, 0xe5810000, 0xe12fff1e, 0x100000]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        core.load(&code, 0x100000, code.len(), MemoryPermission::ReadExecute)?;
+
+        let result = Rc::new(RefCell::new(None));
+        let result_clone = result.clone();
+        let mut core_clone = core.clone();
+        core.spawn(move || async move {
+            *result_clone.borrow_mut() = Some(core_clone.run_function::<u32>(0x100000, &[]).await);
+
+            Ok::<_, anyhow::Error>(())
+        });
+        system.tick()?;
+
+        assert!(matches!(
+            *result.borrow(),
+            Some(Err(ArmCoreError::MemoryFault {
+                address: 0x100000,
+                access: MemoryAccessKind::Write,
+                ..
+            }))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_truncated() -> ArmCoreResult<()> {
+        let system = System::new(Box::new(test_platform()), Box::new(()));
+        let core = ArmCore::new(system.clone())?;
+
+        let state = save(&core, &system);
+
+        let mut reader = SnapshotReader::new(&state[..state.len() / 2]);
+        assert!(core.read_state(&mut reader).is_err());
+
+        Ok(())
+    }
+}
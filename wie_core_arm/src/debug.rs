@@ -0,0 +1,33 @@
+use crate::{context::ArmCoreContext, engine::StopReason};
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub(crate) enum DebugExecution {
+    Running,
+    Halted,
+    Resuming { step: bool },
+}
+
+// Execution state shared between the debugger frontend and the halted task
+pub(crate) struct DebugState {
+    pub execution: DebugExecution,
+    pub halted_context: Option<ArmCoreContext>,
+    pub pending_halt: Option<StopReason>,
+    pub pending_report: Option<StopReason>,
+    pub last_stop: Option<StopReason>,
+}
+
+impl DebugState {
+    pub fn new() -> Self {
+        Self {
+            execution: DebugExecution::Running,
+            halted_context: None,
+            pending_halt: None,
+            pending_report: None,
+            last_stop: None,
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.execution != DebugExecution::Running
+    }
+}
@@ -8,21 +8,79 @@ pub use armv4t_emu::Armv4tEmuEngine;
 use crate::ArmCoreResult;
 
 pub trait ArmEngine {
-    fn run(&mut self, end: u32, hook: Range<u32>, count: u32) -> ArmCoreResult<()>;
+    fn run(&mut self, end: u32, hook: Range<u32>, count: u32) -> ArmCoreResult<Option<StopReason>>;
+    fn step(&mut self) -> ArmCoreResult<Option<StopReason>>;
     fn reg_write(&mut self, reg: ArmRegister, value: u32);
     fn reg_read(&self, reg: ArmRegister) -> u32;
     fn mem_map(&mut self, address: u32, size: usize, permission: MemoryPermission);
     fn mem_write(&mut self, address: u32, data: &[u8]) -> ArmCoreResult<()>;
     fn mem_read(&mut self, address: u32, size: usize) -> ArmCoreResult<Vec<u8>>;
+    fn mem_pages(&self) -> Vec<(u32, MemoryPermission, Vec<u8>)>;
+    fn mem_restore_pages(&mut self, pages: Vec<(u32, MemoryPermission, Vec<u8>)>);
+    fn add_breakpoint(&mut self, address: u32);
+    fn remove_breakpoint(&mut self, address: u32);
+    fn add_watchpoint(&mut self, address: u32, size: u32, kind: WatchpointKind);
+    fn remove_watchpoint(&mut self, address: u32, size: u32, kind: WatchpointKind);
+    fn clear_breakpoints(&mut self);
+    fn clear_watchpoints(&mut self);
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WatchpointKind {
+    Read,
+    Write,
+    Access,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StopReason {
+    Breakpoint,
+    Watchpoint { address: u32, kind: WatchpointKind },
+    Step,
+    Interrupt,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemoryAccessKind {
+    Read,
+    Write,
+    Execute,
 }
 
 #[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MemoryPermission {
     ReadExecute = 5,
     ReadWrite = 6,
     ReadWriteExecute = 7,
 }
 
+impl MemoryPermission {
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            5 => Some(Self::ReadExecute),
+            6 => Some(Self::ReadWrite),
+            7 => Some(Self::ReadWriteExecute),
+            _ => None,
+        }
+    }
+
+    pub fn allows(self, access: MemoryAccessKind) -> bool {
+        let bit = match access {
+            MemoryAccessKind::Read => 4,
+            MemoryAccessKind::Write => 2,
+            MemoryAccessKind::Execute => 1,
+        };
+
+        self as u8 & bit != 0
+    }
+
+    // pages are shared between mappings, so keep every access any of them allowed
+    pub fn union(self, other: Self) -> Self {
+        Self::from_raw(self as u8 | other as u8).unwrap()
+    }
+}
+
 #[derive(Eq, PartialEq)]
 pub enum ArmRegister {
     R0,
@@ -1,13 +1,25 @@
-use alloc::{boxed::Box, vec::Vec};
-use core::{array, cell::RefCell, ops::Range};
+mod armv5te;
+
+use alloc::{boxed::Box, collections::BTreeSet, vec::Vec};
+use core::{
+    array,
+    cell::RefCell,
+    ops::{Range, RangeInclusive},
+};
 
 use armv4t_emu::{reg, Cpu, Memory, Mode};
 
-use crate::engine::{ArmCoreResult, ArmEngine, ArmRegister, MemoryPermission};
+use crate::{
+    engine::{ArmCoreResult, ArmEngine, ArmRegister, MemoryAccessKind, MemoryPermission, StopReason, WatchpointKind},
+    ArmCoreError,
+};
+
+use self::armv5te::Armv5teStep;
 
 pub struct Armv4tEmuEngine {
     cpu: Cpu,
     mem: Armv4tEmuMemory,
+    breakpoints: BTreeSet<u32>,
 }
 
 impl Armv4tEmuEngine {
@@ -15,23 +27,65 @@ impl Armv4tEmuEngine {
         Self {
             cpu: Cpu::new(),
             mem: Armv4tEmuMemory::new(),
+            breakpoints: BTreeSet::new(),
         }
     }
 }
 
 impl ArmEngine for Armv4tEmuEngine {
-    fn run(&mut self, end: u32, hook: Range<u32>, mut count: u32) -> ArmCoreResult<()> {
+    fn run(&mut self, end: u32, hook: Range<u32>, mut count: u32) -> ArmCoreResult<Option<StopReason>> {
         loop {
             let pc = self.cpu.reg_get(Mode::User, reg::PC);
             if pc == end || hook.contains(&pc) || count == 0 {
                 break;
             }
 
-            self.cpu.step(&mut self.mem);
+            if self.breakpoints.contains(&pc) {
+                return Ok(Some(StopReason::Breakpoint));
+            }
+
+            if let Some(x) = self.step()? {
+                return Ok(Some(x));
+            }
             count -= 1;
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    fn step(&mut self) -> ArmCoreResult<Option<StopReason>> {
+        let pc = self.cpu.reg_get(Mode::User, reg::PC);
+        if !self.mem.is_allowed(pc, MemoryAccessKind::Execute) {
+            return Err(ArmCoreError::MemoryFault {
+                address: pc,
+                access: MemoryAccessKind::Execute,
+                pc,
+            });
+        }
+
+        self.mem.fetch_address = Some(pc);
+        match armv5te::step(&mut self.cpu, &mut self.mem) {
+            Armv5teStep::Executed => {}
+            Armv5teStep::NotHandled => {
+                self.cpu.step(&mut self.mem);
+            }
+            Armv5teStep::LoadPc(address) => {
+                let value = self.mem.r32(address);
+
+                self.cpu.step(&mut self.mem);
+                armv5te::set_pc_interworking(&mut self.cpu, value);
+            }
+        }
+
+        self.mem.fetch_address = None;
+
+        if let Some((address, access)) = self.mem.fault.take() {
+            self.mem.watchpoint_hit = None;
+
+            return Err(ArmCoreError::MemoryFault { address, access, pc });
+        }
+
+        Ok(self.mem.watchpoint_hit.take())
     }
 
     fn reg_write(&mut self, reg: ArmRegister, value: u32) {
@@ -50,21 +104,72 @@ impl ArmEngine for Armv4tEmuEngine {
         self.cpu.reg_get(Mode::User, reg.into_armv4t())
     }
 
-    fn mem_map(&mut self, address: u32, size: usize, _permission: MemoryPermission) {
-        self.mem.map(address, size);
+    fn mem_map(&mut self, address: u32, size: usize, permission: MemoryPermission) {
+        self.mem.map(address, size, permission);
     }
 
+    // host accesses ignore page permissions, as the host is what loads code into read-execute pages
     fn mem_write(&mut self, address: u32, data: &[u8]) -> ArmCoreResult<()> {
+        if let Some(x) = self.mem.first_unmapped(address, data.len()) {
+            return Err(ArmCoreError::MemoryFault {
+                address: x,
+                access: MemoryAccessKind::Write,
+                pc: self.cpu.reg_get(Mode::User, reg::PC),
+            });
+        }
+
         self.mem.write_range(address, data);
 
         Ok(())
     }
 
     fn mem_read(&mut self, address: u32, size: usize) -> ArmCoreResult<Vec<u8>> {
+        if let Some(x) = self.mem.first_unmapped(address, size) {
+            return Err(ArmCoreError::MemoryFault {
+                address: x,
+                access: MemoryAccessKind::Read,
+                pc: self.cpu.reg_get(Mode::User, reg::PC),
+            });
+        }
+
         let result = self.mem.read_range(address, size);
 
         Ok(result)
     }
+
+    fn mem_pages(&self) -> Vec<(u32, MemoryPermission, Vec<u8>)> {
+        self.mem.pages()
+    }
+
+    fn mem_restore_pages(&mut self, pages: Vec<(u32, MemoryPermission, Vec<u8>)>) {
+        self.mem.restore_pages(pages);
+    }
+
+    fn add_breakpoint(&mut self, address: u32) {
+        self.breakpoints.insert(address);
+    }
+
+    fn remove_breakpoint(&mut self, address: u32) {
+        self.breakpoints.remove(&address);
+    }
+
+    fn add_watchpoint(&mut self, address: u32, size: u32, kind: WatchpointKind) {
+        self.mem.watchpoints.push((watchpoint_range(address, size), kind));
+    }
+
+    fn remove_watchpoint(&mut self, address: u32, size: u32, kind: WatchpointKind) {
+        let range = watchpoint_range(address, size);
+
+        self.mem.watchpoints.retain(|x| *x != (range.clone(), kind));
+    }
+
+    fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    fn clear_watchpoints(&mut self) {
+        self.mem.watchpoints.clear();
+    }
 }
 
 impl ArmRegister {
@@ -91,29 +196,140 @@ impl ArmRegister {
     }
 }
 
+// inclusive, so ranges ending at the top of the address space don't overflow
+fn watchpoint_range(address: u32, size: u32) -> RangeInclusive<u32> {
+    address..=address.saturating_add(size.max(1) - 1)
+}
+
 const TOTAL_MEMORY: usize = 0xffffffff;
 const PAGE_SIZE: usize = 0x10000;
 const PAGE_MASK: u32 = (PAGE_SIZE - 1) as _;
 
+struct Page {
+    data: RefCell<[u8; PAGE_SIZE]>,
+    permission: MemoryPermission,
+}
+
+impl Page {
+    fn new(data: [u8; PAGE_SIZE], permission: MemoryPermission) -> Box<Self> {
+        Box::new(Self {
+            data: RefCell::new(data),
+            permission,
+        })
+    }
+}
+
 struct Armv4tEmuMemory {
-    pages: [Option<Box<RefCell<[u8; PAGE_SIZE]>>>; TOTAL_MEMORY / PAGE_SIZE],
+    pages: [Option<Box<Page>>; TOTAL_MEMORY / PAGE_SIZE],
+    watchpoints: Vec<(RangeInclusive<u32>, WatchpointKind)>,
+    watchpoint_hit: Option<StopReason>,
+    fetch_address: Option<u32>,
+    fault: Option<(u32, MemoryAccessKind)>,
 }
 
 impl Armv4tEmuMemory {
     fn new() -> Self {
         Self {
             pages: array::from_fn(|_| None),
+            watchpoints: Vec::new(),
+            watchpoint_hit: None,
+            fetch_address: None,
+            fault: None,
+        }
+    }
+
+    fn first_unmapped(&self, address: u32, size: usize) -> Option<u32> {
+        if size == 0 {
+            return None;
+        }
+
+        let end = address as u64 + size as u64 - 1;
+        if end > u32::MAX as u64 {
+            return Some(address);
+        }
+
+        let page_start = address as usize / PAGE_SIZE;
+        let page_end = end as usize / PAGE_SIZE;
+
+        (page_start..=page_end)
+            .find(|&x| self.pages.get(x).map(|x| x.is_none()).unwrap_or(true))
+            .map(|x| (x * PAGE_SIZE).max(address as usize) as u32)
+    }
+
+    // the last page isn't addressable, as TOTAL_MEMORY doesn't cover it
+    fn is_allowed(&self, address: u32, access: MemoryAccessKind) -> bool {
+        self.pages
+            .get(address as usize / PAGE_SIZE)
+            .and_then(|x| x.as_ref())
+            .map(|x| x.permission.allows(access))
+            .unwrap_or(false)
+    }
+
+    fn check_watchpoint(&mut self, addr: u32, size: u32, write: bool) {
+        if self.watchpoints.is_empty() {
+            return;
+        }
+
+        // instruction fetches aren't data accesses
+        if !write && self.fetch_address == Some(addr) {
+            return;
+        }
+
+        let last = addr.saturating_add(size - 1);
+
+        let hit = self.watchpoints.iter().find(|(range, kind)| {
+            let kind_matches = match kind {
+                WatchpointKind::Read => !write,
+                WatchpointKind::Write => write,
+                WatchpointKind::Access => true,
+            };
+
+            kind_matches && *range.start() <= last && addr <= *range.end()
+        });
+
+        if let Some((range, kind)) = hit {
+            self.watchpoint_hit = Some(StopReason::Watchpoint {
+                address: *range.start(),
+                kind: *kind,
+            });
         }
     }
 
-    fn map(&mut self, address: u32, size: usize) {
+    fn map(&mut self, address: u32, size: usize, permission: MemoryPermission) {
         let page_start = address & !PAGE_MASK;
         let page_end = (address + size as u32 + PAGE_MASK) & !PAGE_MASK;
 
         for page in (page_start..page_end).step_by(PAGE_SIZE) {
             let page_data = &mut self.pages[page as usize / PAGE_SIZE];
-            if page_data.is_none() {
-                *page_data = Some(Box::new(RefCell::new([0; PAGE_SIZE])));
+            if let Some(x) = page_data {
+                x.permission = x.permission.union(permission);
+            } else {
+                *page_data = Some(Page::new([0; PAGE_SIZE], permission));
+            }
+        }
+    }
+
+    fn pages(&self) -> Vec<(u32, MemoryPermission, Vec<u8>)> {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, page)| {
+                page.as_ref()
+                    .map(|x| ((index * PAGE_SIZE) as u32, x.permission, x.data.borrow().to_vec()))
+            })
+            .collect()
+    }
+
+    fn restore_pages(&mut self, pages: Vec<(u32, MemoryPermission, Vec<u8>)>) {
+        self.pages = array::from_fn(|_| None);
+
+        for (address, permission, data) in pages {
+            let mut page = [0; PAGE_SIZE];
+            let length = data.len().min(PAGE_SIZE);
+            page[..length].copy_from_slice(&data[..length]);
+
+            if let Some(x) = self.pages.get_mut(address as usize / PAGE_SIZE) {
+                *x = Some(Page::new(page, permission));
             }
         }
     }
@@ -129,7 +345,7 @@ impl Armv4tEmuMemory {
             let offset = (current_address - page_address) as usize;
             let available_bytes = (PAGE_SIZE - offset).min(remaining_size);
 
-            result.extend_from_slice(&page_data.borrow()[offset..offset + available_bytes]);
+            result.extend_from_slice(&page_data.data.borrow()[offset..offset + available_bytes]);
             remaining_size -= available_bytes;
             current_address += available_bytes as u32;
         }
@@ -147,45 +363,63 @@ impl Armv4tEmuMemory {
             let offset = (current_address - page_address) as usize;
             let available_bytes = (PAGE_SIZE - offset).min(data.len() - data_index);
 
-            page_data.borrow_mut()[offset..offset + available_bytes].copy_from_slice(&data[data_index..data_index + available_bytes]);
+            page_data.data.borrow_mut()[offset..offset + available_bytes].copy_from_slice(&data[data_index..data_index + available_bytes]);
             data_index += available_bytes;
             current_address += available_bytes as u32;
         }
     }
 
-    fn get_page(&mut self, addr: u32) -> &RefCell<[u8; PAGE_SIZE]> {
-        let page_address = addr & !PAGE_MASK;
-        let page_data = self.pages[page_address as usize / PAGE_SIZE].as_mut();
+    // Memory trait can't return errors, so faults are recorded here and reported after the instruction completes
+    fn get_page(&mut self, addr: u32, access: MemoryAccessKind) -> Option<&RefCell<[u8; PAGE_SIZE]>> {
+        if !self.is_allowed(addr, access) {
+            if self.fault.is_none() {
+                self.fault = Some((addr, access));
+            }
 
-        if let Some(x) = page_data {
-            x
-        } else {
-            panic!("Access to unmapped address {:#x}", addr); // TODO can we propagate error?
+            return None;
         }
+
+        self.pages[addr as usize / PAGE_SIZE].as_ref().map(|x| &x.data)
     }
 }
 
 impl Memory for Armv4tEmuMemory {
     fn r8(&mut self, addr: u32) -> u8 {
+        self.check_watchpoint(addr, 1, false);
+
         let offset = addr & PAGE_MASK;
 
-        let data = self.get_page(addr).borrow();
+        let Some(page) = self.get_page(addr, MemoryAccessKind::Read) else {
+            return 0;
+        };
+        let data = page.borrow();
 
         data[offset as usize]
     }
 
     fn r16(&mut self, addr: u32) -> u16 {
+        self.check_watchpoint(addr, 2, false);
+
         let offset = addr & PAGE_MASK;
 
-        let data = self.get_page(addr).borrow();
+        let Some(page) = self.get_page(addr, MemoryAccessKind::Read) else {
+            return 0;
+        };
+        let data = page.borrow();
 
         (data[offset as usize] as u16) | ((data[offset as usize + 1] as u16) << 8)
     }
 
     fn r32(&mut self, addr: u32) -> u32 {
+        self.check_watchpoint(addr, 4, false);
+
         let offset = addr & PAGE_MASK;
 
-        let data = self.get_page(addr).borrow();
+        let Some(page) = self.get_page(addr, MemoryAccessKind::Read) else {
+            return 0;
+        };
+        let data = page.borrow();
+
         (data[offset as usize] as u32)
             | ((data[offset as usize + 1] as u32) << 8)
             | ((data[offset as usize + 2] as u32) << 16)
@@ -193,26 +427,41 @@ impl Memory for Armv4tEmuMemory {
     }
 
     fn w8(&mut self, addr: u32, val: u8) {
+        self.check_watchpoint(addr, 1, true);
+
         let offset = addr & PAGE_MASK;
 
-        let mut data = self.get_page(addr).borrow_mut();
+        let Some(page) = self.get_page(addr, MemoryAccessKind::Write) else {
+            return;
+        };
+        let mut data = page.borrow_mut();
 
         data[offset as usize] = val;
     }
 
     fn w16(&mut self, addr: u32, val: u16) {
+        self.check_watchpoint(addr, 2, true);
+
         let offset = addr & PAGE_MASK;
 
-        let mut data = self.get_page(addr).borrow_mut();
+        let Some(page) = self.get_page(addr, MemoryAccessKind::Write) else {
+            return;
+        };
+        let mut data = page.borrow_mut();
 
         data[offset as usize] = val as u8;
         data[offset as usize + 1] = (val >> 8) as u8;
     }
 
     fn w32(&mut self, addr: u32, val: u32) {
+        self.check_watchpoint(addr, 4, true);
+
         let offset = addr & PAGE_MASK;
 
-        let mut data = self.get_page(addr).borrow_mut();
+        let Some(page) = self.get_page(addr, MemoryAccessKind::Write) else {
+            return;
+        };
+        let mut data = page.borrow_mut();
 
         data[offset as usize] = val as u8;
         data[offset as usize + 1] = (val >> 8) as u8;
@@ -227,14 +476,18 @@ mod tests {
     use armv4t_emu::Memory;
 
     use super::Armv4tEmuMemory;
+    use crate::{
+        engine::{ArmEngine, ArmRegister, Armv4tEmuEngine, MemoryAccessKind, MemoryPermission, StopReason, WatchpointKind},
+        ArmCoreError,
+    };
 
     #[test]
     fn test_memory_basic() {
         let mut memory = Armv4tEmuMemory::new();
 
-        memory.map(0x10000, 0x1000);
-        memory.map(0x11000, 0x1000);
-        memory.map(0x20000, 0x10000);
+        memory.map(0x10000, 0x1000, MemoryPermission::ReadWrite);
+        memory.map(0x11000, 0x1000, MemoryPermission::ReadWrite);
+        memory.map(0x20000, 0x10000, MemoryPermission::ReadWrite);
 
         memory.write_range(0x10000, &[123; 0x1000]);
 
@@ -268,12 +521,31 @@ mod tests {
         assert_eq!(r32, 0x12345678);
     }
 
+    #[test]
+    fn test_memory_pages() {
+        let mut memory = Armv4tEmuMemory::new();
+
+        memory.map(0x10000, 0x20000, MemoryPermission::ReadWrite);
+        memory.write_range(0x10000, &[123; 0x1000]);
+
+        let pages = memory.pages();
+        assert_eq!(pages.len(), 2);
+
+        memory.write_range(0x10000, &[0; 0x1000]);
+        memory.map(0x40000, 0x1000, MemoryPermission::ReadWrite);
+
+        memory.restore_pages(pages);
+
+        assert_eq!(memory.read_range(0x10000, 0x1000), vec![123; 0x1000]);
+        assert!(memory.pages[4].is_none());
+    }
+
     #[test]
     #[should_panic]
     fn test_memory_unmapped_read() {
         let mut memory = Armv4tEmuMemory::new();
 
-        memory.map(0x10000, 0x10000);
+        memory.map(0x10000, 0x10000, MemoryPermission::ReadWrite);
 
         memory.read_range(0x1f500, 0x1000);
     }
@@ -283,8 +555,104 @@ mod tests {
     fn test_memory_unmapped_write() {
         let mut memory = Armv4tEmuMemory::new();
 
-        memory.map(0x10000, 0x10000);
+        memory.map(0x10000, 0x10000, MemoryPermission::ReadWrite);
 
         memory.write_range(0x1f500, &[12; 0x1000]);
     }
+
+    #[test]
+    fn test_memory_permission() {
+        let mut memory = Armv4tEmuMemory::new();
+
+        memory.map(0x10000, 0x10000, MemoryPermission::ReadExecute);
+        memory.map(0x20000, 0x10000, MemoryPermission::ReadWrite);
+
+        memory.w32(0x20010, 0x12345678);
+        assert_eq!(memory.r32(0x20010), 0x12345678);
+        assert_eq!(memory.r32(0x10010), 0);
+        assert!(memory.fault.is_none());
+
+        memory.w32(0x10010, 0x12345678);
+        assert_eq!(memory.fault.take(), Some((0x10010, MemoryAccessKind::Write)));
+        assert_eq!(memory.r32(0x10010), 0);
+
+        memory.r8(0x30000);
+        assert_eq!(memory.fault.take(), Some((0x30000, MemoryAccessKind::Read)));
+
+        memory.map(0x10000, 0x1000, MemoryPermission::ReadWrite);
+        memory.w32(0x10010, 0x12345678);
+        assert!(memory.fault.is_none());
+
+        let pages = memory.pages();
+        assert_eq!(pages[0].1, MemoryPermission::ReadWriteExecute);
+        assert_eq!(pages[1].1, MemoryPermission::ReadWrite);
+    }
+
+    #[test]
+    fn test_engine_fault() {
+        let mut engine = alloc::boxed::Box::new(Armv4tEmuEngine::new());
+
+        engine.mem_map(0x20000, 0x10000, MemoryPermission::ReadWrite);
+        engine.reg_write(ArmRegister::PC, 0x20000);
+
+        assert!(matches!(
+            engine.step(),
+            Err(ArmCoreError::MemoryFault {
+                address: 0x20000,
+                access: MemoryAccessKind::Execute,
+                pc: 0x20000
+            })
+        ));
+
+        engine.reg_write(ArmRegister::PC, 0xfffffff0);
+        assert!(matches!(
+            engine.step(),
+            Err(ArmCoreError::MemoryFault {
+                address: 0xfffffff0,
+                access: MemoryAccessKind::Execute,
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            engine.mem_read(0x2fff0, 0x20),
+            Err(ArmCoreError::MemoryFault {
+                address: 0x30000,
+                access: MemoryAccessKind::Read,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_engine_watchpoint() {
+        let mut engine = alloc::boxed::Box::new(Armv4tEmuEngine::new());
+
+        engine.mem_map(0x10000, 0x1000, MemoryPermission::ReadWriteExecute);
+        engine.mem_write(0x10000, &0xe3a00001u32.to_le_bytes()).unwrap(); // mov r0, #1
+        engine.mem_write(0x10004, &0xe5910000u32.to_le_bytes()).unwrap(); // ldr r0, [r1]
+        engine.reg_write(ArmRegister::PC, 0x10000);
+        engine.reg_write(ArmRegister::R1, 0x10000);
+
+        // range reaching the end of address space
+        engine.add_watchpoint(0xfffffffc, 8, WatchpointKind::Access);
+        engine.remove_watchpoint(0xfffffffc, 8, WatchpointKind::Access);
+        assert!(engine.mem.watchpoints.is_empty());
+
+        // fetching instruction isn't a read
+        engine.add_watchpoint(0x10000, 4, WatchpointKind::Read);
+        assert_eq!(engine.step().unwrap(), None);
+
+        assert_eq!(
+            engine.step().unwrap(),
+            Some(StopReason::Watchpoint {
+                address: 0x10000,
+                kind: WatchpointKind::Read
+            })
+        );
+
+        engine.clear_watchpoints();
+        engine.mem.r32(0x10000);
+        assert!(engine.mem.watchpoint_hit.is_none());
+    }
 }
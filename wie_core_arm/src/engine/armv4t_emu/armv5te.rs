@@ -0,0 +1,633 @@
+// ARMv5TE instructions missing on armv4t_emu, executed before handing over to armv4t_emu
+
+use armv4t_emu::{reg, Cpu, Memory, Mode};
+
+const CPSR_T: u32 = 1 << 5;
+const CPSR_Q: u32 = 1 << 27;
+
+pub enum Armv5teStep {
+    Executed,
+    NotHandled,
+    // armv4t_emu can execute it, but pc should be set with interworking using the word at given address
+    LoadPc(u32),
+}
+
+pub fn step<M>(cpu: &mut Cpu, mem: &mut M) -> Armv5teStep
+where
+    M: Memory,
+{
+    let cpsr = cpu.reg_get(Mode::User, reg::CPSR);
+    let pc = cpu.reg_get(Mode::User, reg::PC);
+
+    if cpsr & CPSR_T != 0 {
+        let instruction = mem.r16(pc);
+
+        step_thumb(cpu, instruction, pc)
+    } else {
+        let instruction = mem.r32(pc);
+
+        step_arm(cpu, mem, instruction, pc, cpsr)
+    }
+}
+
+// finish load to pc with armv5 interworking semantic
+pub fn set_pc_interworking(cpu: &mut Cpu, value: u32) {
+    let cpsr = cpu.reg_get(Mode::User, reg::CPSR);
+
+    if value & 1 != 0 {
+        cpu.reg_set(Mode::User, reg::CPSR, cpsr | CPSR_T);
+        cpu.reg_set(Mode::User, reg::PC, value & !1);
+    } else {
+        cpu.reg_set(Mode::User, reg::CPSR, cpsr & !CPSR_T);
+        cpu.reg_set(Mode::User, reg::PC, value & !3);
+    }
+}
+
+fn step_thumb(cpu: &mut Cpu, instruction: u16, pc: u32) -> Armv5teStep {
+    let instruction = instruction as u32;
+
+    if instruction & 0xf800 == 0xe800 {
+        // BLX (1) suffix, prefix is handled by armv4t_emu as BL prefix
+        let lr = cpu.reg_get(Mode::User, reg::LR);
+        let target = lr.wrapping_add((instruction & 0x7ff) << 1) & !3;
+
+        cpu.reg_set(Mode::User, reg::LR, (pc + 2) | 1);
+        set_pc_interworking(cpu, target);
+
+        Armv5teStep::Executed
+    } else if instruction & 0xff87 == 0x4780 {
+        // BLX (2)
+        let rm = ((instruction >> 3) & 0xf) as u8;
+        let target = read_reg(cpu, rm, pc + 4);
+
+        cpu.reg_set(Mode::User, reg::LR, (pc + 2) | 1);
+        set_pc_interworking(cpu, target);
+
+        Armv5teStep::Executed
+    } else if instruction & 0xff00 == 0xbd00 {
+        // POP with pc
+        let sp = cpu.reg_get(Mode::User, reg::SP);
+        let count = (instruction & 0xff).count_ones();
+
+        Armv5teStep::LoadPc(sp + count * 4)
+    } else {
+        Armv5teStep::NotHandled
+    }
+}
+
+fn step_arm<M>(cpu: &mut Cpu, mem: &mut M, instruction: u32, pc: u32, cpsr: u32) -> Armv5teStep
+where
+    M: Memory,
+{
+    let cond = instruction >> 28;
+
+    if cond == 0xf {
+        if instruction & 0x0e000000 == 0x0a000000 {
+            // BLX (1)
+            let offset = (((instruction << 8) as i32) >> 6) as u32 | ((instruction >> 23) & 2);
+
+            cpu.reg_set(Mode::User, reg::LR, pc + 4);
+            set_pc_interworking(cpu, pc.wrapping_add(8).wrapping_add(offset) | 1);
+
+            return Armv5teStep::Executed;
+        } else if instruction & 0x0d70f000 == 0x0550f000 {
+            // PLD
+            cpu.reg_set(Mode::User, reg::PC, pc + 4);
+
+            return Armv5teStep::Executed;
+        }
+
+        return Armv5teStep::NotHandled;
+    }
+
+    let class = decode_arm(instruction);
+    if let ArmClass::Unknown = class {
+        return Armv5teStep::NotHandled;
+    }
+
+    if !condition_passed(cond, cpsr) {
+        if let ArmClass::LoadPc = class {
+            return Armv5teStep::NotHandled;
+        }
+        cpu.reg_set(Mode::User, reg::PC, pc + 4);
+
+        return Armv5teStep::Executed;
+    }
+
+    let rd = ((instruction >> 12) & 0xf) as u8;
+    let rn = ((instruction >> 16) & 0xf) as u8;
+    let rs = ((instruction >> 8) & 0xf) as u8;
+    let rm = (instruction & 0xf) as u8;
+    let read = |cpu: &Cpu, index: u8| read_reg(cpu, index, pc + 8);
+
+    match class {
+        ArmClass::BlxRegister => {
+            let target = read(cpu, rm);
+
+            cpu.reg_set(Mode::User, reg::LR, pc + 4);
+            set_pc_interworking(cpu, target);
+
+            return Armv5teStep::Executed;
+        }
+        ArmClass::Clz => {
+            let value = read(cpu, rm).leading_zeros();
+
+            cpu.reg_set(Mode::User, rd, value);
+        }
+        ArmClass::SaturatingArithmetic => {
+            let mut saturated = false;
+
+            let mut operand = read(cpu, rn) as i32;
+            if instruction & (1 << 22) != 0 {
+                operand = saturate(operand as i64 * 2, &mut saturated);
+            }
+
+            let value = read(cpu, rm) as i64;
+            let result = if instruction & (1 << 21) != 0 {
+                saturate(value - operand as i64, &mut saturated)
+            } else {
+                saturate(value + operand as i64, &mut saturated)
+            };
+
+            cpu.reg_set(Mode::User, rd, result as u32);
+            if saturated {
+                set_q(cpu);
+            }
+        }
+        ArmClass::SignedMultiply => {
+            // destination is on rn position for multiplies
+            let (x, y) = (instruction & (1 << 5) != 0, instruction & (1 << 6) != 0);
+            let operand1 = half(read(cpu, rm), x) as i64;
+            let operand2 = half(read(cpu, rs), y) as i64;
+
+            match (instruction >> 21) & 3 {
+                0 => {
+                    // SMLAxy
+                    let result = operand1 * operand2 + read(cpu, rd) as i32 as i64;
+
+                    cpu.reg_set(Mode::User, rn, result as u32);
+                    if result != result as i32 as i64 {
+                        set_q(cpu);
+                    }
+                }
+                1 => {
+                    let product = (read(cpu, rm) as i32 as i64 * operand2) >> 16;
+                    if x {
+                        // SMULWy
+                        cpu.reg_set(Mode::User, rn, product as u32);
+                    } else {
+                        // SMLAWy
+                        let result = product + read(cpu, rd) as i32 as i64;
+
+                        cpu.reg_set(Mode::User, rn, result as u32);
+                        if result != result as i32 as i64 {
+                            set_q(cpu);
+                        }
+                    }
+                }
+                2 => {
+                    // SMLALxy
+                    let accumulator = ((read(cpu, rn) as u64) << 32 | read(cpu, rd) as u64) as i64;
+                    let result = accumulator.wrapping_add(operand1 * operand2) as u64;
+
+                    cpu.reg_set(Mode::User, rd, result as u32);
+                    cpu.reg_set(Mode::User, rn, (result >> 32) as u32);
+                }
+                _ => {
+                    // SMULxy
+                    cpu.reg_set(Mode::User, rn, (operand1 * operand2) as u32);
+                }
+            }
+        }
+        ArmClass::DoublewordTransfer => {
+            let pre_index = instruction & (1 << 24) != 0;
+            let up = instruction & (1 << 23) != 0;
+            let immediate = instruction & (1 << 22) != 0;
+            let write_back = instruction & (1 << 21) != 0;
+            let store = instruction & (1 << 5) != 0;
+
+            let offset = if immediate {
+                ((instruction >> 4) & 0xf0) | (instruction & 0xf)
+            } else {
+                read(cpu, rm)
+            };
+
+            let base = read(cpu, rn);
+            let offset_address = if up { base.wrapping_add(offset) } else { base.wrapping_sub(offset) };
+            let address = if pre_index { offset_address } else { base };
+
+            if store {
+                let (low, high) = (read(cpu, rd), read(cpu, rd + 1));
+                mem.w32(address, low);
+                mem.w32(address + 4, high);
+            } else {
+                let (low, high) = (mem.r32(address), mem.r32(address + 4));
+                cpu.reg_set(Mode::User, rd, low);
+                cpu.reg_set(Mode::User, rd + 1, high);
+            }
+
+            if !pre_index || write_back {
+                cpu.reg_set(Mode::User, rn, offset_address);
+            }
+        }
+        ArmClass::LoadPc => return Armv5teStep::LoadPc(load_pc_address(cpu, instruction, pc)),
+        ArmClass::Unknown => unreachable!(),
+    }
+
+    cpu.reg_set(Mode::User, reg::PC, pc + 4);
+
+    Armv5teStep::Executed
+}
+
+enum ArmClass {
+    BlxRegister,
+    Clz,
+    SaturatingArithmetic,
+    SignedMultiply,
+    DoublewordTransfer,
+    LoadPc,
+    Unknown,
+}
+
+fn decode_arm(instruction: u32) -> ArmClass {
+    if instruction & 0x0ffffff0 == 0x012fff30 {
+        ArmClass::BlxRegister
+    } else if instruction & 0x0fff0ff0 == 0x016f0f10 {
+        ArmClass::Clz
+    } else if instruction & 0x0f900ff0 == 0x01000050 {
+        ArmClass::SaturatingArithmetic
+    } else if instruction & 0x0f900090 == 0x01000080 {
+        ArmClass::SignedMultiply
+    } else if instruction & 0x0e1000d0 == 0x000000d0 && (instruction >> 12) & 1 == 0 && (instruction >> 12) & 0xf != 14 {
+        ArmClass::DoublewordTransfer
+    } else if instruction & 0x0e508000 == 0x08108000 {
+        // LDM with pc, without S bit
+        ArmClass::LoadPc
+    } else if instruction & 0x0c50f000 == 0x0410f000 && (instruction & (1 << 25) == 0 || instruction & (1 << 4) == 0) {
+        // LDR pc
+        ArmClass::LoadPc
+    } else {
+        ArmClass::Unknown
+    }
+}
+
+fn load_pc_address(cpu: &Cpu, instruction: u32, pc: u32) -> u32 {
+    let pre_index = instruction & (1 << 24) != 0;
+    let up = instruction & (1 << 23) != 0;
+    let base = read_reg(cpu, ((instruction >> 16) & 0xf) as u8, pc + 8);
+
+    if instruction & 0x0e000000 == 0x08000000 {
+        // pc is the highest register, loaded from the highest address
+        let count = (instruction & 0xffff).count_ones();
+
+        match (pre_index, up) {
+            (false, true) => base + (count - 1) * 4,
+            (true, true) => base + count * 4,
+            (false, false) => base,
+            (true, false) => base - 4,
+        }
+    } else {
+        let offset = if instruction & (1 << 25) == 0 {
+            instruction & 0xfff
+        } else {
+            let value = read_reg(cpu, (instruction & 0xf) as u8, pc + 8);
+            let amount = (instruction >> 7) & 0x1f;
+
+            match (instruction >> 5) & 3 {
+                0 => value << amount,
+                1 => {
+                    if amount == 0 {
+                        0
+                    } else {
+                        value >> amount
+                    }
+                }
+                2 => ((value as i32) >> if amount == 0 { 31 } else { amount }) as u32,
+                _ => {
+                    if amount == 0 {
+                        // rrx, shifts carry in
+                        let carry = cpu.reg_get(Mode::User, reg::CPSR) & (1 << 29) != 0;
+
+                        ((carry as u32) << 31) | (value >> 1)
+                    } else {
+                        value.rotate_right(amount)
+                    }
+                }
+            }
+        };
+
+        match (pre_index, up) {
+            (true, true) => base.wrapping_add(offset),
+            (true, false) => base.wrapping_sub(offset),
+            (false, _) => base,
+        }
+    }
+}
+
+fn read_reg(cpu: &Cpu, index: u8, pc_value: u32) -> u32 {
+    if index == reg::PC {
+        pc_value
+    } else {
+        cpu.reg_get(Mode::User, index)
+    }
+}
+
+fn half(value: u32, top: bool) -> i16 {
+    if top {
+        (value >> 16) as i16
+    } else {
+        value as i16
+    }
+}
+
+fn saturate(value: i64, saturated: &mut bool) -> i32 {
+    if value > i32::MAX as i64 {
+        *saturated = true;
+        i32::MAX
+    } else if value < i32::MIN as i64 {
+        *saturated = true;
+        i32::MIN
+    } else {
+        value as i32
+    }
+}
+
+fn set_q(cpu: &mut Cpu) {
+    let cpsr = cpu.reg_get(Mode::User, reg::CPSR);
+
+    cpu.reg_set(Mode::User, reg::CPSR, cpsr | CPSR_Q);
+}
+
+fn condition_passed(cond: u32, cpsr: u32) -> bool {
+    let n = cpsr & (1 << 31) != 0;
+    let z = cpsr & (1 << 30) != 0;
+    let c = cpsr & (1 << 29) != 0;
+    let v = cpsr & (1 << 28) != 0;
+
+    match cond {
+        0 => z,
+        1 => !z,
+        2 => c,
+        3 => !c,
+        4 => n,
+        5 => !n,
+        6 => v,
+        7 => !v,
+        8 => c && !z,
+        9 => !c || z,
+        10 => n == v,
+        11 => n != v,
+        12 => !z && n == v,
+        13 => z || n != v,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use crate::engine::{ArmEngine, ArmRegister, Armv4tEmuEngine, MemoryPermission};
+
+    const BASE: u32 = 0x10000;
+
+    fn engine_with(code: &[u8], thumb: bool) -> Box<Armv4tEmuEngine> {
+        let mut engine = Box::new(Armv4tEmuEngine::new());
+
+        engine.mem_map(BASE, 0x10000, MemoryPermission::ReadWriteExecute);
+        engine.mem_write(BASE, code).unwrap();
+        engine.reg_write(ArmRegister::Cpsr, 0x10);
+        engine.reg_write(ArmRegister::PC, if thumb { BASE + 1 } else { BASE });
+
+        engine
+    }
+
+    fn arm(instruction: u32) -> Box<Armv4tEmuEngine> {
+        engine_with(&instruction.to_le_bytes(), false)
+    }
+
+    fn thumb(instruction: u16) -> Box<Armv4tEmuEngine> {
+        engine_with(&instruction.to_le_bytes(), true)
+    }
+
+    fn is_thumb(engine: &Armv4tEmuEngine) -> bool {
+        engine.reg_read(ArmRegister::Cpsr) & (1 << 5) != 0
+    }
+
+    fn q_flag(engine: &Armv4tEmuEngine) -> bool {
+        engine.reg_read(ArmRegister::Cpsr) & (1 << 27) != 0
+    }
+
+    #[test]
+    fn test_branch_exchange() {
+        // blx r3
+        let mut engine = arm(0xe12fff33);
+        engine.reg_write(ArmRegister::R3, 0x20101);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::PC), 0x20100);
+        assert_eq!(engine.reg_read(ArmRegister::LR), BASE + 4);
+        assert!(is_thumb(&engine));
+
+        // blx #0x10 with H bit
+        let mut engine = arm(0xfb000002);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::PC), BASE + 0x12);
+        assert_eq!(engine.reg_read(ArmRegister::LR), BASE + 4);
+        assert!(is_thumb(&engine));
+
+        // thumb blx r3
+        let mut engine = thumb(0x4798);
+        engine.reg_write(ArmRegister::R3, 0x20000);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::PC), 0x20000);
+        assert_eq!(engine.reg_read(ArmRegister::LR), BASE + 3);
+        assert!(!is_thumb(&engine));
+
+        // thumb blx suffix, lr is set by bl prefix
+        let mut engine = thumb(0xe804);
+        engine.reg_write(ArmRegister::LR, 0x20002);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::PC), 0x20008);
+        assert_eq!(engine.reg_read(ArmRegister::LR), BASE + 3);
+        assert!(!is_thumb(&engine));
+    }
+
+    #[test]
+    fn test_count_leading_zeros() {
+        // clz r0, r1
+        let mut engine = arm(0xe16f0f11);
+        engine.reg_write(ArmRegister::R1, 0x00f00000);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 8);
+        assert_eq!(engine.reg_read(ArmRegister::PC), BASE + 4);
+
+        let mut engine = arm(0xe16f0f11);
+        engine.reg_write(ArmRegister::R1, 0);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 32);
+
+        // clzeq r0, r1 with z flag clear
+        let mut engine = arm(0x016f0f11);
+        engine.reg_write(ArmRegister::R0, 1234);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 1234);
+        assert_eq!(engine.reg_read(ArmRegister::PC), BASE + 4);
+    }
+
+    #[test]
+    fn test_saturating_arithmetic() {
+        // qadd r0, r1, r2
+        let mut engine = arm(0xe1020051);
+        engine.reg_write(ArmRegister::R1, 0x7fffffff);
+        engine.reg_write(ArmRegister::R2, 1);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 0x7fffffff);
+        assert!(q_flag(&engine));
+
+        // qsub r0, r1, r2
+        let mut engine = arm(0xe1220051);
+        engine.reg_write(ArmRegister::R1, 10);
+        engine.reg_write(ArmRegister::R2, 3);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 7);
+        assert!(!q_flag(&engine));
+
+        // qdadd r0, r1, r2
+        let mut engine = arm(0xe1420051);
+        engine.reg_write(ArmRegister::R1, 1);
+        engine.reg_write(ArmRegister::R2, 0xc0000000);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 0x80000001);
+        assert!(!q_flag(&engine));
+
+        let mut engine = arm(0xe1420051);
+        engine.reg_write(ArmRegister::R1, 1);
+        engine.reg_write(ArmRegister::R2, 0x40000000);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 0x7fffffff);
+        assert!(q_flag(&engine));
+
+        // qdsub r0, r1, r2
+        let mut engine = arm(0xe1620051);
+        engine.reg_write(ArmRegister::R1, 10);
+        engine.reg_write(ArmRegister::R2, 3);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 4);
+    }
+
+    #[test]
+    fn test_signed_multiply() {
+        // smulbb r0, r1, r2
+        let mut engine = arm(0xe1600281);
+        engine.reg_write(ArmRegister::R1, 0xffff0003);
+        engine.reg_write(ArmRegister::R2, 0x0005fffe);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), -6i32 as u32);
+
+        // smultt r0, r1, r2
+        let mut engine = arm(0xe16002e1);
+        engine.reg_write(ArmRegister::R1, 0xffff0003);
+        engine.reg_write(ArmRegister::R2, 0x0005fffe);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), -5i32 as u32);
+
+        // smlabb r0, r1, r2, r3
+        let mut engine = arm(0xe1003281);
+        engine.reg_write(ArmRegister::R1, 3);
+        engine.reg_write(ArmRegister::R2, 4);
+        engine.reg_write(ArmRegister::R3, 10);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 22);
+        assert!(!q_flag(&engine));
+
+        let mut engine = arm(0xe1003281);
+        engine.reg_write(ArmRegister::R1, 1);
+        engine.reg_write(ArmRegister::R2, 1);
+        engine.reg_write(ArmRegister::R3, 0x7fffffff);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 0x80000000);
+        assert!(q_flag(&engine));
+
+        // smulwb r0, r1, r2
+        let mut engine = arm(0xe12002a1);
+        engine.reg_write(ArmRegister::R1, 0x20000);
+        engine.reg_write(ArmRegister::R2, 3);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 6);
+
+        // smlawb r0, r1, r2, r3
+        let mut engine = arm(0xe1203281);
+        engine.reg_write(ArmRegister::R1, 0x20000);
+        engine.reg_write(ArmRegister::R2, 3);
+        engine.reg_write(ArmRegister::R3, 4);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 10);
+
+        // smlalbb r0, r1, r2, r3
+        let mut engine = arm(0xe1410382);
+        engine.reg_write(ArmRegister::R0, 5);
+        engine.reg_write(ArmRegister::R1, 0);
+        engine.reg_write(ArmRegister::R2, 0xfffe);
+        engine.reg_write(ArmRegister::R3, 3);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R0), 0xffffffff);
+        assert_eq!(engine.reg_read(ArmRegister::R1), 0xffffffff);
+    }
+
+    #[test]
+    fn test_doubleword_transfer() {
+        // ldrd r2, [r0, #8]
+        let mut engine = arm(0xe1c020d8);
+        engine.mem_write(BASE + 0x108, &[1, 0, 0, 0, 2, 0, 0, 0]).unwrap();
+        engine.reg_write(ArmRegister::R0, BASE + 0x100);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::R2), 1);
+        assert_eq!(engine.reg_read(ArmRegister::R3), 2);
+        assert_eq!(engine.reg_read(ArmRegister::R0), BASE + 0x100);
+
+        // strd r2, [r0], #8
+        let mut engine = arm(0xe0c020f8);
+        engine.reg_write(ArmRegister::R0, BASE + 0x100);
+        engine.reg_write(ArmRegister::R2, 3);
+        engine.reg_write(ArmRegister::R3, 4);
+        engine.step().unwrap();
+        assert_eq!(engine.mem_read(BASE + 0x100, 8).unwrap(), [3, 0, 0, 0, 4, 0, 0, 0]);
+        assert_eq!(engine.reg_read(ArmRegister::R0), BASE + 0x108);
+    }
+
+    #[test]
+    fn test_preload() {
+        // pld [r0]
+        let mut engine = arm(0xf5d0f000);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::PC), BASE + 4);
+    }
+
+    #[test]
+    fn test_load_pc_interworking() {
+        // pop {pc}
+        let mut engine = thumb(0xbd00);
+        engine.mem_write(BASE + 0x800, &0x20000u32.to_le_bytes()).unwrap();
+        engine.reg_write(ArmRegister::SP, BASE + 0x800);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::PC), 0x20000);
+        assert!(!is_thumb(&engine));
+
+        // ldr pc, [r0, #4]
+        let mut engine = arm(0xe590f004);
+        engine.mem_write(BASE + 0x804, &0x20001u32.to_le_bytes()).unwrap();
+        engine.reg_write(ArmRegister::R0, BASE + 0x800);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::PC), 0x20000);
+        assert!(is_thumb(&engine));
+
+        // ldr pc, [r0, r1, rrx] with carry set
+        let mut engine = arm(0xe790f061);
+        engine.mem_write(BASE + 0x1000, &0x20000u32.to_le_bytes()).unwrap();
+        engine.reg_write(ArmRegister::Cpsr, 0x10 | (1 << 29));
+        engine.reg_write(ArmRegister::R0, (BASE + 0x1000).wrapping_sub(0x80000800));
+        engine.reg_write(ArmRegister::R1, 0x1000);
+        engine.step().unwrap();
+        assert_eq!(engine.reg_read(ArmRegister::PC), 0x20000);
+    }
+}
@@ -1,19 +1,51 @@
 use alloc::string::String;
-use wie_util::ByteReadWriteError;
+use core::fmt;
+
+use wie_util::{ByteReadWriteError, SnapshotError};
+
+use crate::engine::MemoryAccessKind;
 
 #[derive(Debug)]
 pub enum ArmCoreError {
     InvalidMemoryAccess,
+    MemoryFault { address: u32, access: MemoryAccessKind, pc: u32 },
     FunctionCallError(String),
+    InvalidSnapshot,
+    OutOfMemory,
+    InvalidFree(u32),
     Other,
 }
 
+impl fmt::Display for ArmCoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArmCoreError::MemoryFault { address, access, pc } => {
+                let access = match access {
+                    MemoryAccessKind::Read => "read from",
+                    MemoryAccessKind::Write => "write to",
+                    MemoryAccessKind::Execute => "execution of",
+                };
+
+                write!(f, "Memory fault: {} {:#x} at pc {:#x}", access, address, pc)
+            }
+            ArmCoreError::InvalidFree(address) => write!(f, "Invalid free of {:#x}", address),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
 impl From<ByteReadWriteError> for ArmCoreError {
     fn from(_: ByteReadWriteError) -> Self {
         ArmCoreError::InvalidMemoryAccess
     }
 }
 
+impl From<SnapshotError> for ArmCoreError {
+    fn from(_: SnapshotError) -> Self {
+        ArmCoreError::InvalidSnapshot
+    }
+}
+
 impl From<ArmCoreError> for ByteReadWriteError {
     fn from(_: ArmCoreError) -> Self {
         ByteReadWriteError::InvalidAddress
@@ -22,6 +54,6 @@ impl From<ArmCoreError> for ByteReadWriteError {
 
 impl From<ArmCoreError> for anyhow::Error {
     fn from(e: ArmCoreError) -> Self {
-        anyhow::anyhow!("{:?}", e)
+        anyhow::anyhow!("{}", e)
     }
 }
@@ -1,5 +1,7 @@
-use alloc::boxed::Box;
+use alloc::{boxed::Box, rc::Rc};
 use core::{
+    cell::RefCell,
+    fmt::Debug,
     future::Future,
     marker::PhantomData,
     pin::Pin,
@@ -8,12 +10,13 @@ use core::{
 
 use wie_backend::AsyncCallable;
 
-use crate::{context::ArmCoreContext, Allocator, ArmCore};
+use crate::{context::ArmCoreContext, Allocator, ArmCore, ArmCoreError};
 
 pub struct SpawnFuture<C, R, E> {
     core: ArmCore,
-    context: ArmCoreContext,
-    stack_base: u32,
+    context: Rc<RefCell<ArmCoreContext>>,
+    stack_base: Option<u32>, // taken once the stack is freed
+    stack_error: Option<ArmCoreError>,
     callable_fut: Pin<Box<dyn Future<Output = Result<R, E>>>>,
     _phantom: PhantomData<C>,
 }
@@ -24,32 +27,59 @@ where
     R: 'static,
     E: core::fmt::Debug + 'static,
 {
+    // the task fails on its first poll if there's no memory left for its stack
     pub fn new(mut core: ArmCore, callable: C) -> Self {
-        let stack_base = Allocator::alloc(&mut core, 0x1000).unwrap();
-        let context = ArmCoreContext::new(stack_base);
+        let (stack_base, stack_error) = match Allocator::alloc(&mut core, 0x1000) {
+            Ok(x) => (Some(x), None),
+            Err(x) => (None, Some(x)),
+        };
+        let context = Rc::new(RefCell::new(ArmCoreContext::new(stack_base.unwrap_or(0))));
+        if let Some(x) = stack_base {
+            core.register_task_context(x, context.clone());
+        }
         let callable_fut = Box::pin(callable.call());
 
         Self {
             core,
             context,
             stack_base,
+            stack_error,
             callable_fut,
             _phantom: PhantomData,
         }
     }
 }
 
-impl<C, R, E> Future for SpawnFuture<C, R, E> {
-    type Output = Result<R, E>;
+impl<C, R, E> SpawnFuture<C, R, E> {
+    fn release(&mut self) {
+        if let Some(stack_base) = self.stack_base.take() {
+            self.core.unregister_task_context(stack_base);
+            if let Err(x) = Allocator::free(&mut self.core, stack_base) {
+                tracing::error!("Failed to free task stack {:#x}: {}", stack_base, x);
+            }
+        }
+    }
+}
+
+impl<C, R, E> Future for SpawnFuture<C, R, E>
+where
+    E: Debug,
+{
+    type Output = anyhow::Result<R>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        self.core.clone().restore_context(&self.context); // XXX clone is added to satisfy borrow checker
+        if let Some(x) = self.stack_error.take() {
+            return Poll::Ready(Err(anyhow::anyhow!("Failed to allocate task stack: {}", x)));
+        }
+
+        self.core.clone().restore_context(&self.context.borrow()); // XXX clone is added to satisfy borrow checker
         let result = self.callable_fut.as_mut().poll(cx);
-        self.context = self.core.save_context();
+        *self.context.borrow_mut() = self.core.save_context();
 
         if let Poll::Ready(x) = result {
-            let stack_base = self.stack_base;
-            Allocator::free(&mut self.core, stack_base).unwrap();
+            // registers are still where the task failed, which is what we want to see whether the task is critical or not
+            let x = x.map_err(|e| anyhow::anyhow!("{:?}\n{}", e, self.core.dump_reg_stack(self.core.image_base())));
+            self.release();
 
             Poll::Ready(x)
         } else {
@@ -58,4 +88,11 @@ impl<C, R, E> Future for SpawnFuture<C, R, E> {
     }
 }
 
+// cancelled tasks are dropped without completing
+impl<C, R, E> Drop for SpawnFuture<C, R, E> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
 impl<C, R, E> Unpin for SpawnFuture<C, R, E> {}
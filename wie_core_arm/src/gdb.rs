@@ -0,0 +1,453 @@
+use alloc::{format, string::String, vec::Vec};
+
+use wie_backend::Debugger;
+use wie_util::{ByteRead, ByteWrite};
+
+use crate::{
+    engine::{StopReason, WatchpointKind},
+    ArmCore,
+};
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <architecture>arm</architecture>
+  <feature name="org.gnu.gdb.arm.core">
+    <reg name="r0" bitsize="32"/>
+    <reg name="r1" bitsize="32"/>
+    <reg name="r2" bitsize="32"/>
+    <reg name="r3" bitsize="32"/>
+    <reg name="r4" bitsize="32"/>
+    <reg name="r5" bitsize="32"/>
+    <reg name="r6" bitsize="32"/>
+    <reg name="r7" bitsize="32"/>
+    <reg name="r8" bitsize="32"/>
+    <reg name="r9" bitsize="32"/>
+    <reg name="r10" bitsize="32"/>
+    <reg name="r11" bitsize="32"/>
+    <reg name="r12" bitsize="32"/>
+    <reg name="sp" bitsize="32" type="data_ptr"/>
+    <reg name="lr" bitsize="32"/>
+    <reg name="pc" bitsize="32" type="code_ptr"/>
+    <reg name="cpsr" bitsize="32"/>
+  </feature>
+</target>
+"#;
+
+const REGISTER_COUNT: usize = 17;
+
+// GDB remote serial protocol frontend for ArmCore, independent from transport
+pub struct GdbStub {
+    core: ArmCore,
+    buffer: Vec<u8>,
+    no_ack: bool,
+    running: bool,
+}
+
+impl GdbStub {
+    pub fn new(mut core: ArmCore) -> Self {
+        // gdb expects target to be stopped on attach
+        core.debug_request_halt();
+
+        Self {
+            core,
+            buffer: Vec::new(),
+            no_ack: false,
+            running: false,
+        }
+    }
+
+    // returns None on malformed packet, Some(None) if there's nothing to reply yet
+    fn handle_packet(&mut self, packet: &str) -> Option<Option<String>> {
+        tracing::trace!("gdb packet: {}", packet);
+
+        let command = packet.get(..1).unwrap_or_default();
+        let args = packet.get(1..).unwrap_or_default();
+
+        let response = match command {
+            "?" => self.stop_reply(self.core.debug_last_stop().unwrap_or(StopReason::Interrupt)),
+            "g" => self.core.debug_read_registers().iter().map(|x| encode_hex(&x.to_le_bytes())).collect(),
+            "G" => {
+                let data = decode_hex(args)?;
+                if data.len() < REGISTER_COUNT * 4 {
+                    return Some(Some("E01".into()));
+                }
+
+                let mut registers = [0; REGISTER_COUNT];
+                for (i, register) in registers.iter_mut().enumerate() {
+                    *register = u32::from_le_bytes(data[i * 4..i * 4 + 4].try_into().unwrap());
+                }
+                self.core.debug_write_registers(registers);
+
+                "OK".into()
+            }
+            "p" => {
+                let index = usize::from_str_radix(args, 16).ok()?;
+                let registers = self.core.debug_read_registers();
+
+                match registers.get(index) {
+                    Some(x) => encode_hex(&x.to_le_bytes()),
+                    None => "E01".into(),
+                }
+            }
+            "P" => {
+                let (index, value) = args.split_once('=')?;
+                let index = usize::from_str_radix(index, 16).ok()?;
+                let value = decode_hex(value)?;
+
+                let mut registers = self.core.debug_read_registers();
+                if index >= REGISTER_COUNT || value.len() != 4 {
+                    return Some(Some("E01".into()));
+                }
+                registers[index] = u32::from_le_bytes(value.try_into().unwrap());
+                self.core.debug_write_registers(registers);
+
+                "OK".into()
+            }
+            "m" => {
+                let (address, length) = parse_address_length(args)?;
+
+                match self.core.read_bytes(address, length) {
+                    Ok(x) => encode_hex(&x),
+                    Err(_) => "E14".into(),
+                }
+            }
+            "M" => {
+                let (address_length, data) = args.split_once(':')?;
+                let (address, _) = parse_address_length(address_length)?;
+                let data = decode_hex(data)?;
+
+                match self.core.write_bytes(address, &data) {
+                    Ok(_) => "OK".into(),
+                    Err(_) => "E14".into(),
+                }
+            }
+            "c" | "s" => {
+                if !args.is_empty() {
+                    let mut registers = self.core.debug_read_registers();
+                    registers[15] = u32::from_str_radix(args, 16).ok()?;
+                    self.core.debug_write_registers(registers);
+                }
+
+                self.core.debug_resume(command == "s");
+                self.running = true;
+
+                return Some(None);
+            }
+            "Z" | "z" => self.handle_breakpoint(command == "Z", args)?,
+            "H" => "OK".into(),
+            "T" => "OK".into(),
+            "D" => {
+                self.detach();
+
+                "OK".into()
+            }
+            "k" => {
+                self.detach();
+
+                return Some(None);
+            }
+            "q" | "Q" => self.handle_query(packet),
+            _ => String::new(),
+        };
+
+        Some(Some(response))
+    }
+
+    fn handle_query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            format!("PacketSize={:x};qXfer:features:read+;swbreak+;QStartNoAckMode+", 0x4000)
+        } else if packet == "QStartNoAckMode" {
+            self.no_ack = true;
+
+            "OK".into()
+        } else if packet == "qAttached" {
+            "1".into()
+        } else if packet == "qC" {
+            "QC1".into()
+        } else if packet == "qfThreadInfo" {
+            "m1".into()
+        } else if packet == "qsThreadInfo" {
+            "l".into()
+        } else if let Some(x) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            let (offset, length) = match parse_address_length(x) {
+                Some((offset, length)) => (offset as usize, length as usize),
+                None => return "E01".into(),
+            };
+
+            let data = TARGET_XML.as_bytes();
+            if offset >= data.len() {
+                return "l".into();
+            }
+
+            let end = (offset + length).min(data.len());
+            let chunk = core::str::from_utf8(&data[offset..end]).unwrap();
+
+            format!("{}{}", if end == data.len() { "l" } else { "m" }, chunk)
+        } else if let Some(x) = packet.strip_prefix("qRcmd,") {
+            match decode_hex(x).and_then(|x| String::from_utf8(x).ok()) {
+                Some(command) => encode_hex(self.handle_monitor(command.trim()).as_bytes()),
+                None => "E01".into(),
+            }
+        } else {
+            String::new()
+        }
+    }
+
+    // `monitor symbol <address>` describes address with loaded guest symbols, as gdb doesn't have them
+    fn handle_monitor(&self, command: &str) -> String {
+        let Some(address) = command.strip_prefix("symbol ") else {
+            return "Usage: monitor symbol <address>\n".into();
+        };
+
+        let address = address.trim();
+        let address = match u32::from_str_radix(address.trim_start_matches("0x"), 16) {
+            Ok(x) => x,
+            Err(_) => return format!("Invalid address {}\n", address),
+        };
+
+        match self.core.describe_address(address) {
+            Some(x) => format!("{:#x}: {}\n", address, x),
+            None => format!("No symbol matches {:#x}\n", address),
+        }
+    }
+
+    fn handle_breakpoint(&mut self, insert: bool, args: &str) -> Option<String> {
+        let mut parts = args.split(',');
+        let kind = parts.next()?;
+        let address = u32::from_str_radix(parts.next()?, 16).ok()?;
+        let size = u32::from_str_radix(parts.next()?, 16).ok()?;
+
+        match kind {
+            "0" | "1" => self.core.debug_set_breakpoint(address & !1, insert),
+            "2" => self.core.debug_set_watchpoint(address, size, WatchpointKind::Write, insert),
+            "3" => self.core.debug_set_watchpoint(address, size, WatchpointKind::Read, insert),
+            "4" => self.core.debug_set_watchpoint(address, size, WatchpointKind::Access, insert),
+            _ => return Some(String::new()),
+        }
+
+        Some("OK".into())
+    }
+
+    fn stop_reply(&self, reason: StopReason) -> String {
+        match reason {
+            StopReason::Breakpoint => "T05swbreak:;".into(),
+            StopReason::Watchpoint { address, kind } => {
+                let name = match kind {
+                    WatchpointKind::Write => "watch",
+                    WatchpointKind::Read => "rwatch",
+                    WatchpointKind::Access => "awatch",
+                };
+
+                format!("T05{}:{:x};", name, address)
+            }
+            StopReason::Step => "T05".into(),
+            StopReason::Interrupt => "T02".into(),
+        }
+    }
+
+    fn frame(&self, response: &str) -> Vec<u8> {
+        let checksum = response.bytes().fold(0u8, |acc, x| acc.wrapping_add(x));
+
+        format!("${}#{:02x}", response, checksum).into_bytes()
+    }
+}
+
+impl Debugger for GdbStub {
+    fn handle_input(&mut self, data: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(data);
+
+        let mut output = Vec::new();
+        loop {
+            match self.buffer.first() {
+                None => break,
+                Some(b'+') | Some(b'-') => {
+                    self.buffer.remove(0);
+                }
+                Some(0x03) => {
+                    self.buffer.remove(0);
+                    self.core.debug_request_halt();
+                }
+                Some(b'$') => {
+                    let end = match self.buffer.iter().position(|&x| x == b'#') {
+                        Some(x) if self.buffer.len() >= x + 3 => x,
+                        _ => break, // incomplete packet
+                    };
+
+                    let packet = String::from_utf8_lossy(&self.buffer[1..end]).into_owned();
+                    self.buffer.drain(..end + 3);
+
+                    if !self.no_ack {
+                        output.push(b'+');
+                    }
+
+                    match self.handle_packet(&packet) {
+                        Some(Some(x)) => output.extend(self.frame(&x)),
+                        Some(None) => {}
+                        None => output.extend(self.frame("E01")),
+                    }
+                }
+                Some(_) => {
+                    self.buffer.remove(0);
+                }
+            }
+        }
+
+        output
+    }
+
+    fn detach(&mut self) {
+        self.core.debug_detach();
+        self.running = false;
+    }
+
+    fn poll(&mut self) -> Vec<u8> {
+        let stop = self.core.debug_take_stop_event();
+
+        match stop {
+            Some(x) if self.running => {
+                self.running = false;
+
+                self.frame(&self.stop_reply(x))
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn parse_address_length(args: &str) -> Option<(u32, u32)> {
+    let (address, length) = args.split_once(',')?;
+
+    Some((u32::from_str_radix(address, 16).ok()?, u32::from_str_radix(length, 16).ok()?))
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn decode_hex(data: &str) -> Option<Vec<u8>> {
+    if data.len() % 2 == 1 {
+        return None;
+    }
+
+    (0..data.len())
+        .step_by(2)
+        .map(|x| u8::from_str_radix(data.get(x..x + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, format, rc::Rc, vec::Vec};
+    use core::cell::Cell;
+
+    use wie_backend::{Debugger, HeadlessPlatform, System, VirtualClock};
+
+    use test_utils::test_platform;
+
+    use crate::{Allocator, ArmCore, GdbStub, MemoryPermission, SymbolTable};
+
+    #[test]
+    fn test_gdb_packets() {
+        let core = ArmCore::new(System::new(Box::new(test_platform()), Box::new(()))).unwrap();
+        let mut stub = GdbStub::new(core);
+
+        let registers = format!("+${}10000000#", "00000000".repeat(16));
+        let output = stub.handle_input(b"$g#67");
+        assert_eq!(&output[..registers.len()], registers.as_bytes());
+
+        // unmapped memory
+        assert_eq!(stub.handle_input(b"$m0,4#fd"), b"+$E14#aa");
+
+        // partial packet
+        assert_eq!(stub.handle_input(b"$m0,"), b"");
+        assert_eq!(stub.handle_input(b"4#fd"), b"+$E14#aa");
+    }
+
+    #[test]
+    fn test_gdb_monitor_symbol() {
+        let mut core = ArmCore::new(System::new(Box::new(test_platform()), Box::new(()))).unwrap();
+        let mut symbols = SymbolTable::new();
+        symbols.add("main", 0x1001, 0x20);
+        core.set_symbols(symbols);
+
+        let mut stub = GdbStub::new(core);
+
+        // monitor symbol 1010
+        assert_eq!(
+            stub.handle_input(b"$qRcmd,73796d626f6c2031303130#24"),
+            b"+$3078313031303a206d61696e2b307831300a#1f"
+        );
+    }
+
+    #[test]
+    fn test_gdb_stop_replies() -> anyhow::Result<()> {
+        let clock = VirtualClock::new(0);
+        let mut system = System::new(Box::new(HeadlessPlatform::new(240, 320, clock.clone())), Box::new(()));
+        let mut core = ArmCore::new(system.clone())?;
+        Allocator::init(&mut core)?; // for task stacks
+
+        let code = [
+            0xe3a00001u32, // mov r0, #1
+            0xe3a02002,    // mov r2, #2
+            0xe5810000,    // str r0, [r1]
+            0xe12fff1e,    // bx lr
+        ];
+        let code = code.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>();
+        core.load(&code, 0x10000, 0x1000, MemoryPermission::ReadExecute)?;
+        core.map(0x20000, 0x1000)?;
+
+        let finished = Rc::new(Cell::new(0));
+        let spawn = |core: &mut ArmCore| {
+            let mut core_clone = core.clone();
+            let finished = finished.clone();
+            core.spawn(move || async move {
+                core_clone.run_function::<()>(0x10000, &[0, 0x20000]).await?;
+                finished.set(finished.get() + 1);
+
+                anyhow::Ok(())
+            });
+        };
+
+        let mut stub = GdbStub::new(core.clone());
+        spawn(&mut core);
+
+        // halted on attach
+        system.tick()?;
+        assert!(core.debug_is_halted());
+        assert_eq!(stub.poll(), b"");
+
+        assert_eq!(stub.handle_input(b"$Z0,10004,4#0b"), b"+$OK#9a");
+        assert_eq!(stub.handle_input(b"$c#63"), b"+");
+        clock.advance(10);
+        system.tick()?;
+        assert_eq!(stub.poll(), b"$T05swbreak:;#1d");
+        assert_eq!(core.debug_read_registers()[15], 0x10004);
+
+        assert_eq!(stub.handle_input(b"$s#73"), b"+");
+        clock.advance(10);
+        system.tick()?;
+        assert_eq!(stub.poll(), b"$T05#b9");
+        assert_eq!(core.debug_read_registers()[15], 0x10008);
+
+        assert_eq!(stub.handle_input(b"$Z2,20000,4#0a"), b"+$OK#9a");
+        assert_eq!(stub.handle_input(b"$c#63"), b"+");
+        clock.advance(10);
+        system.tick()?;
+        assert_eq!(stub.poll(), b"$T05watch:20000;#37");
+
+        // detach drops breakpoint and watchpoint, so the next run doesn't stop
+        assert_eq!(stub.handle_input(b"$D#44"), b"+$OK#9a");
+        clock.advance(10);
+        system.tick()?;
+        assert_eq!(finished.get(), 1);
+
+        spawn(&mut core);
+        system.tick()?;
+        assert_eq!(finished.get(), 2);
+        assert!(!core.debug_is_halted());
+        assert_eq!(core.read_bytes(0x20000, 4)?, [1, 0, 0, 0]);
+
+        Ok(())
+    }
+}
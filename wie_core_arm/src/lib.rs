@@ -4,16 +4,22 @@ extern crate alloc;
 mod allocator;
 mod context;
 mod core;
+mod debug;
 mod engine;
 mod error;
 mod function;
 mod future;
+mod gdb;
+mod symbol;
 
 pub type ArmCoreResult<T> = Result<T, error::ArmCoreError>;
 
 pub use self::{
-    allocator::Allocator,
-    core::{ArmCore, PEB_BASE},
+    allocator::{Allocator, AllocatorStats},
+    core::{ArmCore, ArmCoreState, PEB_BASE},
+    engine::{MemoryAccessKind, MemoryPermission, StopReason, WatchpointKind},
     error::ArmCoreError,
     function::{EmulatedFunction, EmulatedFunctionParam},
+    gdb::GdbStub,
+    symbol::SymbolTable,
 };
@@ -0,0 +1,140 @@
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+struct Symbol {
+    name: String,
+    size: u32, // 0 if unknown
+}
+
+// Guest symbols by address, to describe code addresses as `function+offset`
+#[derive(Default)]
+pub struct SymbolTable {
+    symbols: BTreeMap<u32, Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self { symbols: BTreeMap::new() }
+    }
+
+    pub fn add(&mut self, name: &str, address: u32, size: u32) {
+        // thumb functions have lowest bit set
+        self.symbols.insert(
+            address & !1,
+            Symbol {
+                name: name.to_string(),
+                size,
+            },
+        );
+    }
+
+    // one symbol per line, as `address name` (gnu ld), `address type name` (nm) or `name address type size` (armlink).
+    // other lines are skipped. addresses are relative to `base`. returns the number of symbols added
+    pub fn add_map(&mut self, map: &str, base: u32) -> usize {
+        let mut count = 0;
+        for line in map.lines() {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+            let symbol = match tokens.as_slice() {
+                [address, name] => parse_address(address).map(|x| (*name, x, 0)),
+                [address, kind, name] if kind.len() == 1 => parse_address(address).map(|x| (*name, x, 0)),
+                [name, address, _, "Code" | "Data", size, ..] => parse_address(address).map(|x| (*name, x, size.parse().unwrap_or(0))),
+                _ => None,
+            };
+
+            if let Some((name, address, size)) = symbol {
+                if is_symbol_name(name) {
+                    self.add(name, base.wrapping_add(address), size);
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    pub fn lookup(&self, address: u32) -> Option<(&str, u32)> {
+        let (symbol_address, symbol) = self.symbols.range(..=address).next_back()?;
+        let offset = address - symbol_address;
+
+        if symbol.size != 0 && offset >= symbol.size {
+            return None;
+        }
+
+        Some((&symbol.name, offset))
+    }
+
+    pub fn describe(&self, address: u32) -> Option<String> {
+        let (name, offset) = self.lookup(address & !1)?;
+
+        Some(format!("{}+{:#x}", name, offset))
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+// bare hex is only taken in fixed width, so words like `add` are not read as addresses
+fn parse_address(token: &str) -> Option<u32> {
+    let hex = match token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")) {
+        Some(x) => x,
+        None if token.len() == 8 => token,
+        None => return None,
+    };
+
+    u32::from_str_radix(hex, 16).ok()
+}
+
+// skips sections like `.text` and arm mapping symbols like `$t`
+fn is_symbol_name(name: &str) -> bool {
+    name.starts_with(|x: char| x.is_ascii_alphabetic() || x == '_') && name.chars().all(|x| x.is_ascii_alphanumeric() || x == '_' || x == '$')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SymbolTable;
+
+    #[test]
+    fn test_lookup() {
+        let mut symbols = SymbolTable::new();
+        symbols.add("main", 0x1001, 0x20);
+        symbols.add("unsized", 0x2000, 0);
+
+        assert_eq!(symbols.lookup(0x1000), Some(("main", 0)));
+        assert_eq!(symbols.lookup(0x101f), Some(("main", 0x1f)));
+        assert_eq!(symbols.lookup(0x1020), None);
+        assert_eq!(symbols.lookup(0xfff), None);
+        assert_eq!(symbols.lookup(0x2100), Some(("unsized", 0x100)));
+        assert_eq!(symbols.describe(0x1011).as_deref(), Some("main+0x10"));
+    }
+
+    #[test]
+    fn test_add_map() {
+        let map = "
+    Symbol Name                              Value     Ov Type        Size  Object(Section)
+    startClet                                0x00000101   Thumb Code    24  client.o(.text)
+    $t                                       0x00000100   Number         0  client.o(.text)
+ .text          0x00000200       0x40 game.o
+                0x00000200                paint
+00000300 T update
+add sub
+";
+
+        let mut symbols = SymbolTable::new();
+        assert_eq!(symbols.add_map(map, 0x100000), 3);
+
+        assert_eq!(symbols.lookup(0x100104), Some(("startClet", 4)));
+        assert_eq!(symbols.lookup(0x100118), None);
+        assert_eq!(symbols.lookup(0x100210), Some(("paint", 0x10)));
+        assert_eq!(symbols.lookup(0x100300), Some(("update", 0)));
+    }
+}
@@ -12,7 +12,7 @@ use java_runtime::{classes::java::lang::String as JavaString, Runtime};
 use jvm::{runtime::JavaLangString, ClassInstanceRef, JavaError, Jvm, JvmCallback, Result as JvmResult};
 use jvm_rust::{ClassDefinitionImpl, JvmDetailImpl};
 
-use wie_backend::{AsyncCallable, System};
+use wie_backend::{AsyncCallable, System, TaskOptions};
 use wie_midp::MIDPJavaContextBase;
 use wie_wipi_java::WIPIJavaContextBase;
 
@@ -51,10 +51,13 @@ impl Runtime for JvmCoreRuntime {
             }
         }
 
-        self.system.clone().spawn(SpawnProxy {
-            jvm: self.jvm.clone(),
-            callback,
-        });
+        self.system.clone().spawn_with(
+            TaskOptions::new("java_thread"),
+            SpawnProxy {
+                jvm: self.jvm.clone(),
+                callback,
+            },
+        );
     }
 
     fn now(&self) -> u64 {
@@ -168,11 +171,14 @@ impl WIPIJavaContextBase for JvmCoreContext {
     }
 
     fn spawn(&mut self, callback: Box<dyn MethodBody<JavaError, dyn WIPIJavaContextBase>>) -> JvmResult<()> {
-        self.system.spawn(SpawnProxy {
-            jvm: self.jvm.clone(),
-            callback,
-            context: Box::new(self.clone()),
-        });
+        self.system.spawn_with(
+            TaskOptions::new("wipi_java_callback"),
+            SpawnProxy {
+                jvm: self.jvm.clone(),
+                callback,
+                context: Box::new(self.clone()),
+            },
+        );
 
         Ok(())
     }
@@ -184,11 +190,14 @@ impl MIDPJavaContextBase for JvmCoreContext {
     }
 
     fn spawn(&mut self, callback: Box<dyn MethodBody<JavaError, dyn MIDPJavaContextBase>>) -> JvmResult<()> {
-        self.system.spawn(SpawnProxy {
-            jvm: self.jvm.clone(),
-            callback,
-            context: Box::new(self.clone()),
-        });
+        self.system.spawn_with(
+            TaskOptions::new("midp_callback"),
+            SpawnProxy {
+                jvm: self.jvm.clone(),
+                callback,
+                context: Box::new(self.clone()),
+            },
+        );
 
         Ok(())
     }
@@ -200,11 +209,14 @@ impl SKVMJavaContextBase for JvmCoreContext {
     }
 
     fn spawn(&mut self, callback: Box<dyn MethodBody<JavaError, dyn SKVMJavaContextBase>>) -> JvmResult<()> {
-        self.system.spawn(SpawnProxy {
-            jvm: self.jvm.clone(),
-            callback,
-            context: Box::new(self.clone()),
-        });
+        self.system.spawn_with(
+            TaskOptions::new("skvm_callback"),
+            SpawnProxy {
+                jvm: self.jvm.clone(),
+                callback,
+                context: Box::new(self.clone()),
+            },
+        );
 
         Ok(())
     }
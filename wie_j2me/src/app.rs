@@ -1,6 +1,6 @@
 use alloc::{string::String, vec::Vec};
 
-use wie_backend::{App, Event, System};
+use wie_backend::{App, Event, System, TaskOptions};
 use wie_core_jvm::JvmCore;
 
 pub struct J2MEApp {
@@ -51,8 +51,9 @@ impl App for J2MEApp {
         let main_class_name = self.main_class_name.clone();
         let jar = self.jar.clone();
 
-        self.system
-            .spawn(move || async move { Self::do_start(&mut system, jar, main_class_name).await });
+        self.system.spawn_with(TaskOptions::new("main").critical(), move || async move {
+            Self::do_start(&mut system, jar, main_class_name).await
+        });
 
         Ok(())
     }
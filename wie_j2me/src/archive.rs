@@ -1,11 +1,10 @@
 use alloc::{
     boxed::Box,
-    str,
     string::{String, ToString},
     vec::Vec,
 };
 
-use wie_backend::{App, Archive, Platform, System};
+use wie_backend::{App, Archive, DeviceProfile, Platform, System, TextEncoding};
 
 use crate::app::J2MEApp;
 
@@ -13,16 +12,22 @@ pub struct J2MEArchive {
     jar: Vec<u8>,
     name: String,
     main_class_name: Option<String>,
+    encoding: TextEncoding,
+    screen_size: Option<(u32, u32)>,
 }
 
 impl J2MEArchive {
+    // jad is utf-8 by spec, but korean handsets, which we mostly target, expect euc-kr unless told otherwise
     pub fn from_jad_jar(jad: Vec<u8>, jar: Vec<u8>) -> Self {
-        let descriptor = J2MEDescriptor::parse(&jad);
+        let encoding = TextEncoding::detect(&jad, TextEncoding::EucKr);
+        let descriptor = J2MEDescriptor::parse(&jad, encoding);
 
         Self {
             jar,
             name: descriptor.name,
             main_class_name: Some(descriptor.main_class_name),
+            encoding,
+            screen_size: descriptor.screen_size,
         }
     }
 
@@ -31,6 +36,8 @@ impl J2MEArchive {
             jar,
             name: filename,
             main_class_name: None,
+            encoding: TextEncoding::EucKr,
+            screen_size: None,
         }
     }
 }
@@ -40,8 +47,24 @@ impl Archive for J2MEArchive {
         self.name.clone()
     }
 
+    fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+    }
+
+    fn device_profile(&self) -> DeviceProfile {
+        match self.screen_size {
+            Some((width, height)) => DeviceProfile::default().with_screen_size(width, height),
+            None => DeviceProfile::default(),
+        }
+    }
+
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
         let system = System::new(platform, Box::new(()));
+        system.set_encoding(self.encoding);
 
         Ok(Box::new(J2MEApp::new(self.main_class_name, self.jar, system)?))
     }
@@ -50,17 +73,19 @@ impl Archive for J2MEArchive {
 struct J2MEDescriptor {
     name: String,
     main_class_name: String,
+    screen_size: Option<(u32, u32)>,
 }
 
 impl J2MEDescriptor {
-    pub fn parse(data: &[u8]) -> Self {
-        let lines = data.split(|x| *x == b'\n');
+    pub fn parse(data: &[u8], encoding: TextEncoding) -> Self {
+        let data = encoding.decode(data);
 
         let mut name = String::new();
         let mut main_class_name = String::new();
+        let mut screen_size = None;
 
-        for line in lines {
-            let line = str::from_utf8(line).unwrap().trim();
+        for line in data.split('\n') {
+            let line = line.trim();
 
             if line.is_empty() {
                 continue;
@@ -74,10 +99,16 @@ impl J2MEDescriptor {
             match key {
                 "MIDlet-Name" => name = value.to_string(),
                 "MIDlet-1" => main_class_name = value.split(',').nth(2).unwrap().trim().to_string(),
+                // not in the spec, but commonly added for the screen the midlet was made for
+                "Nokia-MIDlet-Original-Display-Size" => screen_size = DeviceProfile::parse_screen_size(value),
                 _ => {}
             }
         }
 
-        Self { name, main_class_name }
+        Self {
+            name,
+            main_class_name,
+            screen_size,
+        }
     }
 }
@@ -1,9 +1,10 @@
-use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
 
 use anyhow::Context;
 
-use wie_backend::{App, Event, System};
-use wie_core_arm::{Allocator, ArmCore};
+use wie_backend::{App, Debugger, Event, System, TaskOptions};
+use wie_core_arm::{Allocator, ArmCore, GdbStub, MemoryPermission, SymbolTable};
+use wie_util::{SnapshotReader, SnapshotWriter};
 
 use crate::context::KtfContextExt;
 
@@ -20,7 +21,8 @@ impl KtfApp {
     pub fn new(jar: Vec<u8>, additional_files: BTreeMap<String, Vec<u8>>, main_class_name: Option<String>, system: System) -> anyhow::Result<Self> {
         let mut core = ArmCore::new(system.clone())?;
 
-        system.resource_mut().mount_zip(&jar)?;
+        let encoding = system.encoding();
+        system.resource_mut().mount_zip(&jar, encoding)?;
 
         for (path, data) in additional_files {
             let path = path.trim_start_matches("P/");
@@ -34,7 +36,14 @@ impl KtfApp {
             let filename = resource.files().find(|x| x.starts_with("client.bin")).context("Invalid archive")?;
             let data = resource.data(resource.id(filename).context("Resource not found")?);
 
-            Self::load(&mut core, data, filename)?
+            let bss_size = Self::load(&mut core, data, filename)?;
+
+            // client.bin is stripped, but map file from the build can be put next to it
+            if let Some(x) = resource.id("client.map") {
+                Self::load_symbols(&mut core, resource.data(x));
+            }
+
+            bss_size
         };
 
         Ok(Self {
@@ -83,12 +92,26 @@ impl KtfApp {
         let bss_start = filename.find("client.bin").context("Incorrect filename")? + 10;
         let bss_size = filename[bss_start..].parse::<u32>()?;
 
-        core.load(data, IMAGE_BASE, data.len() + bss_size as usize)?;
+        // client.bin is a flat image with code and data mixed, so only bss can be kept from being executed
+        core.load(data, IMAGE_BASE, data.len(), MemoryPermission::ReadWriteExecute)?;
+        core.map(IMAGE_BASE + data.len() as u32, bss_size)?;
 
         tracing::debug!("Loaded at {:#x}, size {:#x}, bss {:#x}", IMAGE_BASE, data.len(), bss_size);
 
         Ok(bss_size)
     }
+
+    fn load_symbols(core: &mut ArmCore, data: &[u8]) {
+        let map = String::from_utf8_lossy(data);
+
+        let mut symbols = SymbolTable::new();
+        if symbols.add_map(&map, IMAGE_BASE) == 0 {
+            tracing::warn!("No symbols found in client.map");
+            return;
+        }
+
+        core.set_symbols(symbols);
+    }
 }
 
 impl App for KtfApp {
@@ -99,8 +122,9 @@ impl App for KtfApp {
         let bss_size = self.bss_size;
         let main_class_name = self.main_class_name.clone();
 
-        self.core
-            .spawn(move || async move { Self::do_start(&mut core, &mut system, bss_size, main_class_name).await });
+        self.core.spawn_with(TaskOptions::new("main").critical(), move || async move {
+            Self::do_start(&mut core, &mut system, bss_size, main_class_name).await
+        });
 
         Ok(())
     }
@@ -109,9 +133,38 @@ impl App for KtfApp {
         self.system.event_queue().push(event)
     }
 
+    // errors of arm tasks come with registers and call stack of the failing task
     fn tick(&mut self) -> anyhow::Result<()> {
-        self.system
-            .tick()
-            .map_err(|x| anyhow::anyhow!("{}\n{}", x, self.core.dump_reg_stack(IMAGE_BASE)))
+        self.system.tick()
+    }
+
+    fn save_state(&mut self) -> anyhow::Result<Vec<u8>> {
+        let mut writer = SnapshotWriter::new();
+
+        self.core.save_state(&mut writer);
+        self.system.save_state(&mut writer);
+        self.system.save_jvm_state(&mut writer);
+
+        Ok(writer.into_inner())
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let mut reader = SnapshotReader::new(data);
+
+        let core_state = self.core.read_state(&mut reader)?;
+        let system_state = self.system.read_state(&mut reader)?;
+        let jvm_state = self.system.read_jvm_state(&mut reader)?;
+        reader.finish()?;
+
+        // jvm state is only checked against the loaded classes, so it goes before anything is overwritten
+        self.system.restore_jvm_state(jvm_state)?;
+        self.core.restore_state(core_state)?;
+        self.system.restore_state(system_state);
+
+        Ok(())
+    }
+
+    fn debugger(&mut self) -> Option<Box<dyn Debugger>> {
+        Some(Box::new(GdbStub::new(self.core.clone())))
     }
 }
@@ -2,7 +2,7 @@ use alloc::{borrow::ToOwned, boxed::Box, collections::BTreeMap, format, string::
 
 use anyhow::Context;
 
-use wie_backend::{extract_zip, App, Archive, Platform, System};
+use wie_backend::{extract_zip, App, Archive, Carrier, DeviceProfile, Platform, System, TextEncoding};
 
 use crate::{app::KtfApp, context::KtfContext};
 
@@ -11,6 +11,7 @@ pub struct KtfArchive {
     id: String,
     main_class_name: Option<String>,
     additional_files: BTreeMap<String, Vec<u8>>,
+    encoding: TextEncoding,
 }
 
 impl KtfArchive {
@@ -30,17 +31,22 @@ impl KtfArchive {
         false
     }
 
-    pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>) -> anyhow::Result<Self> {
+    // `encoding` overrides the one detected from the descriptor
+    pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>, encoding: Option<TextEncoding>) -> anyhow::Result<Self> {
         let adf = files.get("__adf__").context("Invalid format")?;
-        let adf = KtfAdf::parse(adf);
+        let encoding = encoding.unwrap_or_else(|| TextEncoding::detect(adf, TextEncoding::EucKr));
+        let adf = KtfAdf::parse(adf, encoding);
 
-        tracing::info!("Loading app {}, mclass {}", adf.aid, adf.mclass);
+        tracing::info!("Loading app {}, mclass {}, name {}", adf.aid, adf.mclass, adf.name);
 
         let jar = files.remove(&format!("{}.jar", adf.aid)).context("Invalid format")?;
 
         let additional_files = files.into_iter().filter(|x| x.0.starts_with("P/")).collect();
 
-        Ok(Self::from_jar(jar, adf.aid, Some(adf.mclass), additional_files))
+        Ok(Self {
+            encoding,
+            ..Self::from_jar(jar, adf.aid, Some(adf.mclass), additional_files)
+        })
     }
 
     pub fn from_jar(data: Vec<u8>, id: String, main_class_name: Option<String>, additional_files: BTreeMap<String, Vec<u8>>) -> Self {
@@ -49,6 +55,7 @@ impl KtfArchive {
             id,
             main_class_name,
             additional_files,
+            encoding: TextEncoding::EucKr,
         }
     }
 }
@@ -58,8 +65,21 @@ impl Archive for KtfArchive {
         self.id.to_owned()
     }
 
+    fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+    }
+
+    fn device_profile(&self) -> DeviceProfile {
+        DeviceProfile::new(Carrier::Ktf)
+    }
+
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
         let system = System::new(platform, Box::new(KtfContext::new()));
+        system.set_encoding(self.encoding);
 
         Ok(Box::new(KtfApp::new(self.jar, self.additional_files, self.main_class_name, system)?))
     }
@@ -68,24 +88,27 @@ impl Archive for KtfArchive {
 struct KtfAdf {
     aid: String,
     mclass: String,
+    name: String,
 }
 
 impl KtfAdf {
-    pub fn parse(data: &[u8]) -> Self {
+    pub fn parse(data: &[u8], encoding: TextEncoding) -> Self {
         let mut aid = String::new();
         let mut mclass = String::new();
+        let mut name = String::new();
 
-        let mut lines = data.split(|x| *x == b'\n');
+        let data = encoding.decode(data);
 
-        for line in &mut lines {
-            if line.starts_with(b"AID:") {
-                aid = String::from_utf8_lossy(&line[4..]).into();
-            } else if line.starts_with(b"MClass:") {
-                mclass = String::from_utf8_lossy(&line[7..]).into();
+        for line in data.split('\n') {
+            if let Some(x) = line.strip_prefix("AID:") {
+                aid = x.into();
+            } else if let Some(x) = line.strip_prefix("MClass:") {
+                mclass = x.into();
+            } else if let Some(x) = line.strip_prefix("Name:") {
+                name = x.trim().into();
             }
-            // TODO load name, it's in euc-kr..
         }
 
-        Self { aid, mclass }
+        Self { aid, mclass, name }
     }
 }
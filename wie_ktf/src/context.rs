@@ -1,22 +1,37 @@
-use alloc::rc::Rc;
+use alloc::{borrow::ToOwned, collections::BTreeMap, rc::Rc, string::String};
 
 use wie_backend::System;
+use wie_util::{SnapshotError, SnapshotReader, SnapshotResult, SnapshotWriter};
 
 use jvm::Jvm;
 
 pub struct KtfContext {
     jvm: Option<Rc<Jvm>>,
+    // java objects and classes live on emulated memory, jvm only keeps which classes are loaded where
+    classes: BTreeMap<String, u32>,
 }
 
 impl KtfContext {
     pub fn new() -> Self {
-        Self { jvm: None }
+        Self {
+            jvm: None,
+            classes: BTreeMap::new(),
+        }
     }
 }
 
+// Save state read up front, so nothing is changed if the snapshot turns out to be invalid
+pub struct KtfJvmState {
+    classes: BTreeMap<String, u32>,
+}
+
 pub trait KtfContextExt {
     fn jvm(&mut self) -> Rc<Jvm>;
     fn set_jvm(&mut self, jvm: Jvm);
+    fn add_class(&mut self, name: &str, ptr_raw: u32);
+    fn save_jvm_state(&self, writer: &mut SnapshotWriter);
+    fn read_jvm_state(&self, reader: &mut SnapshotReader) -> SnapshotResult<KtfJvmState>;
+    fn restore_jvm_state(&mut self, state: KtfJvmState) -> anyhow::Result<()>;
 }
 
 impl KtfContextExt for System {
@@ -33,4 +48,53 @@ impl KtfContextExt for System {
 
         context.jvm = Some(Rc::new(jvm))
     }
+
+    fn add_class(&mut self, name: &str, ptr_raw: u32) {
+        let mut context = self.context();
+        let context = (*context).downcast_mut::<KtfContext>().unwrap();
+
+        context.classes.insert(name.to_owned(), ptr_raw);
+    }
+
+    fn save_jvm_state(&self, writer: &mut SnapshotWriter) {
+        let context = self.context();
+        let context = (*context).downcast_ref::<KtfContext>().unwrap();
+
+        writer.write_u32(context.classes.len() as _);
+        for (name, ptr_raw) in &context.classes {
+            writer.write_bytes(name.as_bytes());
+            writer.write_u32(*ptr_raw);
+        }
+    }
+
+    fn read_jvm_state(&self, reader: &mut SnapshotReader) -> SnapshotResult<KtfJvmState> {
+        let count = reader.read_u32()?;
+
+        let mut classes = BTreeMap::new();
+        for _ in 0..count {
+            let name = String::from_utf8(reader.read_bytes()?.to_vec()).map_err(|_| SnapshotError::InvalidData)?;
+            classes.insert(name, reader.read_u32()?);
+        }
+
+        Ok(KtfJvmState { classes })
+    }
+
+    // jvm can't unload classes, and classes loaded after the snapshot would point into memory being replaced.
+    // So the snapshot has to agree with the loaded classes, everything else jvm has is restored with the memory.
+    fn restore_jvm_state(&mut self, state: KtfJvmState) -> anyhow::Result<()> {
+        let context = self.context();
+        let context = (*context).downcast_ref::<KtfContext>().unwrap();
+
+        let mismatch = context
+            .classes
+            .iter()
+            .find(|(name, ptr_raw)| state.classes.get(*name) != Some(ptr_raw))
+            .map(|(name, _)| name)
+            .or_else(|| state.classes.keys().find(|x| !context.classes.contains_key(*x)));
+        if let Some(name) = mismatch {
+            anyhow::bail!("Class {} is loaded differently than in the saved state", name);
+        }
+
+        Ok(())
+    }
 }
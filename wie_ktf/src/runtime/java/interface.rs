@@ -111,10 +111,12 @@ async fn register_class(core: &mut ArmCore, system: &mut System, ptr_class: u32)
     tracing::trace!("register_class({:#x})", ptr_class);
 
     let class = KtfJvmSupport::class_from_raw(core, ptr_class);
-    if system.jvm().has_class(&class.name()?) {
+    let name = class.name()?;
+    if system.jvm().has_class(&name) {
         return Ok(());
     }
 
+    system.add_class(&name, ptr_class);
     system.jvm().register_class(Box::new(class), None).await?;
 
     Ok(())
@@ -72,44 +72,50 @@ impl KtfJvmSupport {
                 ptr_current_java_exception_handler,
             },
         )?;
-        system.set_jvm(Jvm::new(detail::KtfJvmDetail::new(core)).await?);
+        system.set_jvm(Jvm::new(detail::KtfJvmDetail::new(core, system)).await?);
 
         let jvm = system.jvm();
 
         let runtime = KtfRuntime::new(core, system, jvm.clone());
         let core_clone = core.clone();
+        let system_clone = system.clone();
         let jvm_clone = jvm.clone();
         java_runtime::initialize(&jvm, move |name, proto| {
             let name = name.to_string();
             let mut core_clone = core_clone.clone();
+            let mut system_clone = system_clone.clone();
             let jvm_clone = jvm_clone.clone();
             let runtime = runtime.clone();
 
             async move {
-                Box::new(
-                    JavaClassDefinition::new(&mut core_clone, &jvm_clone, &name, proto, Box::new(runtime) as Box<_>)
-                        .await
-                        .unwrap(),
-                ) as Box<_>
+                let class = JavaClassDefinition::new(&mut core_clone, &jvm_clone, &name, proto, Box::new(runtime) as Box<_>)
+                    .await
+                    .unwrap();
+                system_clone.add_class(&name, class.ptr_raw);
+
+                Box::new(class) as Box<_>
             }
         })
         .await?;
 
         let context = KtfWIPIJavaContext::new(core, system, jvm.clone());
         let core_clone = core.clone();
+        let system_clone = system.clone();
         let jvm_clone = jvm.clone();
         wie_wipi_java::register(&jvm, move |name, proto| {
             let name = name.to_string();
             let mut core_clone = core_clone.clone();
+            let mut system_clone = system_clone.clone();
             let jvm_clone = jvm_clone.clone();
             let context = context.clone();
 
             async move {
-                Box::new(
-                    JavaClassDefinition::new(&mut core_clone, &jvm_clone, &name, proto, Box::new(context) as Box<_>)
-                        .await
-                        .unwrap(),
-                ) as Box<_>
+                let class = JavaClassDefinition::new(&mut core_clone, &jvm_clone, &name, proto, Box::new(context) as Box<_>)
+                    .await
+                    .unwrap();
+                system_clone.add_class(&name, class.ptr_raw);
+
+                Box::new(class) as Box<_>
             }
         })
         .await?;
@@ -142,6 +148,7 @@ impl KtfJvmSupport {
         )
         .await?;
 
+        system.add_class("wie/KtfClassLoader", class_loader_class.ptr_raw);
         jvm.register_class(Box::new(class_loader_class), None).await?;
 
         let old_class_loader = jvm.get_system_class_loader().await?;
@@ -192,10 +199,14 @@ mod test {
 
     use wie_backend::System;
     use wie_core_arm::{Allocator, ArmCore};
+    use wie_util::{SnapshotReader, SnapshotWriter};
 
-    use crate::{context::KtfContext, runtime::java::jvm_support::KtfJvmSupport};
+    use crate::{
+        context::{KtfContext, KtfContextExt},
+        runtime::java::jvm_support::KtfJvmSupport,
+    };
 
-    use test_utils::TestPlatform;
+    use test_utils::test_platform;
 
     async fn init_jvm(system: &mut System) -> anyhow::Result<Rc<Jvm>> {
         let mut core = ArmCore::new(system.clone())?;
@@ -214,7 +225,7 @@ mod test {
 
     #[futures_test::test]
     async fn test_jvm_support() -> anyhow::Result<()> {
-        let mut system = System::new(Box::new(TestPlatform), Box::new(KtfContext::new()));
+        let mut system = System::new(Box::new(test_platform()), Box::new(KtfContext::new()));
         let jvm = init_jvm(&mut system).await?;
 
         let string1 = JavaLangString::from_rust_string(&jvm, "test1").await?;
@@ -228,4 +239,28 @@ mod test {
 
         Ok(())
     }
+
+    #[futures_test::test]
+    async fn test_jvm_state() -> anyhow::Result<()> {
+        let mut system = System::new(Box::new(test_platform()), Box::new(KtfContext::new()));
+        let jvm = init_jvm(&mut system).await?;
+
+        let mut writer = SnapshotWriter::new();
+        system.save_jvm_state(&mut writer);
+        let state = writer.into_inner();
+
+        let mut reader = SnapshotReader::new(&state);
+        let jvm_state = system.read_jvm_state(&mut reader)?;
+        reader.finish()?;
+        system.restore_jvm_state(jvm_state)?;
+
+        // loads a new array class
+        jvm.instantiate_array("[[J", 1).await?;
+
+        let mut reader = SnapshotReader::new(&state);
+        let jvm_state = system.read_jvm_state(&mut reader)?;
+        assert!(system.restore_jvm_state(jvm_state).is_err());
+
+        Ok(())
+    }
 }
@@ -15,7 +15,10 @@ use wie_backend::System;
 use wie_core_arm::{Allocator, ArmCore};
 use wie_util::write_null_terminated_string;
 
-use crate::runtime::java::jvm_support::{class_definition::JavaClassDefinition, context_data::JavaContextData};
+use crate::{
+    context::KtfContextExt,
+    runtime::java::jvm_support::{class_definition::JavaClassDefinition, context_data::JavaContextData},
+};
 
 pub trait ClassLoaderContextBase: DynClone {
     fn core(&mut self) -> &mut ArmCore;
@@ -85,6 +88,7 @@ impl KtfClassLoader {
 
         if ptr_raw != 0 {
             let class = JavaClassDefinition::from_raw(ptr_raw, core);
+            context.system().clone().add_class(&name, ptr_raw);
             jvm.register_class(Box::new(class), Some(this.into())).await?;
 
             Ok(jvm.resolve_class(&name).await?.java_class(jvm).await?.into())
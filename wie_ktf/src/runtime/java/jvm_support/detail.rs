@@ -2,17 +2,24 @@ use alloc::{boxed::Box, format};
 
 use jvm::{ClassDefinition, Jvm, JvmDetail, Result as JvmResult};
 
+use wie_backend::System;
 use wie_core_arm::ArmCore;
 
+use crate::context::KtfContextExt;
+
 use super::array_class_definition::JavaArrayClassDefinition;
 
 pub struct KtfJvmDetail {
     core: ArmCore,
+    system: System,
 }
 
 impl KtfJvmDetail {
-    pub fn new(core: &ArmCore) -> Self {
-        Self { core: core.clone() }
+    pub fn new(core: &ArmCore, system: &System) -> Self {
+        Self {
+            core: core.clone(),
+            system: system.clone(),
+        }
     }
 }
 
@@ -25,6 +32,7 @@ impl JvmDetail for KtfJvmDetail {
     async fn define_array_class(&self, jvm: &Jvm, element_type_name: &str) -> JvmResult<Box<dyn ClassDefinition>> {
         let class_name = format!("[{}", element_type_name);
         let class = JavaArrayClassDefinition::new(&mut self.core.clone(), jvm, &class_name).await.unwrap();
+        self.system.clone().add_class(&class_name, class.class.ptr_raw);
 
         Ok(Box::new(class) as Box<_>)
     }
@@ -1,7 +1,7 @@
 use alloc::{boxed::Box, rc::Rc, string::String, vec, vec::Vec};
 use core::time::Duration;
 
-use wie_backend::{AsyncCallable, System};
+use wie_backend::{AsyncCallable, System, TaskOptions};
 use wie_core_arm::ArmCore;
 
 use java_runtime::Runtime;
@@ -52,10 +52,13 @@ impl Runtime for KtfRuntime {
             }
         }
 
-        self.core.clone().spawn(SpawnProxy {
-            jvm: self.jvm.clone(),
-            callback,
-        });
+        self.core.clone().spawn_with(
+            TaskOptions::new("java_thread"),
+            SpawnProxy {
+                jvm: self.jvm.clone(),
+                callback,
+            },
+        );
     }
 
     fn now(&self) -> u64 {
@@ -3,7 +3,7 @@ use alloc::{boxed::Box, rc::Rc};
 use java_class_proto::MethodBody;
 use jvm::{JavaError, Jvm, Result as JvmResult};
 
-use wie_backend::{AsyncCallable, System};
+use wie_backend::{AsyncCallable, System, TaskOptions};
 use wie_core_arm::ArmCore;
 use wie_wipi_java::WIPIJavaContextBase;
 
@@ -49,12 +49,15 @@ impl WIPIJavaContextBase for KtfWIPIJavaContext {
             }
         }
 
-        self.core.spawn(SpawnProxy {
-            core: self.core.clone(),
-            system: self.system.clone(),
-            jvm: self.jvm.clone(),
-            callback,
-        });
+        self.core.spawn_with(
+            TaskOptions::new("wipi_java_callback"),
+            SpawnProxy {
+                core: self.core.clone(),
+                system: self.system.clone(),
+                jvm: self.jvm.clone(),
+                callback,
+            },
+        );
 
         Ok(())
     }
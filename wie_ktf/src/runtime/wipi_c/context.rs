@@ -1,6 +1,6 @@
-use alloc::{boxed::Box, vec, vec::Vec};
+use alloc::{boxed::Box, format, vec, vec::Vec};
 
-use wie_backend::{AsyncCallable, System};
+use wie_backend::{AsyncCallable, System, TaskOptions};
 use wie_core_arm::{Allocator, ArmCore, ArmCoreError, EmulatedFunction, EmulatedFunctionParam};
 use wie_util::{read_generic, write_generic, ByteRead, ByteWrite};
 use wie_wipi_c::{WIPICContext, WIPICError, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord};
@@ -19,26 +19,22 @@ impl<'a> KtfWIPICContext<'a> {
 #[async_trait::async_trait(?Send)]
 impl WIPICContext for KtfWIPICContext<'_> {
     fn alloc_raw(&mut self, size: WIPICWord) -> WIPICResult<WIPICWord> {
-        Ok(Allocator::alloc(self.core, size).unwrap())
+        Allocator::alloc(self.core, size).map_err(to_wipi_c_error)
     }
 
     fn alloc(&mut self, size: WIPICWord) -> WIPICResult<WIPICMemoryId> {
-        let ptr = Allocator::alloc(self.core, size + 12).unwrap(); // all allocation has indirect pointer
+        let ptr = Allocator::alloc(self.core, size + 12).map_err(to_wipi_c_error)?; // all allocation has indirect pointer
         write_generic(self.core, ptr, ptr + 4)?;
 
         Ok(WIPICMemoryId(ptr))
     }
 
     fn free(&mut self, memory: WIPICMemoryId) -> WIPICResult<()> {
-        Allocator::free(self.core, memory.0).unwrap();
-
-        Ok(())
+        Allocator::free(self.core, memory.0).map_err(to_wipi_c_error)
     }
 
     fn free_raw(&mut self, address: WIPICWord) -> WIPICResult<()> {
-        Allocator::free(self.core, address).unwrap();
-
-        Ok(())
+        Allocator::free(self.core, address).map_err(to_wipi_c_error)
     }
 
     fn data_ptr(&self, memory: WIPICMemoryId) -> WIPICResult<WIPICWord> {
@@ -47,6 +43,14 @@ impl WIPICContext for KtfWIPICContext<'_> {
         Ok(base + 8) // all data has offset of 8 bytes
     }
 
+    fn total_memory(&self) -> WIPICResult<WIPICWord> {
+        Ok(Allocator::stats(self.core).map_err(to_wipi_c_error)?.total)
+    }
+
+    fn free_memory(&self) -> WIPICResult<WIPICWord> {
+        Ok(Allocator::stats(self.core).map_err(to_wipi_c_error)?.free)
+    }
+
     fn register_function(&mut self, body: WIPICMethodBody) -> WIPICResult<WIPICWord> {
         struct CMethodProxy {
             body: WIPICMethodBody,
@@ -112,11 +116,14 @@ impl WIPICContext for KtfWIPICContext<'_> {
 
         let system = self.system.clone();
 
-        self.core.spawn(SpawnProxy {
-            core: self.core.clone(),
-            system,
-            callback,
-        });
+        self.core.spawn_with(
+            TaskOptions::new("wipi_c_callback"),
+            SpawnProxy {
+                core: self.core.clone(),
+                system,
+                callback,
+            },
+        );
 
         Ok(())
     }
@@ -133,3 +140,10 @@ impl ByteWrite for KtfWIPICContext<'_> {
         self.core.write_bytes(address, data)
     }
 }
+
+fn to_wipi_c_error(error: ArmCoreError) -> WIPICError {
+    match error {
+        ArmCoreError::OutOfMemory => WIPICError::OutOfMemory,
+        x => WIPICError::BackendError(format!("{}", x)),
+    }
+}
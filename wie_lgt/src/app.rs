@@ -1,10 +1,11 @@
-use alloc::string::String;
+use alloc::{boxed::Box, string::String, vec::Vec};
 
 use anyhow::Context;
 use elf::{endian::AnyEndian, ElfBytes};
 
-use wie_backend::{App, Event, System};
-use wie_core_arm::{Allocator, ArmCore};
+use wie_backend::{App, Debugger, Event, System, TaskOptions};
+use wie_core_arm::{Allocator, ArmCore, GdbStub, MemoryPermission, SymbolTable};
+use wie_util::{SnapshotReader, SnapshotWriter};
 
 pub struct LgtApp {
     core: ArmCore,
@@ -65,9 +66,39 @@ impl LgtApp {
 
                 let data = elf.section_data(&shdr)?.0;
 
-                core.load(data, shdr.sh_addr as u32, shdr.sh_size as usize)?;
+                let writable = shdr.sh_flags & elf::abi::SHF_WRITE as u64 != 0;
+                let executable = shdr.sh_flags & elf::abi::SHF_EXECINSTR as u64 != 0;
+                let permission = match (writable, executable) {
+                    (true, true) => MemoryPermission::ReadWriteExecute,
+                    (true, false) => MemoryPermission::ReadWrite,
+                    (false, _) => MemoryPermission::ReadExecute,
+                };
+
+                core.load(data, shdr.sh_addr as u32, shdr.sh_size as usize, permission)?;
+            }
+        }
+
+        // binaries are usually not stripped, so symbols give readable call stacks
+        // they are only for diagnostics, so broken tables don't fail loading
+        let mut symbols = SymbolTable::new();
+        match elf.symbol_table() {
+            Ok(Some((symtab, strtab))) => {
+                for symbol in symtab.iter() {
+                    if symbol.is_undefined() || !matches!(symbol.st_symtype(), elf::abi::STT_FUNC | elf::abi::STT_OBJECT) {
+                        continue;
+                    }
+
+                    match strtab.get(symbol.st_name as usize) {
+                        Ok(name) if !name.is_empty() => symbols.add(name, symbol.st_value as u32, symbol.st_size as u32),
+                        Ok(_) => {}
+                        Err(x) => tracing::warn!("Skipping symbol with invalid name: {}", x),
+                    }
+                }
             }
+            Ok(None) => {}
+            Err(x) => tracing::warn!("Invalid symbol table: {}", x),
         }
+        core.set_symbols(symbols);
 
         tracing::debug!("Entrypoint: {:#x}", elf.ehdr.e_entry);
 
@@ -83,8 +114,9 @@ impl App for LgtApp {
         let entrypoint = self.entrypoint;
         let main_class_name = self.main_class_name.clone();
 
-        self.core
-            .spawn(move || async move { Self::do_start(&mut core, &mut system, entrypoint, main_class_name).await });
+        self.core.spawn_with(TaskOptions::new("main").critical(), move || async move {
+            Self::do_start(&mut core, &mut system, entrypoint, main_class_name).await
+        });
 
         Ok(())
     }
@@ -96,4 +128,30 @@ impl App for LgtApp {
     fn tick(&mut self) -> anyhow::Result<()> {
         self.system.tick()
     }
+
+    fn save_state(&mut self) -> anyhow::Result<Vec<u8>> {
+        let mut writer = SnapshotWriter::new();
+
+        self.core.save_state(&mut writer);
+        self.system.save_state(&mut writer);
+
+        Ok(writer.into_inner())
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let mut reader = SnapshotReader::new(data);
+
+        let core_state = self.core.read_state(&mut reader)?;
+        let system_state = self.system.read_state(&mut reader)?;
+        reader.finish()?;
+
+        self.core.restore_state(core_state)?;
+        self.system.restore_state(system_state);
+
+        Ok(())
+    }
+
+    fn debugger(&mut self) -> Option<Box<dyn Debugger>> {
+        Some(Box::new(GdbStub::new(self.core.clone())))
+    }
 }
@@ -2,7 +2,7 @@ use alloc::{borrow::ToOwned, boxed::Box, collections::BTreeMap, format, string::
 
 use anyhow::Context;
 
-use wie_backend::{extract_zip, App, Archive, Platform, System};
+use wie_backend::{extract_zip, App, Archive, Carrier, DeviceProfile, Platform, System, TextEncoding};
 
 use crate::app::LgtApp;
 
@@ -10,6 +10,7 @@ pub struct LgtArchive {
     jar: Vec<u8>,
     id: String,
     main_class_name: Option<String>,
+    encoding: TextEncoding,
 }
 
 impl LgtArchive {
@@ -23,15 +24,20 @@ impl LgtArchive {
         files.contains_key("binary.mod")
     }
 
-    pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>) -> anyhow::Result<Self> {
+    // `encoding` overrides the one detected from the descriptor
+    pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>, encoding: Option<TextEncoding>) -> anyhow::Result<Self> {
         let app_info = files.get("app_info").context("Invalid format")?;
-        let app_info = LgtAppInfo::parse(app_info);
+        let encoding = encoding.unwrap_or_else(|| TextEncoding::detect(app_info, TextEncoding::EucKr));
+        let app_info = LgtAppInfo::parse(app_info, encoding);
 
-        tracing::info!("Loading app {}, mclass {}", app_info.aid, app_info.mclass);
+        tracing::info!("Loading app {}, mclass {}, name {}", app_info.aid, app_info.mclass, app_info.name);
 
         let jar = files.remove(&format!("{}.jar", app_info.aid)).context("Invalid format")?;
 
-        Ok(Self::from_jar(jar, &app_info.aid, Some(app_info.mclass)))
+        Ok(Self {
+            encoding,
+            ..Self::from_jar(jar, &app_info.aid, Some(app_info.mclass))
+        })
     }
 
     pub fn from_jar(data: Vec<u8>, id: &str, main_class_name: Option<String>) -> Self {
@@ -39,6 +45,7 @@ impl LgtArchive {
             jar: data,
             id: id.into(),
             main_class_name,
+            encoding: TextEncoding::EucKr,
         }
     }
 }
@@ -48,10 +55,23 @@ impl Archive for LgtArchive {
         self.id.to_owned()
     }
 
+    fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+    }
+
+    fn device_profile(&self) -> DeviceProfile {
+        DeviceProfile::new(Carrier::Lgt)
+    }
+
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
         let system = System::new(platform, Box::new(()));
+        system.set_encoding(self.encoding);
 
-        system.resource_mut().mount_zip(&self.jar)?;
+        system.resource_mut().mount_zip(&self.jar, self.encoding)?;
 
         Ok(Box::new(LgtApp::new(self.main_class_name, system)?))
     }
@@ -61,24 +81,27 @@ impl Archive for LgtArchive {
 struct LgtAppInfo {
     aid: String,
     mclass: String,
+    name: String,
 }
 
 impl LgtAppInfo {
-    pub fn parse(data: &[u8]) -> Self {
+    pub fn parse(data: &[u8], encoding: TextEncoding) -> Self {
         let mut aid = String::new();
         let mut mclass = String::new();
+        let mut name = String::new();
 
-        let mut lines = data.split(|x| *x == b'\n');
+        let data = encoding.decode(data);
 
-        for line in &mut lines {
-            if line.starts_with(b"AID:") {
-                aid = String::from_utf8_lossy(&line[4..]).into();
-            } else if line.starts_with(b"MClass:") {
-                mclass = String::from_utf8_lossy(&line[7..]).into();
+        for line in data.split('\n') {
+            if let Some(x) = line.strip_prefix("AID:") {
+                aid = x.into();
+            } else if let Some(x) = line.strip_prefix("MClass:") {
+                mclass = x.into();
+            } else if let Some(x) = line.strip_prefix("Name:") {
+                name = x.trim().into();
             }
-            // TODO load name, it's in euc-kr..
         }
 
-        Self { aid, mclass }
+        Self { aid, mclass, name }
     }
 }
@@ -2,7 +2,7 @@ use alloc::{string::String, vec::Vec};
 
 use jvm::Result as JvmResult;
 
-use wie_backend::{App, Event, System};
+use wie_backend::{App, Event, System, TaskOptions};
 use wie_core_jvm::JvmCore;
 
 pub struct SktApp {
@@ -59,8 +59,9 @@ impl App for SktApp {
         let jar = self.jar.clone();
         let main_class_name = self.main_class_name.clone();
 
-        self.system
-            .spawn(move || async move { Self::do_start(&mut system, jar, main_class_name).await });
+        self.system.spawn_with(TaskOptions::new("main").critical(), move || async move {
+            Self::do_start(&mut system, jar, main_class_name).await
+        });
 
         Ok(())
     }
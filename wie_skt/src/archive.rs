@@ -2,14 +2,13 @@ use alloc::{
     borrow::ToOwned,
     boxed::Box,
     collections::BTreeMap,
-    str,
     string::{String, ToString},
     vec::Vec,
 };
 
 use anyhow::Context;
 
-use wie_backend::{App, Archive, Platform, System};
+use wie_backend::{App, Archive, Carrier, DeviceProfile, Platform, System, TextEncoding};
 
 use crate::app::SktApp;
 
@@ -18,6 +17,7 @@ pub struct SktArchive {
     id: String,
     main_class_name: Option<String>,
     additional_files: BTreeMap<String, Vec<u8>>,
+    encoding: TextEncoding,
 }
 
 impl SktArchive {
@@ -29,16 +29,21 @@ impl SktArchive {
         jar.starts_with(b"\x20\x00\x00\x00\x00\x00\x00\x00")
     }
 
-    pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>) -> anyhow::Result<Self> {
+    // `encoding` overrides the one detected from the descriptor
+    pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>, encoding: Option<TextEncoding>) -> anyhow::Result<Self> {
         let msd_file = files.iter().find(|x| x.0.ends_with(".msd")).unwrap();
-        let msd = SktMsd::parse(msd_file.0, msd_file.1);
+        let encoding = encoding.unwrap_or_else(|| TextEncoding::detect(msd_file.1, TextEncoding::EucKr));
+        let msd = SktMsd::parse(msd_file.0, msd_file.1, encoding);
 
-        tracing::info!("Loading app {}, mclass {}", msd.id, msd.main_class);
+        tracing::info!("Loading app {}, mclass {}, name {}", msd.id, msd.main_class, msd.name);
 
         let jar_name = msd_file.0.replace(".msd", ".jar");
         let jar = files.remove(&jar_name).context("Invalid format")?;
 
-        Ok(Self::from_jar(jar, &msd.id, Some(msd.main_class), files))
+        Ok(Self {
+            encoding,
+            ..Self::from_jar(jar, &msd.id, Some(msd.main_class), files)
+        })
     }
 
     pub fn from_jar(data: Vec<u8>, id: &str, main_class_name: Option<String>, additional_files: BTreeMap<String, Vec<u8>>) -> Self {
@@ -47,6 +52,7 @@ impl SktArchive {
             id: id.into(),
             main_class_name,
             additional_files,
+            encoding: TextEncoding::EucKr,
         }
     }
 }
@@ -56,8 +62,21 @@ impl Archive for SktArchive {
         self.id.to_owned()
     }
 
+    fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+    }
+
+    fn device_profile(&self) -> DeviceProfile {
+        DeviceProfile::new(Carrier::Skt)
+    }
+
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
         let system = System::new(platform, Box::new(()));
+        system.set_encoding(self.encoding);
 
         for (filename, data) in self.additional_files {
             system.resource_mut().add(&filename, data)
@@ -70,26 +89,30 @@ impl Archive for SktArchive {
 struct SktMsd {
     id: String,
     main_class: String,
+    name: String,
 }
 
 impl SktMsd {
-    pub fn parse(filename: &str, data: &[u8]) -> Self {
+    pub fn parse(filename: &str, data: &[u8], encoding: TextEncoding) -> Self {
         let mut main_class = String::new();
         let mut id = filename[..filename.find('.').unwrap()].into();
+        let mut name = String::new();
 
-        let mut lines = data.split(|x| *x == b'\n');
+        let data = encoding.decode(data);
 
-        for line in &mut lines {
-            if line.starts_with(b"MIDlet-1:") {
-                let value = line[10..].split(|x| *x == b',').collect::<Vec<_>>();
-                main_class = str::from_utf8(value[2]).unwrap().trim().to_string();
+        for line in data.split('\n') {
+            if let Some(x) = line.strip_prefix("MIDlet-1:") {
+                let value = x.split(',').collect::<Vec<_>>();
+                main_class = value[2].trim().to_string();
+            }
+            if let Some(x) = line.strip_prefix("DD-ProgName:") {
+                id = x.trim().to_string();
             }
-            if line.starts_with(b"DD-ProgName") {
-                id = str::from_utf8(&line[12..]).unwrap().trim().to_string();
+            if let Some(x) = line.strip_prefix("MIDlet-Name:") {
+                name = x.trim().to_string();
             }
-            // TODO load name, it's in euc-kr..
         }
 
-        Self { id, main_class }
+        Self { id, main_class, name }
     }
 }
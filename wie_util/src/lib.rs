@@ -1,11 +1,15 @@
 #![no_std]
 extern crate alloc;
 
+mod snapshot;
+
 use alloc::{string::String, vec::Vec};
 use core::{mem::size_of, result};
 
 use bytemuck::{bytes_of, from_bytes, AnyBitPattern, NoUninit};
 
+pub use self::snapshot::{SnapshotError, SnapshotReader, SnapshotResult, SnapshotWriter};
+
 pub fn round_up(num_to_round: usize, multiple: usize) -> usize {
     if multiple == 0 {
         return num_to_round;
@@ -51,6 +55,15 @@ where
 }
 
 pub fn read_null_terminated_string<R>(reader: &R, address: u32) -> Result<String>
+where
+    R: ?Sized + ByteRead,
+{
+    let result = read_null_terminated_bytes(reader, address)?;
+
+    Ok(String::from_utf8(result).unwrap())
+}
+
+pub fn read_null_terminated_bytes<R>(reader: &R, address: u32) -> Result<Vec<u8>>
 where
     R: ?Sized + ByteRead,
 {
@@ -71,7 +84,7 @@ where
 
     // tracing::trace!("Read address: {:#x}, data: {:02x?}", address, result);
 
-    Ok(String::from_utf8(result).unwrap())
+    Ok(result)
 }
 
 pub fn write_null_terminated_string<W>(writer: &mut W, address: u32, string: &str) -> Result<()>
@@ -0,0 +1,96 @@
+use alloc::vec::Vec;
+use core::result;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    UnexpectedEnd,
+    InvalidData,
+}
+
+impl From<SnapshotError> for anyhow::Error {
+    fn from(e: SnapshotError) -> Self {
+        anyhow::anyhow!("Invalid snapshot: {:?}", e)
+    }
+}
+
+pub type SnapshotResult<T> = result::Result<T, SnapshotError>;
+
+// Little-endian encoder for save states
+#[derive(Default)]
+pub struct SnapshotWriter {
+    data: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, data: &[u8]) {
+        self.write_u32(data.len() as _);
+        self.data.extend_from_slice(data);
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub struct SnapshotReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    pub fn read_u8(&mut self) -> SnapshotResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_u32(&mut self) -> SnapshotResult<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_u64(&mut self) -> SnapshotResult<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn read_bytes(&mut self) -> SnapshotResult<&'a [u8]> {
+        let length = self.read_u32()?;
+
+        self.take(length as _)
+    }
+
+    // trailing data means the snapshot was written by something else
+    pub fn finish(self) -> SnapshotResult<()> {
+        if self.offset != self.data.len() {
+            return Err(SnapshotError::InvalidData);
+        }
+
+        Ok(())
+    }
+
+    fn take(&mut self, size: usize) -> SnapshotResult<&'a [u8]> {
+        let end = self.offset.checked_add(size).ok_or(SnapshotError::UnexpectedEnd)?;
+        let result = self.data.get(self.offset..end).ok_or(SnapshotError::UnexpectedEnd)?;
+
+        self.offset = end;
+
+        Ok(result)
+    }
+}
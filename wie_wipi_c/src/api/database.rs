@@ -3,7 +3,7 @@ use core::mem::size_of;
 
 use bytemuck::{Pod, Zeroable};
 
-use wie_backend::Database;
+use wie_backend::{Database, DatabaseError, DatabaseResult};
 use wie_util::{read_generic, write_generic};
 
 use crate::{context::WIPICContext, method::MethodImpl, WIPICError, WIPICMethodBody, WIPICResult, WIPICWord};
@@ -26,6 +26,14 @@ async fn open_database(context: &mut dyn WIPICContext, name: String, record_size
     let name_bytes = name.as_bytes();
     let mut handle = DatabaseHandle { name: [0; 32] };
 
+    // keep the terminating zero
+    if name_bytes.is_empty() || name_bytes.len() >= handle.name.len() {
+        return Ok(-1); // M_E_ERROR
+    }
+    if let Err(x) = context.system().platform().database_repository().open(&name, create != 0) {
+        return Ok(error_code(x));
+    }
+
     handle.name[..name_bytes.len()].copy_from_slice(name_bytes);
 
     let ptr_handle = context.alloc_raw(size_of::<DatabaseHandle>() as _)?;
@@ -47,8 +55,10 @@ async fn close_database(context: &mut dyn WIPICContext, db_id: i32) -> WIPICResu
 async fn list_record(context: &mut dyn WIPICContext, db_id: i32, buf_ptr: WIPICWord, buf_len: WIPICWord) -> WIPICResult<i32> {
     tracing::debug!("MC_dbListRecords({:#x}, {:#x}, {})", db_id, buf_ptr, buf_len);
 
-    let db = get_database_from_db_id(context, db_id);
-    let ids = db.get_record_ids();
+    let ids = match get_database_from_db_id(context, db_id)?.and_then(|x| x.get_record_ids()) {
+        Ok(x) => x,
+        Err(x) => return Ok(error_code(x)),
+    };
 
     let mut cursor = 0;
     for &id in &ids {
@@ -63,58 +73,66 @@ async fn write_record_single(context: &mut dyn WIPICContext, db_id: i32, buf_ptr
     tracing::debug!("MC_db_write_record_single({:#x}, {:#x}, {})", db_id, buf_ptr, buf_len);
 
     let data = context.read_bytes(buf_ptr, buf_len)?;
-    let mut db = get_database_from_db_id(context, db_id);
 
-    db.set(1, &data);
+    match get_database_from_db_id(context, db_id)?.and_then(|mut x| x.set(1, &data)) {
+        Ok(()) => Ok(1),
+        Err(x) => Ok(error_code(x)),
+    }
+}
+
+async fn update_record(context: &mut dyn WIPICContext, db_id: i32, rec_id: i32, buf_ptr: WIPICWord, buf_len: WIPICWord) -> WIPICResult<i32> {
+    tracing::debug!("MC_dbUpdateRecord({:#x}, {}, {:#x}, {})", db_id, rec_id, buf_ptr, buf_len);
 
-    Ok(1)
+    let data = context.read_bytes(buf_ptr, buf_len)?;
+
+    // updating doesn't create records
+    let result = get_database_from_db_id(context, db_id)?.and_then(|mut x| {
+        x.get_record_size(rec_id as _)?;
+        x.set(rec_id as _, &data)
+    });
+
+    match result {
+        Ok(()) => Ok(0), // success
+        Err(x) => Ok(error_code(x)),
+    }
 }
 
 async fn delete_record(context: &mut dyn WIPICContext, db_id: i32, rec_id: i32) -> WIPICResult<i32> {
     tracing::debug!("MC_dbDeleteRecord({:#x}, {})", db_id, rec_id);
 
-    let mut db = get_database_from_db_id(context, db_id);
-
-    let result = db.delete(rec_id as _);
-
-    if result {
-        Ok(0) // success
-    } else {
-        Ok(-22) // M_E_BADRECID
+    match get_database_from_db_id(context, db_id)?.and_then(|mut x| x.delete(rec_id as _)) {
+        Ok(()) => Ok(0), // success
+        Err(x) => Ok(error_code(x)),
     }
 }
 
 async fn read_record_single(context: &mut dyn WIPICContext, db_id: i32, buf_ptr: WIPICWord, buf_len: WIPICWord) -> WIPICResult<i32> {
     tracing::debug!("MC_db_read_record_single({:#x}, {:#x}, {})", db_id, buf_ptr, buf_len);
 
-    let db = get_database_from_db_id(context, db_id);
-
-    if let Some(x) = db.get(1) {
-        if buf_len < x.len() as _ {
-            return Ok(-18); // M_E_SHORTBUF
-        }
-        context.write_bytes(buf_ptr, &x)?;
-
-        Ok(0)
-    } else {
-        Ok(-22) // M_E_BADRECID
-    }
+    read_record(context, db_id, 1, buf_ptr, buf_len)
 }
 
 async fn select_record(context: &mut dyn WIPICContext, db_id: i32, rec_id: i32, buf_ptr: WIPICWord, buf_len: WIPICWord) -> WIPICResult<i32> {
     tracing::debug!("MC_dbSelectRecord({:#x}, {}, {:#x}, {})", db_id, rec_id, buf_ptr, buf_len);
 
-    let db = get_database_from_db_id(context, db_id);
+    read_record(context, db_id, rec_id, buf_ptr, buf_len)
+}
+
+async fn get_number_of_records(context: &mut dyn WIPICContext, db_id: i32) -> WIPICResult<i32> {
+    tracing::debug!("MC_dbGetNumberOfRecords({:#x})", db_id);
+
+    match get_database_from_db_id(context, db_id)?.and_then(|x| x.get_record_ids()) {
+        Ok(x) => Ok(x.len() as _),
+        Err(x) => Ok(error_code(x)),
+    }
+}
 
-    if let Some(x) = db.get(rec_id as _) {
-        if buf_len < x.len() as _ {
-            return Ok(-18); // M_E_SHORTBUF
-        }
-        context.write_bytes(buf_ptr, &x)?;
+async fn get_record_size(context: &mut dyn WIPICContext, db_id: i32, rec_id: i32) -> WIPICResult<i32> {
+    tracing::debug!("MC_dbGetRecordSize({:#x}, {})", db_id, rec_id);
 
-        Ok(0)
-    } else {
-        Ok(-22) // M_E_BADRECID
+    match get_database_from_db_id(context, db_id)?.and_then(|x| x.get_record_size(rec_id as _)) {
+        Ok(x) => Ok(x as _),
+        Err(x) => Ok(error_code(x)),
     }
 }
 
@@ -124,13 +142,38 @@ async fn unk16(_context: &mut dyn WIPICContext) -> WIPICResult<i32> {
     Ok(1)
 }
 
-fn get_database_from_db_id(context: &mut dyn WIPICContext, db_id: i32) -> Box<dyn Database> {
-    let handle: DatabaseHandle = read_generic(context, db_id as _).unwrap();
+fn read_record(context: &mut dyn WIPICContext, db_id: i32, rec_id: i32, buf_ptr: WIPICWord, buf_len: WIPICWord) -> WIPICResult<i32> {
+    let data = match get_database_from_db_id(context, db_id)?.and_then(|x| x.get(rec_id as _)) {
+        Ok(x) => x,
+        Err(x) => return Ok(error_code(x)),
+    };
+
+    if buf_len < data.len() as _ {
+        return Ok(-18); // M_E_SHORTBUF
+    }
+    context.write_bytes(buf_ptr, &data)?;
+
+    Ok(0)
+}
+
+fn error_code(error: DatabaseError) -> i32 {
+    tracing::warn!("Database error {:?}", error);
+
+    match error {
+        DatabaseError::InvalidRecordId => -22, // M_E_BADRECID
+        _ => -1,                               // M_E_ERROR
+    }
+}
+
+fn get_database_from_db_id(context: &mut dyn WIPICContext, db_id: i32) -> WIPICResult<DatabaseResult<Box<dyn Database>>> {
+    let handle: DatabaseHandle = read_generic(context, db_id as _)?;
 
     let name_length = handle.name.iter().position(|&c| c == 0).unwrap_or(handle.name.len());
-    let db_name = str::from_utf8(&handle.name[..name_length]).unwrap();
+    let Ok(db_name) = str::from_utf8(&handle.name[..name_length]) else {
+        return Ok(Err(DatabaseError::InvalidName));
+    };
 
-    context.system().platform().database_repository().open(db_name)
+    Ok(context.system().platform().database_repository().open(db_name, false))
 }
 
 pub fn get_database_method_table() -> Vec<WIPICMethodBody> {
@@ -140,13 +183,13 @@ pub fn get_database_method_table() -> Vec<WIPICMethodBody> {
         write_record_single.into_body(),
         close_database.into_body(),
         select_record.into_body(),
-        gen_stub(5, "MC_dbUpdateRecord"),
+        update_record.into_body(),
         delete_record.into_body(),
         list_record.into_body(),
         gen_stub(8, "MC_dbSortRecords"),
         gen_stub(9, "MC_dbGetAccessMode"),
-        gen_stub(10, "MC_dbGetNumberOfRecords"),
-        gen_stub(11, "MC_dbGetRecordSize"),
+        get_number_of_records.into_body(),
+        get_record_size.into_body(),
         gen_stub(12, "MC_dbListDataBases"),
         gen_stub(13, ""),
         gen_stub(14, ""),
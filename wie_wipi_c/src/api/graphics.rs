@@ -2,12 +2,18 @@ mod framebuffer;
 mod grp_context;
 mod image;
 
-use alloc::{vec, vec::Vec};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
 use core::mem::size_of;
 
 use bytemuck::Zeroable;
 
-use wie_backend::canvas::{Color, PixelType, Rgb8Pixel};
+use wie_backend::canvas::{
+    encode_image, ArgbPixel, Color, Font, Image, ImageBuffer, ImageFormat, PixelType, Rect, Rgb565Pixel, Rgb8Pixel, VecImageBuffer,
+};
 use wie_util::{read_generic, write_generic};
 
 use crate::{context::WIPICContext, method::MethodImpl, WIPICError, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord};
@@ -20,12 +26,28 @@ use self::{
 
 const FRAMEBUFFER_DEPTH: u32 = 16; // XXX hardcode to 16bpp as some game requires 16bpp framebuffer
 
+// fonts are handed out as their face, size and style or-ed together, tagged so a valid handle is never null
+const FONT_HANDLE_TAG: WIPICWord = 0x1000;
+
+// image types accepted by MC_grpEncodeImage
+const ENCODE_TYPE_BMP: WIPICWord = 0;
+const ENCODE_TYPE_PNG: WIPICWord = 1;
+
 fn gen_stub(_id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(WIPICError::Unimplemented(name.into())) };
 
     body.into_body()
 }
 
+// graphics context is optional for image copies
+fn read_graphics_context(context: &mut dyn WIPICContext, p_gctx: WIPICWord) -> WIPICResult<WIPICGraphicsContext> {
+    if p_gctx == 0 {
+        Ok(WIPICGraphicsContext::zeroed())
+    } else {
+        Ok(read_generic(context, p_gctx)?)
+    }
+}
+
 async fn get_screen_framebuffer(context: &mut dyn WIPICContext, a0: WIPICWord) -> WIPICResult<WIPICMemoryId> {
     tracing::debug!("MC_grpGetScreenFrameBuffer({:#x})", a0);
 
@@ -90,8 +112,11 @@ async fn set_context(context: &mut dyn WIPICContext, p_grp_ctx: WIPICWord, op: W
         }
         _ => {
             tracing::warn!("MC_grpSetContext({:#x}, {:?}, {:#x}): ignoring invalid op", p_grp_ctx, op, pv);
+
+            return Ok(());
         }
     }
+    grp_ctx.set(op);
     write_generic(context, p_grp_ctx, grp_ctx)?;
 
     Ok(())
@@ -104,7 +129,8 @@ async fn put_pixel(context: &mut dyn WIPICContext, dst_fb: WIPICMemoryId, x: i32
     let gctx: WIPICGraphicsContext = read_generic(context, p_gctx)?;
 
     let mut canvas = framebuffer.canvas(context)?;
-    canvas.put_pixel(x as _, y as _, Rgb8Pixel::to_color(gctx.fgpxl));
+    gctx.apply(&mut **canvas);
+    canvas.put_pixel(x, y, Rgb8Pixel::to_color(gctx.fgpxl));
     Ok(())
 }
 
@@ -114,10 +140,188 @@ async fn fill_rect(context: &mut dyn WIPICContext, dst_fb: WIPICMemoryId, x: i32
     let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(dst_fb)?)?;
     let gctx: WIPICGraphicsContext = read_generic(context, p_gctx)?;
     let mut canvas = framebuffer.canvas(context)?;
-    canvas.fill_rect(x as _, y as _, w as _, h as _, Rgb8Pixel::to_color(gctx.fgpxl));
+    gctx.apply(&mut **canvas);
+    canvas.fill_rect(x, y, w, h, Rgb8Pixel::to_color(gctx.fgpxl));
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn draw_arc(
+    context: &mut dyn WIPICContext,
+    dst_fb: WIPICMemoryId,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    start_angle: i32,
+    arc_angle: i32,
+    p_gctx: WIPICWord,
+) -> WIPICResult<()> {
+    tracing::debug!(
+        "MC_grpDrawArc({:#x}, {}, {}, {}, {}, {}, {}, {:#x})",
+        dst_fb.0,
+        x,
+        y,
+        w,
+        h,
+        start_angle,
+        arc_angle,
+        p_gctx
+    );
+
+    let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(dst_fb)?)?;
+    let gctx: WIPICGraphicsContext = read_generic(context, p_gctx)?;
+    let mut canvas = framebuffer.canvas(context)?;
+    gctx.apply(&mut **canvas);
+    canvas.draw_arc(x, y, w, h, start_angle, arc_angle, Rgb8Pixel::to_color(gctx.fgpxl));
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn fill_arc(
+    context: &mut dyn WIPICContext,
+    dst_fb: WIPICMemoryId,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    start_angle: i32,
+    arc_angle: i32,
+    p_gctx: WIPICWord,
+) -> WIPICResult<()> {
+    tracing::debug!(
+        "MC_grpFillArc({:#x}, {}, {}, {}, {}, {}, {}, {:#x})",
+        dst_fb.0,
+        x,
+        y,
+        w,
+        h,
+        start_angle,
+        arc_angle,
+        p_gctx
+    );
+
+    let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(dst_fb)?)?;
+    let gctx: WIPICGraphicsContext = read_generic(context, p_gctx)?;
+    let mut canvas = framebuffer.canvas(context)?;
+    gctx.apply(&mut **canvas);
+    canvas.fill_arc(x, y, w, h, start_angle, arc_angle, Rgb8Pixel::to_color(gctx.fgpxl));
+    Ok(())
+}
+
+fn read_points(context: &mut dyn WIPICContext, x_points: WIPICWord, y_points: WIPICWord, count: i32) -> WIPICResult<Vec<(i32, i32)>> {
+    (0..count.max(0) as WIPICWord)
+        .map(|i| {
+            let x: i32 = read_generic(context, x_points + i * 4)?;
+            let y: i32 = read_generic(context, y_points + i * 4)?;
+
+            Ok((x, y))
+        })
+        .collect()
+}
+
+async fn fill_polygon(
+    context: &mut dyn WIPICContext,
+    dst_fb: WIPICMemoryId,
+    x_points: WIPICWord,
+    y_points: WIPICWord,
+    count: i32,
+    p_gctx: WIPICWord,
+) -> WIPICResult<()> {
+    tracing::debug!(
+        "MC_grpFillPolygon({:#x}, {:#x}, {:#x}, {}, {:#x})",
+        dst_fb.0,
+        x_points,
+        y_points,
+        count,
+        p_gctx
+    );
+
+    let points = read_points(context, x_points, y_points, count)?;
+
+    let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(dst_fb)?)?;
+    let gctx: WIPICGraphicsContext = read_generic(context, p_gctx)?;
+    let mut canvas = framebuffer.canvas(context)?;
+    gctx.apply(&mut **canvas);
+    canvas.fill_polygon(&points, Rgb8Pixel::to_color(gctx.fgpxl));
     Ok(())
 }
 
+async fn draw_polygon(
+    context: &mut dyn WIPICContext,
+    dst_fb: WIPICMemoryId,
+    x_points: WIPICWord,
+    y_points: WIPICWord,
+    count: i32,
+    p_gctx: WIPICWord,
+) -> WIPICResult<()> {
+    tracing::debug!(
+        "MC_grpDrawPolygon({:#x}, {:#x}, {:#x}, {}, {:#x})",
+        dst_fb.0,
+        x_points,
+        y_points,
+        count,
+        p_gctx
+    );
+
+    let points = read_points(context, x_points, y_points, count)?;
+
+    let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(dst_fb)?)?;
+    let gctx: WIPICGraphicsContext = read_generic(context, p_gctx)?;
+    let mut canvas = framebuffer.canvas(context)?;
+    gctx.apply(&mut **canvas);
+    canvas.draw_polygon(&points, Rgb8Pixel::to_color(gctx.fgpxl));
+    Ok(())
+}
+
+fn font_from_handle(handle: WIPICWord) -> Font {
+    Font::from_bits(handle & 0x60, handle & 0x07, handle & 0x18)
+}
+
+async fn get_font(_context: &mut dyn WIPICContext, face: i32, size: i32, style: i32) -> WIPICResult<WIPICWord> {
+    tracing::debug!("MC_grpGetFont({}, {}, {})", face, size, style);
+
+    let font = Font::from_bits(face as _, style as _, size as _);
+
+    Ok(FONT_HANDLE_TAG | font.face as WIPICWord | font.size as WIPICWord | font.style.bits())
+}
+
+async fn get_font_height(_context: &mut dyn WIPICContext, font: WIPICWord) -> WIPICResult<i32> {
+    tracing::debug!("MC_grpGetFontHeight({:#x})", font);
+
+    Ok(font_from_handle(font).height())
+}
+
+async fn get_font_ascent(_context: &mut dyn WIPICContext, font: WIPICWord) -> WIPICResult<i32> {
+    tracing::debug!("MC_grpGetFontAscent({:#x})", font);
+
+    Ok(font_from_handle(font).ascent())
+}
+
+async fn get_font_descent(_context: &mut dyn WIPICContext, font: WIPICWord) -> WIPICResult<i32> {
+    tracing::debug!("MC_grpGetFontDescent({:#x})", font);
+
+    Ok(font_from_handle(font).descent())
+}
+
+async fn get_string_width(context: &mut dyn WIPICContext, font: WIPICWord, ptr_string: WIPICWord, len: i32) -> WIPICResult<i32> {
+    tracing::debug!("MC_grpGetStringWidth({:#x}, {:#x}, {})", font, ptr_string, len);
+
+    let bytes = context.read_bytes(ptr_string, len.max(0) as _)?;
+    let string = context.system().decode_str(&bytes);
+
+    Ok(font_from_handle(font).string_width(&string))
+}
+
+async fn get_unicode_string_width(context: &mut dyn WIPICContext, font: WIPICWord, ptr_string: WIPICWord, len: i32) -> WIPICResult<i32> {
+    tracing::debug!("MC_grpGetUnicodeStringWidth({:#x}, {:#x}, {})", font, ptr_string, len);
+
+    let bytes = context.read_bytes(ptr_string, len.max(0) as WIPICWord * 2)?;
+    let string = String::from_utf16_lossy(&bytes.chunks_exact(2).map(|x| u16::from_le_bytes([x[0], x[1]])).collect::<Vec<_>>());
+
+    Ok(font_from_handle(font).string_width(&string))
+}
+
 async fn create_image(
     context: &mut dyn WIPICContext,
     ptr_image: WIPICWord,
@@ -136,6 +340,97 @@ async fn create_image(
     Ok(1) // MC_GRP_IMAGE_DONE
 }
 
+async fn decode_next_image(context: &mut dyn WIPICContext, image: WIPICMemoryId) -> WIPICResult<WIPICWord> {
+    tracing::debug!("MC_grpDecodeNextImage({:#x})", image.0);
+
+    let ptr_image = context.data_ptr(image)?;
+    let mut wipic_image: WIPICImage = read_generic(context, ptr_image)?;
+
+    wipic_image.next_frame(context)?;
+    write_generic(context, ptr_image, wipic_image)?;
+
+    Ok(1) // MC_GRP_IMAGE_DONE
+}
+
+fn crop<T: PixelType + 'static>(image: &dyn Image, area: &Rect) -> VecImageBuffer<T> {
+    let mut result = VecImageBuffer::<T>::new(area.width as _, area.height as _);
+    for y in 0..area.height {
+        for x in 0..area.width {
+            result.put_pixel(x as _, y as _, image.get_pixel((area.x + x) as _, (area.y + y) as _));
+        }
+    }
+
+    result
+}
+
+// encodes a region of the framebuffer into a newly allocated buffer, returns the encoded length
+#[allow(clippy::too_many_arguments)]
+async fn encode_framebuffer(
+    context: &mut dyn WIPICContext,
+    framebuffer: WIPICMemoryId,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    image_type: WIPICWord,
+    ptr_buf: WIPICWord,
+) -> WIPICResult<i32> {
+    tracing::debug!(
+        "MC_grpEncodeImage({:#x}, {}, {}, {}, {}, {}, {:#x})",
+        framebuffer.0,
+        x,
+        y,
+        w,
+        h,
+        image_type,
+        ptr_buf
+    );
+
+    let format = match image_type {
+        ENCODE_TYPE_BMP => ImageFormat::Bmp,
+        ENCODE_TYPE_PNG => ImageFormat::Png,
+        _ => return Ok(-9), // M_E_INVALID
+    };
+
+    let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(framebuffer)?)?;
+    let image = framebuffer.image(context)?;
+
+    let area = Rect::new(x, y, w, h).intersect(&Rect::new(0, 0, image.width() as _, image.height() as _));
+    if area.is_empty() {
+        return Ok(-9); // M_E_INVALID
+    }
+
+    let data = match framebuffer.bpp {
+        16 => encode_image(&crop::<Rgb565Pixel>(&*image, &area), format),
+        _ => encode_image(&crop::<ArgbPixel>(&*image, &area), format),
+    }
+    .map_err(|x| WIPICError::BackendError(x.to_string()))?;
+
+    let memory = context.alloc(data.len() as _)?;
+    context.write_bytes(context.data_ptr(memory)?, &data)?;
+    write_generic(context, ptr_buf, memory)?;
+
+    Ok(data.len() as _)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn encode_image_ex(
+    context: &mut dyn WIPICContext,
+    framebuffer: WIPICMemoryId,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    image_type: WIPICWord,
+    ptr_buf: WIPICWord,
+    flags: WIPICWord,
+) -> WIPICResult<i32> {
+    tracing::debug!("OEMC_grpEncodeImageEx(.., {:#x})", flags);
+
+    // extra flags select encoder quality on handsets, which doesn't apply to lossless formats
+    encode_framebuffer(context, framebuffer, x, y, w, h, image_type, ptr_buf).await
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn draw_image(
     context: &mut dyn WIPICContext,
@@ -165,41 +460,33 @@ async fn draw_image(
     let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(framebuffer)?)?;
     let image: WIPICImage = read_generic(context, context.data_ptr(image)?)?;
 
+    let gctx = read_graphics_context(context, graphics_context)?;
+
     let src_image = image.img.image(context)?;
     let mut canvas = framebuffer.canvas(context)?;
+    gctx.apply(&mut **canvas);
 
-    canvas.draw(dx as _, dy as _, w as _, h as _, &*src_image, sx as _, sy as _);
+    canvas.draw(dx, dy, w, h, &*src_image, sx, sy);
 
     Ok(())
 }
 
-async fn flush(
-    context: &mut dyn WIPICContext,
-    a0: WIPICWord,
-    framebuffer: WIPICMemoryId,
-    a2: WIPICWord,
-    a3: WIPICWord,
-    a4: WIPICWord,
-    a5: WIPICWord,
-) -> WIPICResult<()> {
-    tracing::debug!(
-        "MC_grpFlushLcd({:#x}, {:#x}, {:#x}, {:#x}, {:#x}, {:#x})",
-        a0,
-        framebuffer.0,
-        a2,
-        a3,
-        a4,
-        a5
-    );
+async fn flush(context: &mut dyn WIPICContext, a0: WIPICWord, framebuffer: WIPICMemoryId, x: i32, y: i32, w: i32, h: i32) -> WIPICResult<()> {
+    tracing::debug!("MC_grpFlushLcd({:#x}, {:#x}, {}, {}, {}, {})", a0, framebuffer.0, x, y, w, h);
 
     let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(framebuffer)?)?;
 
+    let area = Rect::new(x, y, w, h).intersect(&Rect::new(0, 0, framebuffer.width as _, framebuffer.height as _));
+    if area.is_empty() {
+        return Ok(());
+    }
+
     let src_canvas = framebuffer.image(context)?;
 
     let mut platform = context.system().platform();
     let screen = platform.screen();
 
-    screen.paint(&*src_canvas);
+    screen.paint(&*src_canvas, &area);
 
     Ok(())
 }
@@ -226,11 +513,12 @@ async fn get_display_info(context: &mut dyn WIPICContext, reserved: WIPICWord, o
     assert_eq!(reserved, 0);
 
     let mut platform = context.system().platform();
+    let depth = platform.device_profile().color_depth;
     let screen = platform.screen();
 
     let info = WIPICDisplayInfo {
         bpp: FRAMEBUFFER_DEPTH,
-        depth: 16,
+        depth,
         width: screen.width(),
         height: screen.height(),
         bpl: 2 * screen.width(),
@@ -267,10 +555,13 @@ async fn copy_area(
 
     let framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(dst)?)?;
 
+    let gctx = read_graphics_context(context, pgc)?;
+
     let image = framebuffer.image(context)?;
     let mut canvas = framebuffer.canvas(context)?;
+    gctx.apply(&mut **canvas);
 
-    canvas.draw(dx as _, dy as _, w as _, h as _, &*image, x as _, y as _);
+    canvas.draw(dx, dy, w, h, &*image, x, y);
 
     Ok(())
 }
@@ -315,10 +606,13 @@ async fn copy_frame_buffer(
     let src_framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(src)?)?;
     let dst_framebuffer: WIPICFramebuffer = read_generic(context, context.data_ptr(dst)?)?;
 
+    let gctx = read_graphics_context(context, pgc)?;
+
     let src_image = src_framebuffer.image(context)?;
     let mut dst_canvas = dst_framebuffer.canvas(context)?;
+    gctx.apply(&mut **dst_canvas);
 
-    dst_canvas.draw(dx as _, dy as _, w as _, h as _, &*src_image, sx as _, sy as _);
+    dst_canvas.draw(dx, dy, w, h, &*src_image, sx, sy);
 
     Ok(())
 }
@@ -340,8 +634,8 @@ pub fn get_graphics_method_table() -> Vec<WIPICMethodBody> {
         copy_frame_buffer.into_body(),
         draw_image.into_body(),
         copy_area.into_body(),
-        gen_stub(15, "MC_grpDrawArc"),
-        gen_stub(16, "MC_grpFillArc"),
+        draw_arc.into_body(),
+        fill_arc.into_body(),
         gen_stub(17, "MC_grpDrawString"),
         gen_stub(18, "MC_grpDrawUnicodeString"),
         gen_stub(19, "MC_grpGetRGBPixels"),
@@ -351,24 +645,24 @@ pub fn get_graphics_method_table() -> Vec<WIPICMethodBody> {
         gen_stub(23, "MC_grpGetRGBFromPixel"),
         get_display_info.into_body(),
         gen_stub(25, "MC_grpRepaint"),
-        gen_stub(26, "MC_grpGetFont"),
-        gen_stub(27, "MC_grpGetFontHeight"),
-        gen_stub(28, "MC_grpGetFontAscent"),
-        gen_stub(29, "MC_grpGetFontDescent"),
-        gen_stub(30, "MC_grpGetStringWidth"),
-        gen_stub(31, "MC_grpGetUnicodeStringWidth"),
+        get_font.into_body(),
+        get_font_height.into_body(),
+        get_font_ascent.into_body(),
+        get_font_descent.into_body(),
+        get_string_width.into_body(),
+        get_unicode_string_width.into_body(),
         create_image.into_body(),
         gen_stub(33, "MC_grpDestroyImage"),
-        gen_stub(34, "MC_grpDecodeNextImage"),
-        gen_stub(35, "MC_grpEncodeImage"),
+        decode_next_image.into_body(),
+        encode_framebuffer.into_body(),
         gen_stub(36, "MC_grpPostEvent"),
         gen_stub(37, "MC_imHandleInput"),
         gen_stub(38, "MC_imSetCurrentMode"),
         gen_stub(39, "MC_imGetCurrentMode"),
         gen_stub(40, "MC_imGetSupportModeCount"),
         gen_stub(41, "MC_imGetSupportedModes"),
-        gen_stub(42, "MC_grpFillPolygon"),
-        gen_stub(43, "MC_grpDrawPolygon"),
+        fill_polygon.into_body(),
+        draw_polygon.into_body(),
         gen_stub(44, "OEMC_grpShowAnnunciator"),
         gen_stub(45, "OEMC_grpGetAnnunciatorInfo"),
         gen_stub(46, "OEMC_grpSetAnnunciatorIcon"),
@@ -383,7 +677,7 @@ pub fn get_graphics_method_table() -> Vec<WIPICMethodBody> {
         gen_stub(55, "OEMC_grpGetFontInfo"),
         gen_stub(56, "OEMC_grpSetFontHelpLine"),
         gen_stub(57, "OEMC_grpGetFontHelpLine"),
-        gen_stub(58, "OEMC_grpEncodeImageEx"),
+        encode_image_ex.into_body(),
         gen_stub(59, "OEMC_grpGetImageInfo"),
         gen_stub(60, ""),
         gen_stub(61, ""),
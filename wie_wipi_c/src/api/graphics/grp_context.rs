@@ -2,14 +2,17 @@ use core::mem;
 
 use bytemuck::{Pod, Zeroable};
 
+use wie_backend::canvas::Canvas;
+
 use crate::{method::TypeConverter, WIPICContext, WIPICWord};
 
 /// _MC_GrpContext
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct WIPICGraphicsContext {
+    /// bit per WIPICGraphicsContextIdx which has been set
     pub mask: WIPICWord,
-    /// top-left x, y, bottom-right x, y
+    /// top-left x, y, bottom-right x, y, inclusive
     pub clip: [WIPICWord; 4],
     pub fgpxl: WIPICWord,
     pub bgpxl: WIPICWord,
@@ -24,8 +27,30 @@ pub struct WIPICGraphicsContext {
     pub style: WIPICWord,
 }
 
+impl WIPICGraphicsContext {
+    pub fn is_set(&self, idx: WIPICGraphicsContextIdx) -> bool {
+        self.mask & (1 << idx as u32) != 0
+    }
+
+    pub fn set(&mut self, idx: WIPICGraphicsContextIdx) {
+        self.mask |= 1 << idx as u32;
+    }
+
+    // clip is given in framebuffer coordinates, offset moves everything drawn afterwards
+    pub fn apply(&self, canvas: &mut dyn Canvas) {
+        if self.is_set(WIPICGraphicsContextIdx::ClipIdx) {
+            let [x1, y1, x2, y2] = self.clip.map(|x| x as i32);
+
+            canvas.set_clip(x1, y1, x2 - x1 + 1, y2 - y1 + 1);
+        }
+        if self.is_set(WIPICGraphicsContextIdx::OffsetIdx) {
+            canvas.translate(self.offset[0] as i32, self.offset[1] as i32);
+        }
+    }
+}
+
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 #[allow(dead_code)]
 pub enum WIPICGraphicsContextIdx {
     ClipIdx = 0,
@@ -1,8 +1,8 @@
-use alloc::string::ToString;
+use alloc::{rc::Rc, string::ToString, vec::Vec};
 
 use bytemuck::{Pod, Zeroable};
 
-use wie_backend::canvas::decode_image;
+use wie_backend::canvas::{decode_image_frames, ImageFrame};
 
 use crate::{context::WIPICContext, WIPICError, WIPICMemoryId, WIPICResult, WIPICWord};
 
@@ -24,23 +24,68 @@ pub struct WIPICImage {
 
 impl WIPICImage {
     pub fn new(context: &mut dyn WIPICContext, buf: WIPICMemoryId, offset: WIPICWord, len: WIPICWord) -> WIPICResult<Self> {
-        let ptr_image_data = context.data_ptr(buf)?;
-        let data = context.read_bytes(ptr_image_data + offset, len)?;
-        let image = decode_image(&data).map_err(|x| WIPICError::BackendError(x.to_string()))?;
+        let frames = Self::decode_frames(context, buf, offset, len)?;
+        let first = &frames[0];
 
-        let img_framebuffer = WIPICFramebuffer::from_image(context, &*image)?;
+        let img_framebuffer = WIPICFramebuffer::from_image(context, &*first.image)?;
         let mask_framebuffer = WIPICFramebuffer::empty();
 
-        Ok(Self {
+        let result = Self {
             img: img_framebuffer,
             mask: mask_framebuffer,
             loop_count: 0,
-            delay: 0,
-            animated: 0,
+            delay: first.delay,
+            animated: (frames.len() > 1) as _,
             buf,
             offset,
             current: 0,
             len,
-        })
+        };
+
+        // source may have been reused for another image, so always replace
+        if frames.len() > 1 {
+            context.system().image_frames().insert(result.frames_key(), frames);
+        }
+
+        Ok(result)
+    }
+
+    // frames are cached on the host keyed by the source, so the image stays a plain struct on the app side
+    pub fn next_frame(&mut self, context: &mut dyn WIPICContext) -> WIPICResult<()> {
+        if self.animated == 0 {
+            return Ok(());
+        }
+
+        let frames = self.frames(context)?;
+        let current = (self.current as usize + 1) % frames.len();
+        let frame = &frames[current];
+
+        self.img.write(context, frame.image.raw())?;
+        self.current = current as _;
+        self.delay = frame.delay;
+
+        Ok(())
+    }
+
+    fn frames_key(&self) -> u64 {
+        ((self.buf.0 as u64) << 32) | self.offset as u64
+    }
+
+    fn frames(&self, context: &mut dyn WIPICContext) -> WIPICResult<Rc<Vec<ImageFrame>>> {
+        let cached = context.system().image_frames().get(self.frames_key());
+        if let Some(x) = cached {
+            return Ok(x);
+        }
+
+        let frames = Self::decode_frames(context, self.buf, self.offset, self.len)?;
+
+        Ok(context.system().image_frames().insert(self.frames_key(), frames))
+    }
+
+    fn decode_frames(context: &mut dyn WIPICContext, buf: WIPICMemoryId, offset: WIPICWord, len: WIPICWord) -> WIPICResult<Vec<ImageFrame>> {
+        let ptr_image_data = context.data_ptr(buf)?;
+        let data = context.read_bytes(ptr_image_data + offset, len)?;
+
+        decode_image_frames(&data).map_err(|x| WIPICError::BackendError(x.to_string()))
     }
 }
@@ -10,10 +10,10 @@ use core::{cell::Ref, iter};
 use bytemuck::{Pod, Zeroable};
 
 use wie_backend::Instant;
-use wie_util::{read_generic, read_null_terminated_string, write_generic, write_null_terminated_string};
+use wie_util::{read_generic, write_generic};
 
 use crate::{
-    context::WIPICContext,
+    context::{read_string, write_string, WIPICContext},
     method::{MethodBody, MethodImpl},
     WIPICError, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord,
 };
@@ -43,10 +43,18 @@ async fn current_time(context: &mut dyn WIPICContext) -> WIPICResult<WIPICWord>
     Ok(context.system().platform().now().raw() as WIPICWord)
 }
 
-async fn get_system_property(_context: &mut dyn WIPICContext, id: String, p_out: WIPICWord, buf_size: WIPICWord) -> WIPICResult<i32> {
-    tracing::warn!("stub MC_knlGetSystemProperty({}, {:#x}, {})", id, p_out, buf_size);
+// properties come from the device profile, unknown ones are empty
+async fn get_system_property(context: &mut dyn WIPICContext, id: String, p_out: WIPICWord, buf_size: WIPICWord) -> WIPICResult<i32> {
+    tracing::debug!("MC_knlGetSystemProperty({}, {:#x}, {})", id, p_out, buf_size);
 
-    Ok(0)
+    let value = context.system().platform().device_profile().property(&id).unwrap_or_default().to_string();
+    if (context.system().encode_str(&value).len() as WIPICWord) >= buf_size {
+        return Ok(-18); // M_E_SHORTBUF
+    }
+
+    let length = write_string(context, p_out, &value)?;
+
+    Ok(length as _)
 }
 
 async fn def_timer(context: &mut dyn WIPICContext, ptr_timer: WIPICWord, fn_callback: WIPICWord) -> WIPICResult<()> {
@@ -112,13 +120,19 @@ async fn unset_timer(_: &mut dyn WIPICContext, a0: WIPICWord) -> WIPICResult<()>
 async fn alloc(context: &mut dyn WIPICContext, size: WIPICWord) -> WIPICResult<WIPICMemoryId> {
     tracing::debug!("MC_knlAlloc({:#x})", size);
 
-    context.alloc(size)
+    match context.alloc(size) {
+        Err(WIPICError::OutOfMemory) => Ok(WIPICMemoryId(0)),
+        x => x,
+    }
 }
 
 async fn calloc(context: &mut dyn WIPICContext, size: WIPICWord) -> WIPICResult<WIPICMemoryId> {
     tracing::debug!("MC_knlCalloc({:#x})", size);
 
-    let memory = context.alloc(size)?;
+    let memory = match context.alloc(size) {
+        Err(WIPICError::OutOfMemory) => return Ok(WIPICMemoryId(0)),
+        x => x?,
+    };
 
     let zero = iter::repeat(0).take(size as usize).collect::<Vec<_>>();
     context.write_bytes(context.data_ptr(memory)?, &zero)?;
@@ -184,21 +198,21 @@ async fn sprintk(context: &mut dyn WIPICContext, dest: WIPICWord, format: String
 
     let result = sprintf(context, &format, &[a0, a1, a2, a3])?;
 
-    write_null_terminated_string(context, dest, &result)?;
+    let length = write_string(context, dest, &result)?;
 
-    Ok(result.len() as _)
+    Ok(length as _)
 }
 
-async fn get_total_memory(_context: &mut dyn WIPICContext) -> WIPICResult<i32> {
-    tracing::warn!("stub MC_knlGetTotalMemory()");
+async fn get_total_memory(context: &mut dyn WIPICContext) -> WIPICResult<i32> {
+    tracing::debug!("MC_knlGetTotalMemory()");
 
-    Ok(0x100000) // TODO hardcoded
+    Ok(context.total_memory()? as _)
 }
 
-async fn get_free_memory(_context: &mut dyn WIPICContext) -> WIPICResult<i32> {
-    tracing::warn!("stub MC_knlGetFreeMemory()");
+async fn get_free_memory(context: &mut dyn WIPICContext) -> WIPICResult<i32> {
+    tracing::debug!("MC_knlGetFreeMemory()");
 
-    Ok(0x100000) // TODO hardcoded
+    Ok(context.free_memory()? as _)
 }
 
 fn sprintf(context: &mut dyn WIPICContext, format: &str, args: &[u32]) -> WIPICResult<String> {
@@ -214,7 +228,7 @@ fn sprintf(context: &mut dyn WIPICContext, format: &str, args: &[u32]) -> WIPICR
                 'd' => result += &arg_iter.next().unwrap().to_string(),
                 's' => {
                     let ptr = arg_iter.next().unwrap();
-                    let str = read_null_terminated_string(context, *ptr)?;
+                    let str = read_string(context, *ptr)?;
 
                     result += &str;
                 }
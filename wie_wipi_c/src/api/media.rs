@@ -1,11 +1,13 @@
 use alloc::{string::String, vec, vec::Vec};
 use core::mem::size_of;
 
+use wie_util::{read_generic, write_generic};
+
 use crate::{context::WIPICContext, method::MethodImpl, WIPICError, WIPICMethodBody, WIPICResult, WIPICWord};
 
 #[repr(C)]
 struct MdaClip {
-    clip_id: i32,
+    clip_id: i32, // audio handle + 1 of the data put, 0 if none
     h_proc: i32,
     r#type: u8,
     in_use: bool,
@@ -74,10 +76,30 @@ async fn clip_create(context: &mut dyn WIPICContext, r#type: String, buf_size: W
     tracing::warn!("stub MC_mdaClipCreate({}, {:#x}, {:#x})", r#type, buf_size, callback);
 
     let clip = context.alloc_raw(size_of::<MdaClip>() as u32)?;
+    write_generic(context, clip, 0u32)?;
 
     Ok(clip)
 }
 
+async fn clip_free(context: &mut dyn WIPICContext, clip: WIPICWord) -> WIPICResult<WIPICWord> {
+    tracing::debug!("MC_mdaClipFree({:#x})", clip);
+
+    release_audio(context, clip)?;
+    context.free_raw(clip)?;
+
+    Ok(0)
+}
+
+fn release_audio(context: &mut dyn WIPICContext, clip: WIPICWord) -> WIPICResult<()> {
+    let clip_id: u32 = read_generic(context, clip)?;
+    if clip_id != 0 {
+        let _ = context.system().audio().unload(clip_id - 1);
+        write_generic(context, clip, 0u32)?;
+    }
+
+    Ok(())
+}
+
 async fn clip_get_type(_context: &mut dyn WIPICContext, clip: WIPICWord, buf: WIPICWord, buf_size: WIPICWord) -> WIPICResult<WIPICWord> {
     tracing::warn!("stub MC_mdaClipGetType({:#x}, {:#x}, {:#x})", clip, buf, buf_size);
 
@@ -107,11 +129,13 @@ async fn clip_put_data(context: &mut dyn WIPICContext, clip: WIPICWord, buf: WIP
 
     let data = context.read_bytes(buf, buf_size)?;
 
-    context
+    release_audio(context, clip)?;
+    let audio_handle = context
         .system()
         .audio()
-        .load_smaf(&data)
+        .load(&data)
         .map_err(|_| WIPICError::BackendError("Invalid Audio".into()))?;
+    write_generic(context, clip, audio_handle + 1)?;
 
     Ok(0)
 }
@@ -161,7 +185,7 @@ async fn record(_context: &mut dyn WIPICContext, clip: WIPICWord) -> WIPICResult
 pub fn get_media_method_table() -> Vec<WIPICMethodBody> {
     vec![
         clip_create.into_body(),
-        gen_stub(1, "MC_mdaClipFree"),
+        clip_free.into_body(),
         gen_stub(2, "MC_mdaSetWaterMark"),
         clip_get_type.into_body(),
         clip_put_data.into_body(),
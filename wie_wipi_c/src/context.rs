@@ -1,7 +1,7 @@
 use alloc::{boxed::Box, string::String};
 
 use wie_backend::System;
-use wie_util::{read_null_terminated_string, ByteRead, ByteWrite};
+use wie_util::{read_null_terminated_bytes, ByteRead, ByteWrite};
 
 use crate::{method::TypeConverter, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord};
 
@@ -12,6 +12,8 @@ pub trait WIPICContext: ByteRead + ByteWrite {
     fn free(&mut self, memory: WIPICMemoryId) -> WIPICResult<()>;
     fn free_raw(&mut self, address: WIPICWord) -> WIPICResult<()>;
     fn data_ptr(&self, memory: WIPICMemoryId) -> WIPICResult<WIPICWord>;
+    fn total_memory(&self) -> WIPICResult<WIPICWord>;
+    fn free_memory(&self) -> WIPICResult<WIPICWord>;
     fn register_function(&mut self, method: WIPICMethodBody) -> WIPICResult<WIPICWord>;
     async fn call_function(&mut self, address: WIPICWord, args: &[WIPICWord]) -> WIPICResult<WIPICWord>;
     fn system(&mut self) -> &mut System;
@@ -58,10 +60,27 @@ impl TypeConverter<()> for () {
 
 impl TypeConverter<String> for String {
     fn to_rust(context: &mut dyn WIPICContext, raw: WIPICWord) -> String {
-        read_null_terminated_string(context, raw).unwrap()
+        read_string(context, raw).unwrap()
     }
 
     fn from_rust(_: &mut dyn WIPICContext, _: String) -> WIPICWord {
         unimplemented!()
     }
 }
+
+// strings on wipi c side are in the encoding of the app
+pub(crate) fn read_string(context: &mut dyn WIPICContext, address: WIPICWord) -> WIPICResult<String> {
+    let bytes = read_null_terminated_bytes(context, address)?;
+
+    Ok(context.system().decode_str(&bytes))
+}
+
+pub(crate) fn write_string(context: &mut dyn WIPICContext, address: WIPICWord, string: &str) -> WIPICResult<WIPICWord> {
+    let mut bytes = context.system().encode_str(string);
+    let length = bytes.len() as WIPICWord;
+    bytes.push(0);
+
+    context.write_bytes(address, &bytes)?;
+
+    Ok(length)
+}
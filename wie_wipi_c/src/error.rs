@@ -6,6 +6,7 @@ use wie_util::ByteReadWriteError;
 pub enum WIPICError {
     Unimplemented(String),
     InvalidMemoryAccess,
+    OutOfMemory,
     BackendError(String),
 }
 
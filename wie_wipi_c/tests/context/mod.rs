@@ -1,3 +1,4 @@
+use test_utils::test_platform;
 use wie_backend::System;
 use wie_util::{ByteRead, ByteWrite};
 use wie_wipi_c::{WIPICContext, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord};
@@ -5,6 +6,7 @@ use wie_wipi_c::{WIPICContext, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPI
 pub struct TestContext {
     memory: [u8; 0x10000],
     last_alloc: usize,
+    system: System,
 }
 
 impl TestContext {
@@ -12,6 +14,7 @@ impl TestContext {
         Self {
             memory: [0; 0x10000],
             last_alloc: 0,
+            system: System::new(Box::new(test_platform()), Box::new(())),
         }
     }
 }
@@ -41,6 +44,14 @@ impl WIPICContext for TestContext {
         Ok(memory.0)
     }
 
+    fn total_memory(&self) -> WIPICResult<WIPICWord> {
+        Ok(self.memory.len() as _)
+    }
+
+    fn free_memory(&self) -> WIPICResult<WIPICWord> {
+        Ok((self.memory.len() - self.last_alloc) as _)
+    }
+
     fn register_function(&mut self, _method: WIPICMethodBody) -> WIPICResult<WIPICWord> {
         todo!()
     }
@@ -50,7 +61,7 @@ impl WIPICContext for TestContext {
     }
 
     fn system(&mut self) -> &mut System {
-        todo!()
+        &mut self.system
     }
 
     fn spawn(&mut self, _callback: WIPICMethodBody) -> WIPICResult<()> {
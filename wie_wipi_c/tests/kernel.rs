@@ -1,4 +1,5 @@
-use wie_util::{read_null_terminated_string, write_null_terminated_string};
+use wie_backend::TextEncoding;
+use wie_util::{read_null_terminated_bytes, read_null_terminated_string, write_null_terminated_string, ByteWrite};
 use wie_wipi_c::{api::kernel::get_kernel_method_table, WIPICContext, WIPICError};
 
 mod context;
@@ -25,3 +26,31 @@ async fn test_sprintk() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[futures_test::test]
+async fn test_sprintk_encoding() -> anyhow::Result<()> {
+    let mut context = context::TestContext::new();
+    context.system().set_encoding(TextEncoding::EucKr);
+
+    let kernel_methods = get_kernel_method_table(|_: &mut dyn WIPICContext| async { Ok::<_, WIPICError>(()) });
+
+    let format = context.alloc_raw(10).unwrap();
+    context.write_bytes(format, &TextEncoding::EucKr.encode("%s점\0")).unwrap();
+
+    let string = context.alloc_raw(10).unwrap();
+    context.write_bytes(string, &TextEncoding::EucKr.encode("한글\0")).unwrap();
+
+    let dest = context.alloc_raw(10).unwrap();
+
+    let length = kernel_methods[1]
+        .call(&mut context, Box::new([dest, format, string, 0, 0, 0, 0]))
+        .await
+        .unwrap();
+
+    let result = read_null_terminated_bytes(&context, dest).unwrap();
+
+    assert_eq!(length, 6);
+    assert_eq!(TextEncoding::EucKr.decode(&result), "한글점");
+
+    Ok(())
+}
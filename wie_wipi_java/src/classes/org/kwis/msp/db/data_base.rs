@@ -8,7 +8,7 @@ use java_constants::MethodAccessFlags;
 use java_runtime::classes::java::lang::String;
 use jvm::{runtime::JavaLangString, Array, ClassInstanceRef, Jvm, Result as JvmResult};
 
-use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
+use crate::context::{java_exception, WIPIJavaClassProto, WIPIJavaContext};
 
 // class org.kwis.msp.db.DataBase
 pub struct DataBase {}
@@ -44,18 +44,23 @@ impl DataBase {
 
     async fn open_data_base(
         jvm: &Jvm,
-        _: &mut WIPIJavaContext,
+        context: &mut WIPIJavaContext,
         data_base_name: ClassInstanceRef<String>,
         record_size: i32,
         create: bool,
     ) -> JvmResult<ClassInstanceRef<DataBase>> {
-        tracing::warn!(
-            "stub org.kwis.msp.db.DataBase::openDataBase({:?}, {}, {})",
+        tracing::debug!(
+            "org.kwis.msp.db.DataBase::openDataBase({:?}, {}, {})",
             &data_base_name,
             record_size,
             create
         );
 
+        let name = JavaLangString::to_rust_string(jvm, &data_base_name).await?;
+        if let Err(x) = context.system().platform().database_repository().open(&name, create) {
+            return Err(java_exception(jvm, "org/kwis/msp/db/DataBaseException", x).await);
+        }
+
         let instance = jvm
             .new_class("org/kwis/msp/db/DataBase", "(Ljava/lang/String;)V", (data_base_name,))
             .await?;
@@ -68,9 +73,10 @@ impl DataBase {
 
         let database = Self::get_database(jvm, context, &this).await?;
 
-        let count = database.get_record_ids().len();
-
-        Ok(count as _)
+        match database.get_record_ids() {
+            Ok(x) => Ok(x.len() as _),
+            Err(x) => Err(java_exception(jvm, "org/kwis/msp/db/DataBaseException", x).await),
+        }
     }
 
     async fn close_data_base(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<DataBase>) -> JvmResult<()> {
@@ -100,9 +106,10 @@ impl DataBase {
         let data = jvm.load_byte_array(&data, offset as _, num_bytes as _).await?;
         let data_raw = cast_vec(data);
 
-        let id = database.add(&data_raw);
-
-        Ok(id as _)
+        match database.add(&data_raw) {
+            Ok(x) => Ok(x as _),
+            Err(x) => Err(java_exception(jvm, "org/kwis/msp/db/DataBaseException", x).await),
+        }
     }
 
     async fn select_record(
@@ -115,7 +122,10 @@ impl DataBase {
 
         let database = Self::get_database(jvm, context, &this).await?;
 
-        let data = database.get(record_id as _).unwrap();
+        let data = match database.get(record_id as _) {
+            Ok(x) => x,
+            Err(x) => return Err(java_exception(jvm, "org/kwis/msp/db/DataBaseRecordException", x).await),
+        };
 
         let mut array = jvm.instantiate_array("B", data.len() as _).await?;
         jvm.store_byte_array(&mut array, 0, cast_vec(data)).await?;
@@ -127,6 +137,11 @@ impl DataBase {
         let db_name = jvm.get_field(this, "dbName", "Ljava/lang/String;").await?;
         let db_name_str = JavaLangString::to_rust_string(jvm, &db_name).await?;
 
-        Ok(context.system().platform().database_repository().open(&db_name_str))
+        let database = context.system().platform().database_repository().open(&db_name_str, false);
+
+        match database {
+            Ok(x) => Ok(x),
+            Err(x) => Err(java_exception(jvm, "org/kwis/msp/db/DataBaseException", x).await),
+        }
     }
 }
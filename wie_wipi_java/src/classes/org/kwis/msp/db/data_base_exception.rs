@@ -1,6 +1,10 @@
 use alloc::vec;
 
-use crate::context::WIPIJavaClassProto;
+use java_class_proto::JavaMethodProto;
+use java_runtime::classes::java::lang::String;
+use jvm::{ClassInstanceRef, Jvm, Result as JvmResult};
+
+use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
 
 // class org.kwis.msp.db.DataBaseException
 pub struct DataBaseException {}
@@ -10,8 +14,17 @@ impl DataBaseException {
         WIPIJavaClassProto {
             parent_class: Some("java/lang/Exception"),
             interfaces: vec![],
-            methods: vec![],
+            methods: vec![JavaMethodProto::new("<init>", "(Ljava/lang/String;)V", Self::init, Default::default())],
             fields: vec![],
         }
     }
+
+    async fn init(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, message: ClassInstanceRef<String>) -> JvmResult<()> {
+        tracing::debug!("org.kwis.msp.db.DataBaseException::<init>({:?}, {:?})", &this, &message);
+
+        jvm.invoke_special(&this, "java/lang/Exception", "<init>", "(Ljava/lang/String;)V", (message,))
+            .await?;
+
+        Ok(())
+    }
 }
@@ -1,4 +1,4 @@
-use alloc::vec;
+use alloc::{string::ToString, vec};
 
 use java_class_proto::JavaMethodProto;
 use java_constants::MethodAccessFlags;
@@ -25,11 +25,20 @@ impl HandsetProperty {
         }
     }
 
-    async fn get_system_property(jvm: &Jvm, _: &mut WIPIJavaContext, name: ClassInstanceRef<String>) -> JvmResult<ClassInstanceRef<String>> {
+    // properties come from the device profile, unknown ones are empty as some apps don't expect null
+    async fn get_system_property(jvm: &Jvm, context: &mut WIPIJavaContext, name: ClassInstanceRef<String>) -> JvmResult<ClassInstanceRef<String>> {
         let name = JavaLangString::to_rust_string(jvm, &name).await?;
-        tracing::warn!("stub org.kwis.msp.handset.HandsetProperty::getSystemProperty({})", name);
+        tracing::debug!("org.kwis.msp.handset.HandsetProperty::getSystemProperty({})", name);
 
-        let result = JavaLangString::from_rust_string(jvm, "").await?;
+        let value = context
+            .system()
+            .platform()
+            .device_profile()
+            .property(&name)
+            .unwrap_or_default()
+            .to_string();
+
+        let result = JavaLangString::from_rust_string(jvm, &value).await?;
         Ok(result.into())
     }
 }
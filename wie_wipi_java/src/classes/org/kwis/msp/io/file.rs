@@ -1,7 +1,7 @@
 use alloc::vec;
 use core::cmp::min;
 
-use bytemuck::cast_slice;
+use bytemuck::{cast_slice, cast_vec};
 
 use java_class_proto::{JavaFieldProto, JavaMethodProto};
 use java_runtime::classes::java::lang::String;
@@ -9,6 +9,8 @@ use jvm::{runtime::JavaLangString, Array, ClassInstanceRef, Jvm, Result as JvmRe
 
 use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
 
+use super::FileSystem;
+
 // class org.kwis.msp.io.File
 pub struct File {}
 
@@ -26,6 +28,7 @@ impl File {
                 JavaMethodProto::new("sizeOf", "()I", Self::size_of, Default::default()),
             ],
             fields: vec![
+                JavaFieldProto::new("filename", "Ljava/lang/String;", Default::default()),
                 JavaFieldProto::new("data", "[B", Default::default()),
                 JavaFieldProto::new("pos", "I", Default::default()),
             ],
@@ -49,22 +52,25 @@ impl File {
         mode: i32,
         flag: i32,
     ) -> JvmResult<()> {
-        tracing::warn!("stub org.kwis.msp.io.File::<init>({:?}, {:?}, {:?}, {:?})", &this, &filename, mode, flag);
+        tracing::debug!("org.kwis.msp.io.File::<init>({:?}, {:?}, {:?}, {:?})", &this, &filename, mode, flag);
 
         let filename = JavaLangString::to_rust_string(jvm, &filename).await?;
-        tracing::debug!("Loading {}", filename);
+        let (path, data) = {
+            let vfs = context.system().vfs();
+            let path = FileSystem::private_path(&vfs, &filename);
 
-        // TODO we don't have filesystem now, emulating file loading with resource for now..
-        let data = {
-            let resource = context.system().resource();
-            let data = resource.data(resource.id(&filename).unwrap());
+            // TODO open mode is not enforced, missing files are created on first write
+            let data = vfs.read(&path).unwrap_or_default();
 
-            cast_slice(data).to_vec()
+            (path, data)
         };
+        tracing::debug!("Opening {}", path);
 
         let mut data_array = jvm.instantiate_array("B", data.len() as _).await?;
-        jvm.store_byte_array(&mut data_array, 0, data).await?;
+        jvm.store_byte_array(&mut data_array, 0, cast_vec(data)).await?;
 
+        let path = JavaLangString::from_rust_string(jvm, &path).await?;
+        jvm.put_field(&mut this, "filename", "Ljava/lang/String;", path).await?;
         jvm.put_field(&mut this, "data", "[B", data_array).await?;
         jvm.put_field(&mut this, "pos", "I", 0).await?;
 
@@ -72,16 +78,48 @@ impl File {
     }
 
     async fn write(
-        _jvm: &Jvm,
-        _: &mut WIPIJavaContext,
-        this: ClassInstanceRef<Self>,
-        buf: ClassInstanceRef<ClassInstanceRef<Array<i8>>>,
+        jvm: &Jvm,
+        context: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        buf: ClassInstanceRef<Array<i8>>,
         offset: i32,
         len: i32,
     ) -> JvmResult<i32> {
-        tracing::warn!("stub org.kwis.msp.io.File::write({:?}, {:?}, {:?}, {:?})", &this, &buf, offset, len);
+        tracing::debug!("org.kwis.msp.io.File::write({:?}, {:?}, {:?}, {:?})", &this, &buf, offset, len);
+
+        let mut data_array = jvm.get_field(&this, "data", "[B").await?;
+        let pos: i32 = jvm.get_field(&this, "pos", "I").await?;
+
+        let buf_len = jvm.array_length(&buf).await?;
+        let length_to_write = min(len.max(0) as usize, buf_len.saturating_sub(offset.max(0) as usize));
+        let bytes = jvm.load_byte_array(&buf, offset.max(0) as _, length_to_write).await?;
+
+        let data_len = jvm.array_length(&data_array).await?;
+        let mut data = jvm.load_byte_array(&data_array, 0, data_len).await?;
+
+        let pos = pos as usize;
+        let end = pos + length_to_write;
+        if end > data.len() {
+            data.resize(end, 0);
+        }
+        data[pos..end].copy_from_slice(&bytes);
+
+        // grow the backing array if we're writing past the end
+        if end > data_len {
+            data_array = jvm.instantiate_array("B", end).await?;
+            jvm.store_byte_array(&mut data_array, 0, data.clone()).await?;
+            jvm.put_field(&mut this, "data", "[B", data_array).await?;
+        } else {
+            jvm.store_byte_array(&mut data_array, pos, bytes).await?;
+        }
+        jvm.put_field(&mut this, "pos", "I", end as i32).await?;
+
+        // written through, as apps often exit without closing files
+        let filename = jvm.get_field(&this, "filename", "Ljava/lang/String;").await?;
+        let filename = JavaLangString::to_rust_string(jvm, &filename).await?;
+        context.system().vfs().write(&filename, cast_slice(&data));
 
-        Ok(0)
+        Ok(length_to_write as _)
     }
 
     async fn read(jvm: &Jvm, _: &mut WIPIJavaContext, mut this: ClassInstanceRef<Self>, mut buf: ClassInstanceRef<Array<i8>>) -> JvmResult<i32> {
@@ -104,7 +142,7 @@ impl File {
     }
 
     async fn close(_jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<()> {
-        tracing::warn!("stub org.kwis.msp.io.File::close({:?})", &this);
+        tracing::debug!("org.kwis.msp.io.File::close({:?})", &this);
 
         Ok(())
     }
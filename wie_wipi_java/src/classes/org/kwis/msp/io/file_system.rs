@@ -1,10 +1,12 @@
-use alloc::{format, vec};
+use alloc::{format, string::String as RustString, vec};
 
 use java_class_proto::JavaMethodProto;
 use java_constants::MethodAccessFlags;
 use java_runtime::classes::java::lang::String;
 use jvm::{runtime::JavaLangString, ClassInstanceRef, Jvm, Result as JvmResult};
 
+use wie_backend::Vfs;
+
 use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
 
 // class org.kwis.msp.io.FileSystem
@@ -25,29 +27,42 @@ impl FileSystem {
         }
     }
 
-    async fn is_file(_jvm: &Jvm, _: &mut WIPIJavaContext, name: ClassInstanceRef<String>) -> JvmResult<bool> {
-        tracing::warn!("stub org.kwis.msp.io.FileSystem::is_file({:?})", &name);
+    async fn is_file(jvm: &Jvm, context: &mut WIPIJavaContext, name: ClassInstanceRef<String>) -> JvmResult<bool> {
+        tracing::debug!("org.kwis.msp.io.FileSystem::isFile({:?})", &name);
+
+        let filename = JavaLangString::to_rust_string(jvm, &name).await?;
+        let vfs = context.system().vfs();
 
-        Ok(false)
+        Ok(vfs.is_file(&Self::private_path(&vfs, &filename)))
     }
 
-    async fn is_directory(_jvm: &Jvm, _: &mut WIPIJavaContext, name: ClassInstanceRef<String>, flag: i32) -> JvmResult<bool> {
-        tracing::warn!("stub org.kwis.msp.io.FileSystem::isDirectory({:?}, {:?})", &name, flag);
+    async fn is_directory(jvm: &Jvm, context: &mut WIPIJavaContext, name: ClassInstanceRef<String>, flag: i32) -> JvmResult<bool> {
+        tracing::debug!("org.kwis.msp.io.FileSystem::isDirectory({:?}, {:?})", &name, flag);
 
-        Ok(true)
+        let filename = JavaLangString::to_rust_string(jvm, &name).await?;
+        let vfs = context.system().vfs();
+
+        Ok(vfs.is_directory(&Self::private_path(&vfs, &filename)))
     }
 
     async fn exists(jvm: &Jvm, context: &mut WIPIJavaContext, name: ClassInstanceRef<String>) -> JvmResult<bool> {
-        tracing::warn!("stub org.kwis.msp.io.FileSystem::exists({:?})", &name);
+        tracing::debug!("org.kwis.msp.io.FileSystem::exists({:?})", &name);
 
         let filename = JavaLangString::to_rust_string(jvm, &name).await?;
+        let vfs = context.system().vfs();
 
-        // emulating filesystem by resource..
-        let filename_on_resource = format!("P{}", filename);
+        Ok(vfs.exists(&Self::private_path(&vfs, &filename)))
+    }
 
-        let id = context.system().resource().id(&filename_on_resource);
+    // app private files are under P/, as in KTF archives. other archives have them on the root,
+    // which is used as long as there's no private file of the same name
+    pub(super) fn private_path(vfs: &Vfs, filename: &str) -> RustString {
+        let private_path = format!("P/{}", filename);
+        if !vfs.exists(&private_path) && vfs.exists(filename) {
+            return filename.into();
+        }
 
-        Ok(id.is_some())
+        private_path
     }
 
     async fn available(_: &Jvm, _: &mut WIPIJavaContext) -> JvmResult<i32> {
@@ -3,6 +3,8 @@ use alloc::{format, vec};
 use java_class_proto::{JavaFieldProto, JavaMethodProto};
 use jvm::{ClassInstanceRef, Jvm, Result as JvmResult};
 
+use wie_backend::canvas::Rect;
+
 use crate::{
     classes::org::kwis::msp::lcdui::Display,
     context::{WIPIJavaClassProto, WIPIJavaContext},
@@ -115,15 +117,23 @@ impl Card {
     }
 
     async fn repaint_with_area(
-        _: &Jvm,
+        jvm: &Jvm,
         context: &mut WIPIJavaContext,
         this: ClassInstanceRef<Card>,
-        a0: i32,
-        a1: i32,
-        a2: i32,
-        a3: i32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
     ) -> JvmResult<()> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Card::repaint({:?}, {}, {}, {}, {})", &this, a0, a1, a2, a3);
+        tracing::debug!("org.kwis.msp.lcdui.Card::repaint({:?}, {}, {}, {}, {})", &this, x, y, width, height);
+
+        let mut display: ClassInstanceRef<Display> = jvm.get_field(&this, "display", "Lorg/kwis/msp/lcdui/Display;").await?;
+        if !display.is_null() {
+            let card_x: i32 = jvm.get_field(&this, "x", "I").await?;
+            let card_y: i32 = jvm.get_field(&this, "y", "I").await?;
+
+            Display::add_dirty_area(jvm, &mut display, Rect::new(x, y, width, height).offset(card_x, card_y)).await?;
+        }
 
         let mut platform = context.system().platform();
         let screen = platform.screen();
@@ -6,6 +6,8 @@ use java_constants::MethodAccessFlags;
 use java_runtime::classes::java::lang::{Object, Runnable, String};
 use jvm::{ClassInstanceRef, JavaError, JavaValue, Jvm, Result as JvmResult};
 
+use wie_backend::canvas::Rect;
+
 use crate::{
     classes::org::kwis::msp::lcdui::{Card, Jlet, JletEventListener},
     context::{WIPIJavaClassProto, WIPIJavaContext},
@@ -63,6 +65,10 @@ impl Display {
                 JavaFieldProto::new("szCard", "I", Default::default()),
                 JavaFieldProto::new("m_w", "I", Default::default()),
                 JavaFieldProto::new("m_h", "I", Default::default()),
+                JavaFieldProto::new("dirtyX", "I", Default::default()),
+                JavaFieldProto::new("dirtyY", "I", Default::default()),
+                JavaFieldProto::new("dirtyW", "I", Default::default()),
+                JavaFieldProto::new("dirtyH", "I", Default::default()),
             ],
         }
     }
@@ -221,4 +227,41 @@ impl Display {
 
         Ok(action)
     }
+
+    // area of the screen requested to be repainted until the next repaint event
+    pub async fn add_dirty_area(jvm: &Jvm, this: &mut ClassInstanceRef<Self>, area: Rect) -> JvmResult<()> {
+        let dirty = Self::dirty_area(jvm, this).await?.union(&area);
+
+        Self::put_dirty_area(jvm, this, dirty).await
+    }
+
+    // everything is dirty if nothing was requested, as for the first paint
+    pub async fn take_dirty_area(jvm: &Jvm, this: &mut ClassInstanceRef<Self>) -> JvmResult<Rect> {
+        let width: i32 = jvm.get_field(this, "m_w", "I").await?;
+        let height: i32 = jvm.get_field(this, "m_h", "I").await?;
+        let screen = Rect::new(0, 0, width, height);
+
+        let dirty = Self::dirty_area(jvm, this).await?;
+        Self::put_dirty_area(jvm, this, Rect::default()).await?;
+
+        Ok(if dirty.is_empty() { screen } else { dirty.intersect(&screen) })
+    }
+
+    async fn dirty_area(jvm: &Jvm, this: &ClassInstanceRef<Self>) -> JvmResult<Rect> {
+        Ok(Rect::new(
+            jvm.get_field(this, "dirtyX", "I").await?,
+            jvm.get_field(this, "dirtyY", "I").await?,
+            jvm.get_field(this, "dirtyW", "I").await?,
+            jvm.get_field(this, "dirtyH", "I").await?,
+        ))
+    }
+
+    async fn put_dirty_area(jvm: &Jvm, this: &mut ClassInstanceRef<Self>, area: Rect) -> JvmResult<()> {
+        jvm.put_field(this, "dirtyX", "I", area.x).await?;
+        jvm.put_field(this, "dirtyY", "I", area.y).await?;
+        jvm.put_field(this, "dirtyW", "I", area.width).await?;
+        jvm.put_field(this, "dirtyH", "I", area.height).await?;
+
+        Ok(())
+    }
 }
@@ -116,34 +116,24 @@ impl EventQueue {
     ) -> JvmResult<()> {
         tracing::debug!("org.kwis.msp.lcdui.EventQueue::getNextEvent({:?}, {:?})", &this, &event);
 
-        loop {
-            let maybe_event = context.system().event_queue().pop();
-
-            if let Some(x) = maybe_event {
-                let event_data = match x {
-                    Event::Redraw => vec![EventQueueEvent::RepaintEvent as _, 0, 0, 0],
-                    Event::Keydown(x) => vec![
-                        EventQueueEvent::KeyEvent as _,
-                        KeyboardEventType::KeyPressed as _,
-                        WIPIKeyCode::from_key_code(x) as _,
-                        0,
-                    ],
-                    Event::Keyup(x) => vec![
-                        EventQueueEvent::KeyEvent as _,
-                        KeyboardEventType::KeyReleased as _,
-                        WIPIKeyCode::from_key_code(x) as _,
-                        0,
-                    ],
-                };
-
-                jvm.store_array(&mut event, 0, event_data).await?;
-
-                break;
-            } else {
-                let until = context.system().platform().now() + 16;
-                context.system().sleep(until).await; // TODO we need to wait for events
-            }
-        }
+        let next_event = context.system().next_event();
+        let event_data = match next_event.await {
+            Event::Redraw => vec![EventQueueEvent::RepaintEvent as _, 0, 0, 0],
+            Event::Keydown(x) => vec![
+                EventQueueEvent::KeyEvent as _,
+                KeyboardEventType::KeyPressed as _,
+                WIPIKeyCode::from_key_code(x) as _,
+                0,
+            ],
+            Event::Keyup(x) => vec![
+                EventQueueEvent::KeyEvent as _,
+                KeyboardEventType::KeyReleased as _,
+                WIPIKeyCode::from_key_code(x) as _,
+                0,
+            ],
+        };
+
+        jvm.store_array(&mut event, 0, event_data).await?;
 
         Ok(())
     }
@@ -191,7 +181,7 @@ impl EventQueue {
     }
 
     async fn repaint(jvm: &Jvm, context: &mut WIPIJavaContext) -> JvmResult<()> {
-        let display = Self::get_current_display(jvm).await?;
+        let mut display = Self::get_current_display(jvm).await?;
         if display.is_null() {
             return Ok(());
         }
@@ -201,9 +191,13 @@ impl EventQueue {
             return Ok(());
         }
 
+        let area = Display::take_dirty_area(jvm, &mut display).await?;
+
         let mut graphics = jvm
             .new_class("org/kwis/msp/lcdui/Graphics", "(Lorg/kwis/msp/lcdui/Display;)V", (display,))
             .await?;
+        jvm.invoke_virtual(&graphics, "setClip", "(IIII)V", (area.x, area.y, area.width, area.height))
+            .await?;
 
         jvm.invoke_virtual(&card, "paint", "(Lorg/kwis/msp/lcdui/Graphics;)V", [graphics.clone().into()])
             .await?;
@@ -222,7 +216,7 @@ impl EventQueue {
             let mut platform = context.system().platform();
             let screen = platform.screen();
 
-            screen.paint(&*image);
+            screen.paint(&*image, &area);
         }
 
         Ok(())
@@ -1,8 +1,11 @@
-use alloc::vec;
+use alloc::{string::String as RustString, vec};
 
 use java_class_proto::{JavaFieldProto, JavaMethodProto};
 use java_constants::{FieldAccessFlags, MethodAccessFlags};
-use jvm::{ClassInstanceRef, Jvm, Result as JvmResult};
+use java_runtime::classes::java::lang::String;
+use jvm::{runtime::JavaLangString, ClassInstanceRef, Jvm, Result as JvmResult};
+
+use wie_backend::canvas;
 
 use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
 
@@ -17,7 +20,15 @@ impl Font {
             methods: vec![
                 JavaMethodProto::new("<clinit>", "()V", Self::cl_init, MethodAccessFlags::STATIC),
                 JavaMethodProto::new("<init>", "()V", Self::init, Default::default()),
+                JavaMethodProto::new("<init>", "(III)V", Self::init_with_attributes, Default::default()),
                 JavaMethodProto::new("getHeight", "()I", Self::get_height, Default::default()),
+                JavaMethodProto::new("getBaselinePosition", "()I", Self::get_baseline_position, Default::default()),
+                JavaMethodProto::new("charWidth", "(C)I", Self::char_width, Default::default()),
+                JavaMethodProto::new("stringWidth", "(Ljava/lang/String;)I", Self::string_width, Default::default()),
+                JavaMethodProto::new("substringWidth", "(Ljava/lang/String;II)I", Self::substring_width, Default::default()),
+                JavaMethodProto::new("getFace", "()I", Self::get_face, Default::default()),
+                JavaMethodProto::new("getStyle", "()I", Self::get_style, Default::default()),
+                JavaMethodProto::new("getSize", "()I", Self::get_size, Default::default()),
                 JavaMethodProto::new(
                     "getDefaultFont",
                     "()Lorg/kwis/msp/lcdui/Font;",
@@ -28,8 +39,18 @@ impl Font {
             ],
             fields: vec![
                 JavaFieldProto::new("FACE_SYSTEM", "I", FieldAccessFlags::STATIC),
+                JavaFieldProto::new("FACE_MONOSPACE", "I", FieldAccessFlags::STATIC),
+                JavaFieldProto::new("FACE_PROPORTIONAL", "I", FieldAccessFlags::STATIC),
                 JavaFieldProto::new("STYLE_PLAIN", "I", FieldAccessFlags::STATIC),
+                JavaFieldProto::new("STYLE_BOLD", "I", FieldAccessFlags::STATIC),
+                JavaFieldProto::new("STYLE_ITALIC", "I", FieldAccessFlags::STATIC),
+                JavaFieldProto::new("STYLE_UNDERLINED", "I", FieldAccessFlags::STATIC),
                 JavaFieldProto::new("SIZE_SMALL", "I", FieldAccessFlags::STATIC),
+                JavaFieldProto::new("SIZE_MEDIUM", "I", FieldAccessFlags::STATIC),
+                JavaFieldProto::new("SIZE_LARGE", "I", FieldAccessFlags::STATIC),
+                JavaFieldProto::new("face", "I", Default::default()),
+                JavaFieldProto::new("style", "I", Default::default()),
+                JavaFieldProto::new("size", "I", Default::default()),
             ],
         }
     }
@@ -37,27 +58,112 @@ impl Font {
     async fn cl_init(jvm: &Jvm, _: &mut WIPIJavaContext) -> JvmResult<()> {
         tracing::debug!("org.kwis.msp.lcdui.Font::<clinit>");
 
-        jvm.put_static_field("org/kwis/msp/lcdui/Font", "FACE_SYSTEM", "I", 0).await?;
-        jvm.put_static_field("org/kwis/msp/lcdui/Font", "STYLE_PLAIN", "I", 0).await?;
-        jvm.put_static_field("org/kwis/msp/lcdui/Font", "SIZE_SMALL", "I", 8).await?;
+        let constants = [
+            ("FACE_SYSTEM", canvas::FontFace::System as i32),
+            ("FACE_MONOSPACE", canvas::FontFace::Monospace as i32),
+            ("FACE_PROPORTIONAL", canvas::FontFace::Proportional as i32),
+            ("STYLE_PLAIN", canvas::FontStyle::PLAIN.bits() as i32),
+            ("STYLE_BOLD", canvas::FontStyle::BOLD.bits() as i32),
+            ("STYLE_ITALIC", canvas::FontStyle::ITALIC.bits() as i32),
+            ("STYLE_UNDERLINED", canvas::FontStyle::UNDERLINED.bits() as i32),
+            ("SIZE_SMALL", canvas::FontSize::Small as i32),
+            ("SIZE_MEDIUM", canvas::FontSize::Medium as i32),
+            ("SIZE_LARGE", canvas::FontSize::Large as i32),
+        ];
+        for (name, value) in constants {
+            jvm.put_static_field("org/kwis/msp/lcdui/Font", name, "I", value).await?;
+        }
 
         Ok(())
     }
 
-    async fn init(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Font>) -> JvmResult<()> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Font::<init>({:?})", &this);
+    async fn init(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::<init>({:?})", &this);
 
         Ok(())
     }
 
-    async fn get_height(_: &Jvm, _: &mut WIPIJavaContext) -> JvmResult<i32> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Font::getHeight");
+    async fn init_with_attributes(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        face: i32,
+        style: i32,
+        size: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::<init>({:?}, {}, {}, {})", &this, face, style, size);
+
+        jvm.put_field(&mut this, "face", "I", face).await?;
+        jvm.put_field(&mut this, "style", "I", style).await?;
+        jvm.put_field(&mut this, "size", "I", size).await?;
+
+        Ok(())
+    }
+
+    async fn get_height(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::getHeight({:?})", &this);
+
+        Ok(Self::font(jvm, &this).await?.height())
+    }
+
+    async fn get_baseline_position(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::getBaselinePosition({:?})", &this);
+
+        Ok(Self::font(jvm, &this).await?.baseline())
+    }
+
+    async fn char_width(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, c: u16) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::charWidth({:?}, {})", &this, c);
+
+        let c = char::from_u32(c as _).unwrap_or(char::REPLACEMENT_CHARACTER);
+
+        Ok(Self::font(jvm, &this).await?.char_width(c))
+    }
+
+    async fn string_width(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, string: ClassInstanceRef<String>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::stringWidth({:?}, {:?})", &this, &string);
+
+        let string = JavaLangString::to_rust_string(jvm, &string).await?;
 
-        Ok(12) // TODO: hardcoded
+        Ok(Self::font(jvm, &this).await?.string_width(&string))
+    }
+
+    async fn substring_width(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        this: ClassInstanceRef<Self>,
+        string: ClassInstanceRef<String>,
+        offset: i32,
+        len: i32,
+    ) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::substringWidth({:?}, {:?}, {}, {})", &this, &string, offset, len);
+
+        let string = JavaLangString::to_rust_string(jvm, &string).await?;
+        let substring = string.chars().skip(offset.max(0) as _).take(len.max(0) as _).collect::<RustString>();
+
+        Ok(Self::font(jvm, &this).await?.string_width(&substring))
+    }
+
+    async fn get_face(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::getFace({:?})", &this);
+
+        jvm.get_field(&this, "face", "I").await
+    }
+
+    async fn get_style(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::getStyle({:?})", &this);
+
+        jvm.get_field(&this, "style", "I").await
+    }
+
+    async fn get_size(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Font::getSize({:?})", &this);
+
+        jvm.get_field(&this, "size", "I").await
     }
 
     async fn get_default_font(jvm: &Jvm, _: &mut WIPIJavaContext) -> JvmResult<ClassInstanceRef<Self>> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Font::getDefaultFont");
+        tracing::debug!("org.kwis.msp.lcdui.Font::getDefaultFont");
 
         let instance = jvm.new_class("org/kwis/msp/lcdui/Font", "()V", []).await?;
 
@@ -65,10 +171,18 @@ impl Font {
     }
 
     async fn get_font(jvm: &Jvm, _: &mut WIPIJavaContext, face: i32, style: i32, size: i32) -> JvmResult<ClassInstanceRef<Font>> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Font::getFont({:?}, {:?}, {:?})", face, style, size);
+        tracing::debug!("org.kwis.msp.lcdui.Font::getFont({:?}, {:?}, {:?})", face, style, size);
 
-        let instance = jvm.new_class("org/kwis/msp/lcdui/Font", "()V", []).await?;
+        let instance = jvm.new_class("org/kwis/msp/lcdui/Font", "(III)V", (face, style, size)).await?;
 
         Ok(instance.into())
     }
+
+    pub async fn font(jvm: &Jvm, this: &ClassInstanceRef<Self>) -> JvmResult<canvas::Font> {
+        let face: i32 = jvm.get_field(this, "face", "I").await?;
+        let style: i32 = jvm.get_field(this, "style", "I").await?;
+        let size: i32 = jvm.get_field(this, "size", "I").await?;
+
+        Ok(canvas::Font::from_bits(face as _, style as _, size as _))
+    }
 }
@@ -3,7 +3,7 @@ use alloc::{format, vec, vec::Vec};
 use bytemuck::cast_vec;
 use jvm::{runtime::JavaLangString, JavaValue};
 
-use wie_backend::canvas::{PixelType, Rgb8Pixel, TextAlignment, VecImageBuffer};
+use wie_backend::canvas::{self, Canvas, PixelType, Rect, Rgb8Pixel, TextAlignment, Transform, VecImageBuffer};
 
 use java_class_proto::{JavaFieldProto, JavaMethodProto, TypeConverter};
 use java_runtime::classes::java::lang::String;
@@ -57,6 +57,13 @@ impl Graphics {
                 JavaMethodProto::new("fillRect", "(IIII)V", Self::fill_rect, Default::default()),
                 JavaMethodProto::new("drawLine", "(IIII)V", Self::draw_line, Default::default()),
                 JavaMethodProto::new("drawRect", "(IIII)V", Self::draw_rect, Default::default()),
+                JavaMethodProto::new("drawArc", "(IIIIII)V", Self::draw_arc, Default::default()),
+                JavaMethodProto::new("fillArc", "(IIIIII)V", Self::fill_arc, Default::default()),
+                JavaMethodProto::new("drawRoundRect", "(IIIIII)V", Self::draw_round_rect, Default::default()),
+                JavaMethodProto::new("fillRoundRect", "(IIIIII)V", Self::fill_round_rect, Default::default()),
+                JavaMethodProto::new("fillTriangle", "(IIIIII)V", Self::fill_triangle, Default::default()),
+                JavaMethodProto::new("drawPolygon", "([I[II)V", Self::draw_polygon, Default::default()),
+                JavaMethodProto::new("fillPolygon", "([I[II)V", Self::fill_polygon, Default::default()),
                 JavaMethodProto::new("drawString", "(Ljava/lang/String;III)V", Self::draw_string, Default::default()),
                 JavaMethodProto::new("drawImage", "(Lorg/kwis/msp/lcdui/Image;III)V", Self::draw_image, Default::default()),
                 JavaMethodProto::new("setClip", "(IIII)V", Self::set_clip, Default::default()),
@@ -75,6 +82,14 @@ impl Graphics {
                 JavaFieldProto::new("w", "I", Default::default()),
                 JavaFieldProto::new("h", "I", Default::default()),
                 JavaFieldProto::new("rgb", "I", Default::default()),
+                JavaFieldProto::new("font", "Lorg/kwis/msp/lcdui/Font;", Default::default()),
+                JavaFieldProto::new("tx", "I", Default::default()),
+                JavaFieldProto::new("ty", "I", Default::default()),
+                // clip is kept untranslated, so later translations don't move it
+                JavaFieldProto::new("clipX", "I", Default::default()),
+                JavaFieldProto::new("clipY", "I", Default::default()),
+                JavaFieldProto::new("clipW", "I", Default::default()),
+                JavaFieldProto::new("clipH", "I", Default::default()),
             ],
         }
     }
@@ -88,6 +103,8 @@ impl Graphics {
 
         jvm.put_field(&mut this, "w", "I", width).await?;
         jvm.put_field(&mut this, "h", "I", height).await?;
+        jvm.put_field(&mut this, "clipW", "I", width).await?;
+        jvm.put_field(&mut this, "clipH", "I", height).await?;
 
         Ok(())
     }
@@ -116,16 +133,16 @@ impl Graphics {
         jvm.put_field(&mut this, "img", "Lorg/kwis/msp/lcdui/Image;", image).await?;
         jvm.put_field(&mut this, "w", "I", width).await?;
         jvm.put_field(&mut this, "h", "I", height).await?;
+        jvm.put_field(&mut this, "clipW", "I", width).await?;
+        jvm.put_field(&mut this, "clipH", "I", height).await?;
 
         Ok(())
     }
 
-    async fn get_font(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>) -> JvmResult<ClassInstanceRef<Font>> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::getFont({:?})", &this);
+    async fn get_font(jvm: &Jvm, _: &mut WIPIJavaContext, mut this: ClassInstanceRef<Self>) -> JvmResult<ClassInstanceRef<Font>> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::getFont({:?})", &this);
 
-        let instance = jvm.new_class("org/kwis/msp/lcdui/Font", "()V", []).await?;
-
-        Ok(instance.into())
+        Self::font(jvm, &mut this).await
     }
 
     async fn set_color(jvm: &Jvm, _: &mut WIPIJavaContext, mut this: ClassInstanceRef<Self>, rgb: i32) -> JvmResult<()> {
@@ -146,8 +163,10 @@ impl Graphics {
         Ok(())
     }
 
-    async fn set_font(_jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>, font: ClassInstanceRef<Font>) -> JvmResult<()> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::setFont({:?}, {:?})", &this, &font);
+    async fn set_font(jvm: &Jvm, _: &mut WIPIJavaContext, mut this: ClassInstanceRef<Self>, font: ClassInstanceRef<Font>) -> JvmResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::setFont({:?}, {:?})", &this, &font);
+
+        jvm.put_field(&mut this, "font", "Lorg/kwis/msp/lcdui/Font;", font).await?;
 
         Ok(())
     }
@@ -158,30 +177,41 @@ impl Graphics {
         Ok(())
     }
 
-    async fn set_clip(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>, x: i32, y: i32, width: i32, height: i32) -> JvmResult<()> {
-        tracing::warn!(
-            "stub org.kwis.msp.lcdui.Graphics::setClip({:?}, {}, {}, {}, {})",
-            &this,
-            x,
-            y,
-            width,
-            height
-        );
+    async fn set_clip(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::setClip({:?}, {}, {}, {}, {})", &this, x, y, width, height);
 
-        Ok(())
+        let tx: i32 = jvm.get_field(&this, "tx", "I").await?;
+        let ty: i32 = jvm.get_field(&this, "ty", "I").await?;
+
+        Self::put_clip(jvm, &mut this, Rect::new(x, y, width.max(0), height.max(0)).offset(tx, ty)).await
     }
 
-    async fn clip_rect(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>, x: i32, y: i32, width: i32, height: i32) -> JvmResult<()> {
-        tracing::warn!(
-            "stub org.kwis.msp.lcdui.Graphics::clipRect({:?}, {}, {}, {}, {})",
-            &this,
-            x,
-            y,
-            width,
-            height
-        );
+    async fn clip_rect(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::clipRect({:?}, {}, {}, {}, {})", &this, x, y, width, height);
 
-        Ok(())
+        let tx: i32 = jvm.get_field(&this, "tx", "I").await?;
+        let ty: i32 = jvm.get_field(&this, "ty", "I").await?;
+
+        let clip = Self::get_clip(jvm, &this).await?;
+        let clip = clip.intersect(&Rect::new(x, y, width.max(0), height.max(0)).offset(tx, ty));
+
+        Self::put_clip(jvm, &mut this, clip).await
     }
 
     async fn fill_rect(
@@ -199,8 +229,9 @@ impl Graphics {
 
         let image = Self::image(jvm, &mut this).await?;
         let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
 
-        canvas.fill_rect(x as _, y as _, width as _, height as _, Rgb8Pixel::to_color(rgb as _));
+        canvas.fill_rect(x, y, width, height, Rgb8Pixel::to_color(rgb as _));
 
         canvas.flush().await;
 
@@ -222,8 +253,9 @@ impl Graphics {
 
         let image = Self::image(jvm, &mut this).await?;
         let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
 
-        canvas.draw_rect(x as _, y as _, width as _, height as _, Rgb8Pixel::to_color(rgb as _));
+        canvas.draw_rect(x, y, width, height, Rgb8Pixel::to_color(rgb as _));
 
         canvas.flush().await;
 
@@ -249,9 +281,13 @@ impl Graphics {
         );
 
         let rust_string = JavaLangString::to_rust_string(jvm, &string).await?;
+        let rgb: i32 = jvm.get_field(&this, "rgb", "I").await?;
+        let font = Self::font(jvm, &mut this).await?;
+        let font = Font::font(jvm, &font).await?;
 
         let image = Self::image(jvm, &mut this).await?;
         let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
 
         let alignment = if anchor.contains(Anchor::HCENTER) {
             TextAlignment::Center
@@ -261,7 +297,17 @@ impl Graphics {
             TextAlignment::Left
         };
 
-        canvas.draw_text(&rust_string, x as _, y as _, alignment);
+        let y = if anchor.contains(Anchor::BASELINE) {
+            y - font.baseline()
+        } else if anchor.contains(Anchor::BOTTOM) {
+            y - font.height()
+        } else if anchor.contains(Anchor::VCENTER) {
+            y - font.height() / 2
+        } else {
+            y
+        };
+
+        canvas.draw_text(&rust_string, x, y, &font, Rgb8Pixel::to_color(rgb as _), alignment);
 
         canvas.flush().await;
 
@@ -275,8 +321,249 @@ impl Graphics {
 
         let image = Self::image(jvm, &mut this).await?;
         let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
+
+        canvas.draw_line(x1, y1, x2, y2, Rgb8Pixel::to_color(rgb as _));
+
+        canvas.flush().await;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn draw_arc(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        start_angle: i32,
+        arc_angle: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!(
+            "org.kwis.msp.lcdui.Graphics::drawArc({:?}, {}, {}, {}, {}, {}, {})",
+            &this,
+            x,
+            y,
+            width,
+            height,
+            start_angle,
+            arc_angle
+        );
+
+        let rgb: i32 = jvm.get_field(&this, "rgb", "I").await?;
+
+        let image = Self::image(jvm, &mut this).await?;
+        let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
+
+        canvas.draw_arc(x, y, width, height, start_angle, arc_angle, Rgb8Pixel::to_color(rgb as _));
+
+        canvas.flush().await;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn fill_arc(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        start_angle: i32,
+        arc_angle: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!(
+            "org.kwis.msp.lcdui.Graphics::fillArc({:?}, {}, {}, {}, {}, {}, {})",
+            &this,
+            x,
+            y,
+            width,
+            height,
+            start_angle,
+            arc_angle
+        );
+
+        let rgb: i32 = jvm.get_field(&this, "rgb", "I").await?;
+
+        let image = Self::image(jvm, &mut this).await?;
+        let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
+
+        canvas.fill_arc(x, y, width, height, start_angle, arc_angle, Rgb8Pixel::to_color(rgb as _));
+
+        canvas.flush().await;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn draw_round_rect(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        arc_width: i32,
+        arc_height: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!(
+            "org.kwis.msp.lcdui.Graphics::drawRoundRect({:?}, {}, {}, {}, {}, {}, {})",
+            &this,
+            x,
+            y,
+            width,
+            height,
+            arc_width,
+            arc_height
+        );
+
+        let rgb: i32 = jvm.get_field(&this, "rgb", "I").await?;
+
+        let image = Self::image(jvm, &mut this).await?;
+        let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
 
-        canvas.draw_line(x1 as _, y1 as _, x2 as _, y2 as _, Rgb8Pixel::to_color(rgb as _));
+        canvas.draw_round_rect(x, y, width, height, arc_width, arc_height, Rgb8Pixel::to_color(rgb as _));
+
+        canvas.flush().await;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn fill_round_rect(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        arc_width: i32,
+        arc_height: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!(
+            "org.kwis.msp.lcdui.Graphics::fillRoundRect({:?}, {}, {}, {}, {}, {}, {})",
+            &this,
+            x,
+            y,
+            width,
+            height,
+            arc_width,
+            arc_height
+        );
+
+        let rgb: i32 = jvm.get_field(&this, "rgb", "I").await?;
+
+        let image = Self::image(jvm, &mut this).await?;
+        let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
+
+        canvas.fill_round_rect(x, y, width, height, arc_width, arc_height, Rgb8Pixel::to_color(rgb as _));
+
+        canvas.flush().await;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn fill_triangle(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        x3: i32,
+        y3: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!(
+            "org.kwis.msp.lcdui.Graphics::fillTriangle({:?}, {}, {}, {}, {}, {}, {})",
+            &this,
+            x1,
+            y1,
+            x2,
+            y2,
+            x3,
+            y3
+        );
+
+        let rgb: i32 = jvm.get_field(&this, "rgb", "I").await?;
+
+        let image = Self::image(jvm, &mut this).await?;
+        let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
+
+        canvas.fill_triangle(x1, y1, x2, y2, x3, y3, Rgb8Pixel::to_color(rgb as _));
+
+        canvas.flush().await;
+
+        Ok(())
+    }
+
+    async fn draw_polygon(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x_points: ClassInstanceRef<Array<i32>>,
+        y_points: ClassInstanceRef<Array<i32>>,
+        count: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!(
+            "org.kwis.msp.lcdui.Graphics::drawPolygon({:?}, {:?}, {:?}, {})",
+            &this,
+            &x_points,
+            &y_points,
+            count
+        );
+
+        let rgb: i32 = jvm.get_field(&this, "rgb", "I").await?;
+        let points = Self::points(jvm, &x_points, &y_points, count).await?;
+
+        let image = Self::image(jvm, &mut this).await?;
+        let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
+
+        canvas.draw_polygon(&points, Rgb8Pixel::to_color(rgb as _));
+
+        canvas.flush().await;
+
+        Ok(())
+    }
+
+    async fn fill_polygon(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        x_points: ClassInstanceRef<Array<i32>>,
+        y_points: ClassInstanceRef<Array<i32>>,
+        count: i32,
+    ) -> JvmResult<()> {
+        tracing::debug!(
+            "org.kwis.msp.lcdui.Graphics::fillPolygon({:?}, {:?}, {:?}, {})",
+            &this,
+            &x_points,
+            &y_points,
+            count
+        );
+
+        let rgb: i32 = jvm.get_field(&this, "rgb", "I").await?;
+        let points = Self::points(jvm, &x_points, &y_points, count).await?;
+
+        let image = Self::image(jvm, &mut this).await?;
+        let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
+
+        canvas.fill_polygon(&points, Rgb8Pixel::to_color(rgb as _));
 
         canvas.flush().await;
 
@@ -305,75 +592,75 @@ impl Graphics {
 
         let image = Self::image(jvm, &mut this).await?;
         let mut canvas = Image::canvas(jvm, &image).await?;
-
-        let x_delta = if anchor.contains(Anchor::HCENTER) {
-            -((src_image.width() / 2) as i32)
-        } else if anchor.contains(Anchor::RIGHT) {
-            -(src_image.width() as i32)
-        } else {
-            0
-        };
-
-        let y_delta = if anchor.contains(Anchor::VCENTER) {
-            -((src_image.height() / 2) as i32)
-        } else if anchor.contains(Anchor::BOTTOM) {
-            -(src_image.height() as i32)
-        } else {
-            0
-        };
-
-        let x = (x + x_delta).max(0);
-        let y = (y + y_delta).max(0);
-
-        canvas.draw(x as _, y as _, src_image.width(), src_image.height(), &*src_image, 0, 0);
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
+
+        canvas.draw_region(
+            &*src_image,
+            0,
+            0,
+            src_image.width() as _,
+            src_image.height() as _,
+            Transform::None,
+            x,
+            y,
+            canvas::Anchor::from_bits(anchor.bits() as _),
+        );
 
         canvas.flush().await;
 
         Ok(())
     }
 
-    async fn get_clip_x(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>) -> JvmResult<i32> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::getClipX({:?})", &this);
+    async fn get_clip_x(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::getClipX({:?})", &this);
+
+        let clip_x: i32 = jvm.get_field(&this, "clipX", "I").await?;
+        let tx: i32 = jvm.get_field(&this, "tx", "I").await?;
 
-        Ok(0)
+        Ok(clip_x.saturating_sub(tx))
     }
 
-    async fn get_clip_y(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>) -> JvmResult<i32> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::getClipY({:?})", &this);
+    async fn get_clip_y(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::getClipY({:?})", &this);
 
-        Ok(0)
+        let clip_y: i32 = jvm.get_field(&this, "clipY", "I").await?;
+        let ty: i32 = jvm.get_field(&this, "ty", "I").await?;
+
+        Ok(clip_y.saturating_sub(ty))
     }
 
     async fn get_clip_width(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::getClipWidth({:?})", &this);
-
-        let w: i32 = jvm.get_field(&this, "w", "I").await?;
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::getClipWidth({:?})", &this);
 
-        Ok(w)
+        jvm.get_field(&this, "clipW", "I").await
     }
 
     async fn get_clip_height(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::getClipHeight({:?})", &this);
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::getClipHeight({:?})", &this);
 
-        let h: i32 = jvm.get_field(&this, "h", "I").await?;
-
-        Ok(h)
+        jvm.get_field(&this, "clipH", "I").await
     }
 
-    async fn get_translate_x(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>) -> JvmResult<i32> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::getTranslateX({:?})", &this);
+    async fn get_translate_x(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::getTranslateX({:?})", &this);
 
-        Ok(0)
+        jvm.get_field(&this, "tx", "I").await
     }
 
-    async fn get_translate_y(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>) -> JvmResult<i32> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::getTranslateY({:?})", &this);
+    async fn get_translate_y(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::getTranslateY({:?})", &this);
 
-        Ok(0)
+        jvm.get_field(&this, "ty", "I").await
     }
 
-    async fn translate(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Graphics>, x: i32, y: i32) -> JvmResult<()> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Graphics::translate({:?}, {}, {})", &this, x, y);
+    async fn translate(jvm: &Jvm, _: &mut WIPIJavaContext, mut this: ClassInstanceRef<Self>, x: i32, y: i32) -> JvmResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Graphics::translate({:?}, {}, {})", &this, x, y);
+
+        let tx: i32 = jvm.get_field(&this, "tx", "I").await?;
+        let ty: i32 = jvm.get_field(&this, "ty", "I").await?;
+
+        jvm.put_field(&mut this, "tx", "I", tx + x).await?;
+        jvm.put_field(&mut this, "ty", "I", ty + y).await?;
 
         Ok(())
     }
@@ -409,14 +696,71 @@ impl Graphics {
 
         let image = Self::image(jvm, &mut this).await?;
         let mut canvas = Image::canvas(jvm, &image).await?;
+        Self::apply_state(jvm, &this, &mut **canvas).await?;
 
-        canvas.draw(x as _, y as _, width as _, height as _, &src_image, 0, 0);
+        canvas.draw(x, y, width, height, &src_image, 0, 0);
 
         canvas.flush().await;
 
         Ok(())
     }
 
+    async fn points(
+        jvm: &Jvm,
+        x_points: &ClassInstanceRef<Array<i32>>,
+        y_points: &ClassInstanceRef<Array<i32>>,
+        count: i32,
+    ) -> JvmResult<Vec<(i32, i32)>> {
+        let x_points: Vec<i32> = jvm.load_array(x_points, 0, count.max(0) as _).await?;
+        let y_points: Vec<i32> = jvm.load_array(y_points, 0, count.max(0) as _).await?;
+
+        Ok(x_points.into_iter().zip(y_points).collect())
+    }
+
+    async fn get_clip(jvm: &Jvm, this: &ClassInstanceRef<Self>) -> JvmResult<Rect> {
+        Ok(Rect::new(
+            jvm.get_field(this, "clipX", "I").await?,
+            jvm.get_field(this, "clipY", "I").await?,
+            jvm.get_field(this, "clipW", "I").await?,
+            jvm.get_field(this, "clipH", "I").await?,
+        ))
+    }
+
+    async fn put_clip(jvm: &Jvm, this: &mut ClassInstanceRef<Self>, clip: Rect) -> JvmResult<()> {
+        jvm.put_field(this, "clipX", "I", clip.x).await?;
+        jvm.put_field(this, "clipY", "I", clip.y).await?;
+        jvm.put_field(this, "clipW", "I", clip.width).await?;
+        jvm.put_field(this, "clipH", "I", clip.height).await?;
+
+        Ok(())
+    }
+
+    // canvases are created per call, so the translation and clip have to be applied each time
+    async fn apply_state(jvm: &Jvm, this: &ClassInstanceRef<Self>, canvas: &mut dyn Canvas) -> JvmResult<()> {
+        let clip = Self::get_clip(jvm, this).await?;
+
+        canvas.set_clip(clip.x, clip.y, clip.width, clip.height);
+        canvas.translate(jvm.get_field(this, "tx", "I").await?, jvm.get_field(this, "ty", "I").await?);
+
+        Ok(())
+    }
+
+    async fn font(jvm: &Jvm, this: &mut ClassInstanceRef<Self>) -> JvmResult<ClassInstanceRef<Font>> {
+        let font: ClassInstanceRef<Font> = jvm.get_field(this, "font", "Lorg/kwis/msp/lcdui/Font;").await?;
+
+        if !font.is_null() {
+            Ok(font)
+        } else {
+            let font: ClassInstanceRef<Font> = jvm
+                .invoke_static("org/kwis/msp/lcdui/Font", "getDefaultFont", "()Lorg/kwis/msp/lcdui/Font;", [])
+                .await?;
+
+            jvm.put_field(this, "font", "Lorg/kwis/msp/lcdui/Font;", font.clone()).await?;
+
+            Ok(font)
+        }
+    }
+
     async fn image(jvm: &Jvm, this: &mut ClassInstanceRef<Graphics>) -> JvmResult<ClassInstanceRef<Image>> {
         let image: ClassInstanceRef<Image> = jvm.get_field(this, "img", "Lorg/kwis/msp/lcdui/Image;").await?;
 
@@ -490,4 +834,48 @@ mod test {
 
         Ok(())
     }
+
+    #[futures_test::test]
+    async fn test_graphics_clip_translate() -> JvmResult<()> {
+        let jvm = test_jvm().await?;
+
+        register(&jvm, |name, proto| {
+            ready(Box::new(ClassDefinitionImpl::from_class_proto(name, proto, Box::new(DummyContext) as Box<_>)) as Box<_>)
+        })
+        .await?;
+
+        let image: ClassInstanceRef<Image> = jvm
+            .invoke_static("org/kwis/msp/lcdui/Image", "createImage", "(II)Lorg/kwis/msp/lcdui/Image;", (10, 10))
+            .await?;
+
+        let graphics = jvm
+            .new_class(
+                "org/kwis/msp/lcdui/Graphics",
+                "(Lorg/kwis/msp/lcdui/Image;IIII)V",
+                (image.clone(), 0, 0, 10, 10),
+            )
+            .await?;
+
+        jvm.invoke_virtual(&graphics, "translate", "(II)V", (2, 2)).await?;
+        jvm.invoke_virtual(&graphics, "setClip", "(IIII)V", (0, 0, 4, 4)).await?;
+        jvm.invoke_virtual(&graphics, "clipRect", "(IIII)V", (-10, -10, 12, 12)).await?;
+
+        let clip_x: i32 = jvm.invoke_virtual(&graphics, "getClipX", "()I", ()).await?;
+        let clip_width: i32 = jvm.invoke_virtual(&graphics, "getClipWidth", "()I", ()).await?;
+        let translate_x: i32 = jvm.invoke_virtual(&graphics, "getTranslateX", "()I", ()).await?;
+        assert_eq!((clip_x, clip_width, translate_x), (0, 2, 2));
+
+        jvm.invoke_virtual(&graphics, "setColor", "(I)V", (0x00ff00,)).await?;
+        jvm.invoke_virtual(&graphics, "fillRect", "(IIII)V", (-100, -100, 200, 200)).await?;
+
+        let image = Image::image(&jvm, &image).await?;
+
+        // only (2, 2) to (3, 3) is inside the clip
+        assert_eq!(image.get_pixel(2, 2).g, 255);
+        assert_eq!(image.get_pixel(3, 3).g, 255);
+        assert_eq!(image.get_pixel(1, 1).g, 0);
+        assert_eq!(image.get_pixel(4, 4).g, 0);
+
+        Ok(())
+    }
 }
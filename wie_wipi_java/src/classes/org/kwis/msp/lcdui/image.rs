@@ -11,11 +11,11 @@ use java_constants::MethodAccessFlags;
 use java_runtime::classes::java::lang::String;
 use jvm::{runtime::JavaLangString, Array, ClassInstanceRef, Jvm, Result as JvmResult};
 
-use wie_backend::canvas::{decode_image, ArgbPixel, Canvas, Image as BackendImage, ImageBufferCanvas, Rgb565Pixel, VecImageBuffer};
+use wie_backend::canvas::{decode_image_frames, ArgbPixel, Canvas, Image as BackendImage, ImageBufferCanvas, Rgb565Pixel, VecImageBuffer};
 
 use crate::{
     classes::org::kwis::msp::lcdui::Graphics,
-    context::{WIPIJavaClassProto, WIPIJavaContext},
+    context::{java_exception, WIPIJavaClassProto, WIPIJavaContext},
 };
 
 // class org.kwis.msp.lcdui.Image
@@ -49,12 +49,21 @@ impl Image {
                 JavaMethodProto::new("getGraphics", "()Lorg/kwis/msp/lcdui/Graphics;", Self::get_graphics, Default::default()),
                 JavaMethodProto::new("getWidth", "()I", Self::get_width, Default::default()),
                 JavaMethodProto::new("getHeight", "()I", Self::get_height, Default::default()),
+                JavaMethodProto::new("getFrameCount", "()I", Self::get_frame_count, Default::default()),
+                JavaMethodProto::new("getFrameDelay", "()I", Self::get_frame_delay, Default::default()),
+                JavaMethodProto::new("nextFrame", "()V", Self::next_frame, Default::default()),
             ],
             fields: vec![
                 JavaFieldProto::new("w", "I", Default::default()),
                 JavaFieldProto::new("h", "I", Default::default()),
                 JavaFieldProto::new("imgData", "[B", Default::default()),
                 JavaFieldProto::new("bpl", "I", Default::default()),
+                JavaFieldProto::new("frame", "I", Default::default()),
+                JavaFieldProto::new("frameCount", "I", Default::default()),
+                JavaFieldProto::new("delay", "I", Default::default()),
+                // pixels of every frame back to back, kept only for animated images
+                JavaFieldProto::new("frames", "[B", Default::default()),
+                JavaFieldProto::new("delays", "[I", Default::default()),
             ],
         }
     }
@@ -89,14 +98,9 @@ impl Image {
         let id = context.system().resource().id(normalized_name).unwrap();
         let system_clone = context.system().clone();
 
-        let image = {
-            let image_data = Ref::map(system_clone.resource(), |x| x.data(id));
+        let image_data = Ref::map(system_clone.resource(), |x| x.data(id)).to_vec();
 
-            decode_image(&image_data)
-        }
-        .unwrap();
-
-        Self::create_image_instance(jvm, image.width(), image.height(), image.raw(), image.bytes_per_pixel()).await
+        Self::create_image_from_data(jvm, image_data).await
     }
 
     async fn create_image_from_bytes(
@@ -109,9 +113,8 @@ impl Image {
         tracing::debug!("org.kwis.msp.lcdui.Image::createImage({:?}, {}, {})", &data, offset, length);
 
         let image_data = jvm.load_byte_array(&data, offset as _, length as _).await?;
-        let image = decode_image(&cast_vec(image_data)).unwrap();
 
-        Self::create_image_instance(jvm, image.width(), image.height(), image.raw(), image.bytes_per_pixel()).await
+        Self::create_image_from_data(jvm, cast_vec(image_data)).await
     }
 
     async fn get_graphics(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<ClassInstanceRef<Graphics>> {
@@ -143,6 +146,46 @@ impl Image {
         jvm.get_field(&this, "h", "I").await
     }
 
+    async fn get_frame_count(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Image::getFrameCount({:?})", &this);
+
+        let frame_count: i32 = jvm.get_field(&this, "frameCount", "I").await?;
+
+        Ok(frame_count.max(1))
+    }
+
+    async fn get_frame_delay(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JvmResult<i32> {
+        tracing::debug!("org.kwis.msp.lcdui.Image::getFrameDelay({:?})", &this);
+
+        jvm.get_field(&this, "delay", "I").await
+    }
+
+    async fn next_frame(jvm: &Jvm, _: &mut WIPIJavaContext, mut this: ClassInstanceRef<Self>) -> JvmResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Image::nextFrame({:?})", &this);
+
+        let frames: ClassInstanceRef<Array<i8>> = jvm.get_field(&this, "frames", "[B").await?;
+        if frames.is_null() {
+            return Ok(());
+        }
+
+        let frame_count: i32 = jvm.get_field(&this, "frameCount", "I").await?;
+        let frame: i32 = jvm.get_field(&this, "frame", "I").await?;
+        let next = (frame + 1) % frame_count;
+
+        let mut data = jvm.get_field(&this, "imgData", "[B").await?;
+        let frame_size = jvm.array_length(&data).await?;
+        let pixels = jvm.load_byte_array(&frames, next as usize * frame_size, frame_size).await?;
+        jvm.store_byte_array(&mut data, 0, pixels).await?;
+
+        let delays: ClassInstanceRef<Array<i32>> = jvm.get_field(&this, "delays", "[I").await?;
+        let delay: Vec<i32> = jvm.load_array(&delays, next as _, 1).await?;
+
+        jvm.put_field(&mut this, "frame", "I", next).await?;
+        jvm.put_field(&mut this, "delay", "I", delay[0]).await?;
+
+        Ok(())
+    }
+
     pub async fn buf(jvm: &Jvm, this: &ClassInstanceRef<Self>) -> JvmResult<Vec<u8>> {
         let java_img_data = jvm.get_field(this, "imgData", "[B").await?;
         let img_data_len = jvm.array_length(&java_img_data).await?;
@@ -180,6 +223,42 @@ impl Image {
         Ok(ImageCanvas::new(jvm, this, width as _, height as _, bytes_per_pixel as _, buf))
     }
 
+    async fn create_image_from_data(jvm: &Jvm, data: Vec<u8>) -> JvmResult<ClassInstanceRef<Image>> {
+        let frames = match decode_image_frames(&data) {
+            Ok(x) => x,
+            Err(x) => return Err(java_exception(jvm, "java/lang/IllegalArgumentException", x).await),
+        };
+        let first = &frames[0];
+
+        let mut instance = Self::create_image_instance(
+            jvm,
+            first.image.width(),
+            first.image.height(),
+            first.image.raw(),
+            first.image.bytes_per_pixel(),
+        )
+        .await?;
+
+        jvm.put_field(&mut instance, "frameCount", "I", frames.len() as i32).await?;
+        jvm.put_field(&mut instance, "delay", "I", first.delay as i32).await?;
+
+        // decoded once, nextFrame only copies the pixels
+        if frames.len() > 1 {
+            let pixels = frames.iter().flat_map(|x| x.image.raw().iter().copied()).collect::<Vec<_>>();
+            let mut frames_array = jvm.instantiate_array("B", pixels.len() as _).await?;
+            jvm.store_byte_array(&mut frames_array, 0, cast_vec(pixels)).await?;
+
+            let delays = frames.iter().map(|x| x.delay as i32).collect::<Vec<_>>();
+            let mut delays_array = jvm.instantiate_array("I", delays.len() as _).await?;
+            jvm.store_array(&mut delays_array, 0, delays).await?;
+
+            jvm.put_field(&mut instance, "frames", "[B", frames_array).await?;
+            jvm.put_field(&mut instance, "delays", "[I", delays_array).await?;
+        }
+
+        Ok(instance)
+    }
+
     async fn create_image_instance(jvm: &Jvm, width: u32, height: u32, data: &[u8], bytes_per_pixel: u32) -> JvmResult<ClassInstanceRef<Image>> {
         let mut instance = jvm.new_class("org/kwis/msp/lcdui/Image", "()V", []).await?;
 
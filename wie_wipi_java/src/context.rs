@@ -1,9 +1,10 @@
-use alloc::boxed::Box;
+use alloc::{boxed::Box, string::ToString};
+use core::fmt::Display;
 
 use dyn_clone::{clone_trait_object, DynClone};
 
 use java_class_proto::{JavaClassProto, MethodBody};
-use jvm::{JavaError, Result as JvmResult};
+use jvm::{runtime::JavaLangString, JavaError, Jvm, Result as JvmResult};
 
 use wie_backend::System;
 
@@ -17,6 +18,19 @@ clone_trait_object!(WIPIJavaContextBase);
 pub(crate) type WIPIJavaClassProto = JavaClassProto<dyn WIPIJavaContextBase>;
 pub(crate) type WIPIJavaContext = dyn WIPIJavaContextBase;
 
+// host errors are thrown to the app as `class_name`, which needs a (Ljava/lang/String;)V constructor
+pub(crate) async fn java_exception(jvm: &Jvm, class_name: &str, error: impl Display) -> JavaError {
+    let message = match JavaLangString::from_rust_string(jvm, &error.to_string()).await {
+        Ok(x) => x,
+        Err(x) => return x,
+    };
+
+    match jvm.new_class(class_name, "(Ljava/lang/String;)V", (message,)).await {
+        Ok(x) => JavaError::JavaException(x),
+        Err(x) => x,
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use alloc::boxed::Box;